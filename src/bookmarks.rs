@@ -0,0 +1,263 @@
+//! Bookmarked documents, kept in a JSON file under `$XDG_DATA_HOME`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    store::{self, Error},
+    xdg,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// The document, without a fragment.
+    pub uri: String,
+    pub title: String,
+    /// The `id` of a heading in the document.
+    #[serde(default)]
+    pub anchor: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The folder the bookmark is filed in, if any.
+    #[serde(default)]
+    pub folder: Option<String>,
+}
+
+impl Bookmark {
+    /// A bookmark for `target`, which may point at a heading with a `#fragment`.
+    pub fn new(target: &str, title: &str) -> Self {
+        let (uri, anchor) = match target.split_once('#') {
+            Some((uri, anchor)) if !anchor.is_empty() => (uri, Some(anchor.to_string())),
+            Some((uri, _)) => (uri, None),
+            None => (target, None),
+        };
+
+        Bookmark {
+            uri: uri.to_string(),
+            title: title.to_string(),
+            anchor,
+            tags: Vec::new(),
+            folder: None,
+        }
+    }
+
+    /// The URI to open, including the anchor.
+    pub fn target(&self) -> String {
+        match &self.anchor {
+            Some(anchor) => format!("{}#{}", self.uri, anchor),
+            None => self.uri.clone(),
+        }
+    }
+}
+
+/// The bookmarks in one folder, or the ones outside any folder.
+#[derive(Debug, Clone)]
+pub struct Folder<'a> {
+    pub name: Option<&'a str>,
+    pub bookmarks: Vec<&'a Bookmark>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    fn path() -> PathBuf {
+        xdg::app_data_dir().join("bookmarks.json")
+    }
+
+    /// Reads the saved bookmarks; there are none if the file doesn't exist yet.
+    pub fn load() -> Result<Bookmarks, Error> {
+        store::load_json(&Self::path())
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        store::save_json(&Self::path(), self)
+    }
+
+    pub fn get(&self, target: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|b| b.target() == target)
+    }
+
+    fn get_mut(&mut self, target: &str) -> Option<&mut Bookmark> {
+        self.bookmarks.iter_mut().find(|b| b.target() == target)
+    }
+
+    /// The targets of every bookmark, for checking whether a page is bookmarked.
+    pub fn targets(&self) -> HashSet<String> {
+        self.bookmarks.iter().map(Bookmark::target).collect()
+    }
+
+    /// Bookmarks `target`, or removes the bookmark if it already exists.
+    ///
+    /// Returns whether the target is now bookmarked.
+    pub fn toggle(&mut self, target: &str, title: &str) -> bool {
+        if self.remove(target) {
+            return false;
+        }
+
+        self.bookmarks.push(Bookmark::new(target, title));
+        true
+    }
+
+    /// Returns whether there was a bookmark to remove.
+    pub fn remove(&mut self, target: &str) -> bool {
+        let len = self.bookmarks.len();
+        self.bookmarks.retain(|b| b.target() != target);
+        self.bookmarks.len() != len
+    }
+
+    /// Changes the title, folder and tags of the bookmark for `target`.
+    ///
+    /// Blank folder names and tags are dropped, and so are repeated tags.
+    pub fn edit(&mut self, target: &str, title: &str, folder: &str, tags: &[String]) {
+        let Some(bookmark) = self.get_mut(target) else {
+            return;
+        };
+
+        if !title.trim().is_empty() {
+            bookmark.title = title.trim().to_string();
+        }
+
+        bookmark.folder = Some(folder.trim().to_string()).filter(|f| !f.is_empty());
+
+        let mut seen = HashSet::new();
+        bookmark.tags = tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty() && seen.insert(*tag))
+            .map(str::to_string)
+            .collect();
+    }
+
+    /// The bookmarks grouped by folder, folders by name, with unfiled bookmarks last.
+    ///
+    /// Bookmarks keep the order they were added in.
+    pub fn folders(&self) -> Vec<Folder<'_>> {
+        let mut folders: BTreeMap<&str, Vec<&Bookmark>> = BTreeMap::new();
+        let mut unfiled = Vec::new();
+
+        for bookmark in &self.bookmarks {
+            match &bookmark.folder {
+                Some(folder) => folders.entry(folder).or_default().push(bookmark),
+                None => unfiled.push(bookmark),
+            }
+        }
+
+        let mut folders: Vec<Folder> = folders
+            .into_iter()
+            .map(|(name, bookmarks)| Folder {
+                name: Some(name),
+                bookmarks,
+            })
+            .collect();
+
+        if !unfiled.is_empty() {
+            folders.push(Folder {
+                name: None,
+                bookmarks: unfiled,
+            });
+        }
+
+        folders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmarks(targets: &[(&str, Option<&str>)]) -> Bookmarks {
+        let mut bookmarks = Bookmarks::default();
+        for (target, folder) in targets {
+            bookmarks.toggle(target, target);
+            bookmarks.edit(target, "", folder.unwrap_or_default(), &[]);
+        }
+        bookmarks
+    }
+
+    #[test]
+    fn anchors() {
+        let bookmark = Bookmark::new("man:ls(1)#options", "ls");
+        assert_eq!(bookmark.uri, "man:ls(1)");
+        assert_eq!(bookmark.anchor.as_deref(), Some("options"));
+        assert_eq!(bookmark.target(), "man:ls(1)#options");
+
+        let bookmark = Bookmark::new("man:ls(1)#", "ls");
+        assert_eq!(bookmark.uri, "man:ls(1)");
+        assert_eq!(bookmark.anchor, None);
+        assert_eq!(bookmark.target(), "man:ls(1)");
+
+        let bookmark = Bookmark::new("info:(find)Top", "find");
+        assert_eq!(
+            (bookmark.uri.as_str(), bookmark.anchor),
+            ("info:(find)Top", None)
+        );
+    }
+
+    #[test]
+    fn toggling() {
+        let mut bookmarks = Bookmarks::default();
+
+        assert!(bookmarks.toggle("man:ls(1)#options", "ls"));
+        assert!(bookmarks.get("man:ls(1)#options").is_some());
+        assert!(bookmarks.get("man:ls(1)").is_none());
+
+        assert!(!bookmarks.toggle("man:ls(1)#options", "ls"));
+        assert!(bookmarks.targets().is_empty());
+    }
+
+    #[test]
+    fn editing() {
+        let mut bookmarks = bookmarks(&[("man:ls(1)", None)]);
+        let tags = ["b", " a ", "", "b", "c", "a"].map(String::from);
+
+        bookmarks.edit("man:ls(1)", "  ", " Shell ", &tags);
+        let bookmark = bookmarks.get("man:ls(1)").unwrap();
+
+        assert_eq!(bookmark.title, "man:ls(1)");
+        assert_eq!(bookmark.folder.as_deref(), Some("Shell"));
+        assert_eq!(bookmark.tags, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn folders() {
+        let bookmarks = bookmarks(&[
+            ("man:ls(1)", Some("Shell")),
+            ("info:find", None),
+            ("man:cp(1)", Some("Files")),
+            ("man:sh(1)", Some("Shell")),
+            ("man:mv(1)", None),
+        ]);
+
+        let folders: Vec<(Option<&str>, Vec<&str>)> = bookmarks
+            .folders()
+            .into_iter()
+            .map(|folder| {
+                let uris = folder.bookmarks.iter().map(|b| b.uri.as_str()).collect();
+                (folder.name, uris)
+            })
+            .collect();
+
+        assert_eq!(
+            folders,
+            [
+                (Some("Files"), vec!["man:cp(1)"]),
+                (Some("Shell"), vec!["man:ls(1)", "man:sh(1)"]),
+                (None, vec!["info:find", "man:mv(1)"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_unfiled_folder_without_unfiled_bookmarks() {
+        let bookmarks = bookmarks(&[("man:ls(1)", Some("Shell"))]);
+        let names: Vec<_> = bookmarks.folders().iter().map(|f| f.name).collect();
+        assert_eq!(names, [Some("Shell")]);
+    }
+}
@@ -0,0 +1,168 @@
+//! Command-line arguments, which name documents the way man(1) and info(1) do.
+
+use std::path::{Path, PathBuf};
+
+use crate::{decompress, html, info::NodeRef, man};
+
+/// URI schemes that arguments can be given in directly.
+const SCHEMES: &[&str] = &["man", "info", "file", "http", "https", "docviewer"];
+
+/// Whether an argument is a section for the page names after it, as in `man 3 printf`.
+///
+/// Only digits and the single-letter sections count, so a page like `ls` is not mistaken for one.
+fn is_section(arg: &str) -> bool {
+    man::looks_like_section(arg)
+        && (arg.starts_with(|c: char| c.is_ascii_digit()) || arg.len() == 1)
+}
+
+fn has_scheme(arg: &str) -> bool {
+    arg.split_once(':')
+        .is_some_and(|(scheme, _)| SCHEMES.contains(&scheme.to_lowercase().as_str()))
+}
+
+/// The URI of a document file: a man page or Info manual if it looks like one, otherwise
+/// whatever WebKit makes of it.
+fn path_uri(path: &Path) -> String {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = decompress::strip_extension(&file_name);
+
+    if name.ends_with(".info") || name.contains(".info-") {
+        return NodeRef::new(&path.to_string_lossy(), "Top").uri();
+    }
+
+    let is_html = name.ends_with(".html") || name.ends_with(".htm");
+    if !is_html && man::split_file_name(&file_name).is_some() {
+        return format!("man:{}", path.display());
+    }
+
+    html::file_uri(path)
+}
+
+/// Turns arguments into the URIs of the documents they name.
+///
+/// Arguments can be URIs, paths (relative to `cwd`), page names like `printf` or `printf.3`,
+/// and sections followed by page names, like `3 printf`. A name that is also a file in `cwd`,
+/// like `README` or `foo.1`, is taken as the file.
+pub fn targets(args: &[String], cwd: &Path) -> Vec<String> {
+    let mut uris = Vec::new();
+    let mut section: Option<&str> = None;
+
+    for (i, arg) in args.iter().enumerate() {
+        let arg = arg.as_str();
+        let is_last = i + 1 == args.len();
+
+        if has_scheme(arg) {
+            uris.push(arg.to_string());
+        } else if arg.contains('/') || arg.ends_with(".html") || cwd.join(arg).is_file() {
+            let path: PathBuf = cwd.join(arg);
+            let path = path.canonicalize().unwrap_or(path);
+            uris.push(path_uri(&path));
+        } else if is_section(arg) && !is_last {
+            section = Some(arg);
+        } else if let Some(section) = section {
+            uris.push(man::ManPageRef::new(arg, Some(section)).uri());
+        } else {
+            // The `man:` scheme tells `printf.3` apart from `python3.12` itself.
+            uris.push(format!("man:{}", arg));
+        }
+    }
+
+    uris
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("docviewer-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn files_in_the_working_directory() {
+        let dir = temp_dir("files");
+        for name in ["README", "index.htm", "coreutils.info.gz", "foo.1"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let uris = targets(
+            &args(&["README", "index.htm", "coreutils.info.gz", "foo.1"]),
+            &dir,
+        );
+
+        assert_eq!(
+            uris,
+            vec![
+                html::file_uri(&dir.join("README")),
+                html::file_uri(&dir.join("index.htm")),
+                NodeRef::new(&dir.join("coreutils.info.gz").to_string_lossy(), "Top").uri(),
+                format!("man:{}", dir.join("foo.1").display()),
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn names_without_files_are_pages() {
+        let dir = temp_dir("pages");
+
+        assert_eq!(targets(&args(&["foo.1"]), &dir), vec!["man:foo.1"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn uris_and_page_names() {
+        let dir = temp_dir("names");
+
+        assert_eq!(
+            targets(
+                &args(&[
+                    "info:coreutils",
+                    "HTTPS://example.com",
+                    "printf",
+                    "printf.3"
+                ]),
+                &dir
+            ),
+            vec![
+                "info:coreutils",
+                "HTTPS://example.com",
+                "man:printf",
+                "man:printf.3"
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sections() {
+        let dir = temp_dir("sections");
+
+        assert_eq!(
+            targets(&args(&["3", "printf", "sprintf"]), &dir),
+            vec!["man:printf(3)", "man:sprintf(3)"]
+        );
+        assert_eq!(
+            targets(&args(&["ls", "n", "tcl"]), &dir),
+            vec!["man:ls", "man:tcl(n)"]
+        );
+        // A section with nothing after it is a page name.
+        assert_eq!(targets(&args(&["ls", "8"]), &dir), vec!["man:ls", "man:8"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
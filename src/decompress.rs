@@ -0,0 +1,261 @@
+//! Reading documentation files that distributions ship compressed.
+//!
+//! Formats are recognised by their magic bytes rather than the file
+//! extension, so a misnamed file is still read correctly. New formats are
+//! added by extending [`CODECS`].
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+type Reader = Box<dyn Read + Send>;
+
+/// A compression format and how to undo it.
+pub struct Codec {
+    pub name: &'static str,
+    /// File name suffixes, including the dot.
+    pub extensions: &'static [&'static str],
+    magic: &'static [u8],
+    decoder: fn(Reader) -> io::Result<Reader>,
+}
+
+pub static CODECS: &[Codec] = &[
+    Codec {
+        name: "gzip",
+        extensions: &[".gz"],
+        magic: &[0x1f, 0x8b],
+        decoder: |r| Ok(Box::new(flate2::read::MultiGzDecoder::new(r))),
+    },
+    Codec {
+        name: "bzip2",
+        extensions: &[".bz2"],
+        magic: b"BZh",
+        decoder: |r| Ok(Box::new(bzip2::read::MultiBzDecoder::new(r))),
+    },
+    Codec {
+        name: "xz",
+        extensions: &[".xz"],
+        magic: &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+        decoder: |r| Ok(Box::new(xz2::read::XzDecoder::new_multi_decoder(r))),
+    },
+    Codec {
+        name: "lzma",
+        extensions: &[".lzma"],
+        magic: &[0x5d, 0x00, 0x00],
+        decoder: |r| {
+            let stream = xz2::stream::Stream::new_lzma_decoder(u64::MAX)?;
+            Ok(Box::new(xz2::read::XzDecoder::new_stream(r, stream)))
+        },
+    },
+    Codec {
+        name: "zstd",
+        extensions: &[".zst"],
+        magic: &[0x28, 0xb5, 0x2f, 0xfd],
+        decoder: |r| Ok(Box::new(zstd::stream::read::Decoder::new(r)?)),
+    },
+];
+
+/// The codec whose extension `file_name` ends with.
+pub fn codec_for_name(file_name: &str) -> Option<&'static Codec> {
+    CODECS
+        .iter()
+        .find(|codec| codec.extensions.iter().any(|ext| file_name.ends_with(ext)))
+}
+
+/// The codec whose magic bytes start `header`.
+pub fn codec_for_header(header: &[u8]) -> Option<&'static Codec> {
+    CODECS.iter().find(|codec| header.starts_with(codec.magic))
+}
+
+/// Removes a compression extension from a file name, if it has one.
+pub fn strip_extension(file_name: &str) -> &str {
+    codec_for_name(file_name)
+        .and_then(|codec| {
+            codec
+                .extensions
+                .iter()
+                .find_map(|ext| file_name.strip_suffix(ext))
+        })
+        .unwrap_or(file_name)
+}
+
+/// `path` itself if it exists, otherwise a compressed variant of it, like `path.gz`.
+pub fn find_variant(path: &Path) -> Option<PathBuf> {
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+
+    CODECS
+        .iter()
+        .flat_map(|codec| codec.extensions)
+        .map(|ext| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(ext);
+            PathBuf::from(name)
+        })
+        .find(|p| p.exists())
+}
+
+/// Opens a file for reading, decompressing it on the fly if needed.
+pub fn open(path: &Path) -> io::Result<Reader> {
+    let mut file = File::open(path)?;
+
+    let mut header = [0; 6];
+    let len = read_header(&mut file, &mut header)?;
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let codec = codec_for_header(&header[..len]).filter(|codec| {
+        // The lzma header is short enough to turn up in plain files, so it needs the extension too.
+        codec.name != "lzma" || codec_for_name(&file_name).is_some_and(|c| c.name == "lzma")
+    });
+
+    let reader: Reader =
+        Box::new(io::Cursor::new(header[..len].to_vec()).chain(BufReader::new(file)));
+
+    match codec {
+        Some(codec) => (codec.decoder)(reader),
+        None => Ok(reader),
+    }
+}
+
+/// Reads a whole file, decompressing it if needed.
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    open(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads a whole file as text, decompressing it if needed and replacing invalid UTF-8.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    read(path).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Fills `buf` as far as the file allows, returning how much was read.
+fn read_header(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use super::*;
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "docviewer-decompress-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(codec_for_name("ls.1.gz").map(|c| c.name), Some("gzip"));
+        assert_eq!(codec_for_name("ls.1.zst").map(|c| c.name), Some("zstd"));
+        assert!(codec_for_name("ls.1").is_none());
+
+        assert_eq!(strip_extension("coreutils.info.bz2"), "coreutils.info");
+        assert_eq!(strip_extension("coreutils.info"), "coreutils.info");
+    }
+
+    #[test]
+    fn headers() {
+        assert_eq!(
+            codec_for_header(&gzip("text")).map(|c| c.name),
+            Some("gzip")
+        );
+        assert_eq!(codec_for_header(b"BZh91AY").map(|c| c.name), Some("bzip2"));
+        assert!(codec_for_header(b".TH LS 1").is_none());
+        assert!(codec_for_header(b"").is_none());
+    }
+
+    #[test]
+    fn magic_bytes_win_over_the_extension() {
+        let dir = temp_dir("magic");
+
+        // Compressed, but named as if it were not.
+        fs::write(dir.join("ls.1"), gzip(".TH LS 1\n")).unwrap();
+        assert_eq!(read_to_string(&dir.join("ls.1")).unwrap(), ".TH LS 1\n");
+
+        // Named as if compressed, but plain.
+        fs::write(dir.join("cat.1.gz"), ".TH CAT 1\n").unwrap();
+        assert_eq!(
+            read_to_string(&dir.join("cat.1.gz")).unwrap(),
+            ".TH CAT 1\n"
+        );
+
+        // Named for the wrong format.
+        fs::write(dir.join("cp.1.xz"), gzip(".TH CP 1\n")).unwrap();
+        assert_eq!(read_to_string(&dir.join("cp.1.xz")).unwrap(), ".TH CP 1\n");
+    }
+
+    #[test]
+    fn lzma_needs_its_extension() {
+        let dir = temp_dir("lzma");
+
+        // Starts like an lzma stream.
+        let plain = [0x5d, 0x00, 0x00, b'x'];
+        fs::write(dir.join("plain"), plain).unwrap();
+        assert_eq!(read(&dir.join("plain")).unwrap(), plain);
+
+        let mut encoder = xz2::write::XzEncoder::new_stream(
+            Vec::new(),
+            xz2::stream::Stream::new_lzma_encoder(
+                &xz2::stream::LzmaOptions::new_preset(6).unwrap(),
+            )
+            .unwrap(),
+        );
+        encoder.write_all(b"compressed").unwrap();
+        fs::write(dir.join("page.lzma"), encoder.finish().unwrap()).unwrap();
+        assert_eq!(read(&dir.join("page.lzma")).unwrap(), b"compressed");
+    }
+
+    #[test]
+    fn short_and_concatenated_files() {
+        let dir = temp_dir("short");
+
+        fs::write(dir.join("a"), "a").unwrap();
+        assert_eq!(read_to_string(&dir.join("a")).unwrap(), "a");
+
+        let mut members = gzip("one ");
+        members.extend(gzip("two"));
+        fs::write(dir.join("b.gz"), members).unwrap();
+        assert_eq!(read_to_string(&dir.join("b.gz")).unwrap(), "one two");
+    }
+
+    #[test]
+    fn variants() {
+        let dir = temp_dir("variants");
+        fs::write(dir.join("ls.1.zst"), "").unwrap();
+        fs::write(dir.join("cat.1"), "").unwrap();
+
+        assert_eq!(find_variant(&dir.join("ls.1")), Some(dir.join("ls.1.zst")));
+        assert_eq!(find_variant(&dir.join("cat.1")), Some(dir.join("cat.1")));
+        assert_eq!(find_variant(&dir.join("cp.1")), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Devhelp books: HTML manuals described by `.devhelp2` or legacy `.devhelp` index files.
+
+use std::{
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{decompress, html, xdg};
+
+/// Directories under each XDG data directory that hold one subdirectory per book.
+const BOOK_DIRS: &[&str] = &["devhelp/books", "gtk-doc/html"];
+
+/// Index file extensions, preferred first.
+const INDEX_EXTENSIONS: &[&str] = &["devhelp2", "devhelp"];
+
+/// An entry in a book's table of contents.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub name: String,
+    pub uri: String,
+    pub children: Vec<Chapter>,
+}
+
+/// A symbol a book documents, like a function or a type.
+#[derive(Debug, Clone)]
+pub struct Keyword {
+    pub name: String,
+    /// `function`, `struct`, `macro` and so on; empty in legacy books.
+    pub kind: String,
+    pub uri: String,
+    pub deprecated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Book {
+    /// The book's short name, unique among installed books.
+    pub id: String,
+    pub title: String,
+    /// The directory the book's pages are in.
+    pub dir: PathBuf,
+    /// The book's start page.
+    pub uri: String,
+    pub chapters: Vec<Chapter>,
+    pub keywords: Vec<Keyword>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Xml(PathBuf, roxmltree::Error),
+    NotABook(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+            Error::Xml(path, e) => write!(f, "Could not parse {}: {}", path.display(), e),
+            Error::NotABook(path) => write!(f, "{} is not a Devhelp book", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Finds and parses every installed book, skipping any that fail to parse.
+///
+/// When several data directories have a book with the same name, the first one wins,
+/// so books in `XDG_DATA_HOME` override system ones.
+pub fn books() -> Vec<Book> {
+    let mut books = Vec::new();
+    let mut seen = HashSet::new();
+
+    for root in book_roots() {
+        let Ok(entries) = fs::read_dir(root) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let Some(index) = find_index(&entry.path()) else {
+                continue;
+            };
+
+            match parse(&index) {
+                Ok(book) if seen.insert(book.id.clone()) => books.push(book),
+                Ok(_) => {}
+                Err(e) => eprintln!("Skipping Devhelp book: {}", e),
+            }
+        }
+    }
+
+    books.sort_by_key(|book| book.title.to_lowercase());
+    books
+}
+
+/// The directories holding a directory for each book, in the order they are searched.
+pub fn book_roots() -> Vec<PathBuf> {
+    xdg::data_dirs()
+        .iter()
+        .flat_map(|data_dir| BOOK_DIRS.iter().map(|book_dir| data_dir.join(book_dir)))
+        .collect()
+}
+
+/// The index file in a book directory, named after the directory.
+fn find_index(dir: &Path) -> Option<PathBuf> {
+    let name = dir.file_name()?.to_string_lossy();
+
+    INDEX_EXTENSIONS
+        .iter()
+        .find_map(|ext| decompress::find_variant(&dir.join(format!("{}.{}", name, ext))))
+}
+
+/// Parses a `.devhelp2` or `.devhelp` index file.
+pub fn parse(path: &Path) -> Result<Book, Error> {
+    let text = decompress::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let document =
+        roxmltree::Document::parse(&text).map_err(|e| Error::Xml(path.to_path_buf(), e))?;
+
+    let root = document.root_element();
+    if root.tag_name().name() != "book" {
+        return Err(Error::NotABook(path.to_path_buf()));
+    }
+
+    // Links are relative to the index file, or to the `base` attribute of legacy books.
+    let base = match root.attribute("base") {
+        Some(base) => PathBuf::from(base),
+        None => path.parent().unwrap_or(Path::new("/")).to_path_buf(),
+    };
+
+    let link =
+        |node: roxmltree::Node| resolve_link(&base, node.attribute("link").unwrap_or_default());
+
+    let file_stem = path.file_name().unwrap_or_default().to_string_lossy();
+    let id = root
+        .attribute("name")
+        .map(str::to_string)
+        .unwrap_or_else(|| file_stem.split('.').next().unwrap_or_default().to_string());
+
+    let mut book = Book {
+        title: root.attribute("title").unwrap_or(&id).to_string(),
+        id,
+        dir: base.clone(),
+        uri: link(root),
+        chapters: Vec::new(),
+        keywords: Vec::new(),
+    };
+
+    for section in root.children().filter(|n| n.is_element()) {
+        match section.tag_name().name() {
+            "chapters" => book.chapters = chapters(section, &link),
+            "functions" => {
+                book.keywords = section
+                    .children()
+                    .filter(|n| matches!(n.tag_name().name(), "keyword" | "function"))
+                    .map(|n| Keyword {
+                        name: n.attribute("name").unwrap_or_default().to_string(),
+                        kind: n.attribute("type").unwrap_or_default().to_string(),
+                        uri: link(n),
+                        deprecated: n.attribute("deprecated").is_some(),
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    Ok(book)
+}
+
+fn chapters(parent: roxmltree::Node, link: &impl Fn(roxmltree::Node) -> String) -> Vec<Chapter> {
+    parent
+        .children()
+        .filter(|n| matches!(n.tag_name().name(), "sub" | "chapter"))
+        .map(|n| Chapter {
+            name: n.attribute("name").unwrap_or_default().to_string(),
+            uri: link(n),
+            children: chapters(n, link),
+        })
+        .collect()
+}
+
+/// Turns a link relative to a book directory into a `file:` URI, keeping any fragment.
+fn resolve_link(base: &Path, link: &str) -> String {
+    let (file, fragment) = match link.split_once('#') {
+        Some((file, fragment)) => (file, Some(fragment)),
+        None => (link, None),
+    };
+
+    let uri = html::file_uri(&base.join(file));
+
+    match fragment {
+        Some(fragment) => format!("{}#{}", uri, fragment),
+        None => uri,
+    }
+}
@@ -0,0 +1,330 @@
+//! Searching the text of a page for the find bar.
+//!
+//! WebKit's `FindController` only matches plain text, so whole-word and regular expression
+//! searches are done by a script instead. It matches the text of each block of the page as a
+//! whole, so words split across inline elements, like the fonts of `\fBgit\fR\-\fIcommit\fR`
+//! in a man page, are still found.
+
+use serde::Deserialize;
+
+/// The most matches a search counts and highlights; finding more shows "more than".
+pub const MAX_MATCHES: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindOption {
+    CaseSensitive,
+    WholeWord,
+    Regex,
+    HighlightAll,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub text: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+    pub highlight_all: bool,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Query {
+            text: String::new(),
+            case_sensitive: false,
+            whole_word: false,
+            regex: false,
+            highlight_all: true,
+        }
+    }
+}
+
+impl Query {
+    pub fn set(&mut self, option: FindOption, value: bool) {
+        match option {
+            FindOption::CaseSensitive => self.case_sensitive = value,
+            FindOption::WholeWord => self.whole_word = value,
+            FindOption::Regex => self.regex = value,
+            FindOption::HighlightAll => self.highlight_all = value,
+        }
+    }
+
+    /// Whether the query needs [`SCRIPT`] rather than WebKit's own search.
+    pub fn needs_script(&self) -> bool {
+        self.whole_word || self.regex
+    }
+
+    pub fn webkit_options(&self) -> webkit6::FindOptions {
+        let mut options = webkit6::FindOptions::WRAP_AROUND;
+        if !self.case_sensitive {
+            options |= webkit6::FindOptions::CASE_INSENSITIVE;
+        }
+        options
+    }
+}
+
+/// What the last search found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Status {
+    #[default]
+    Idle,
+    Matches {
+        count: u32,
+        /// The selected match, counting from 1, when the search knows it.
+        current: Option<u32>,
+    },
+    InvalidPattern(String),
+}
+
+/// The result of running [`SCRIPT`].
+#[derive(Debug, Deserialize)]
+struct ScriptStatus {
+    #[serde(default)]
+    count: u32,
+    #[serde(default)]
+    current: u32,
+    error: Option<String>,
+}
+
+impl Status {
+    /// Reads what [`SCRIPT`] returned.
+    pub fn from_script(json: &str) -> Status {
+        match serde_json::from_str::<ScriptStatus>(json) {
+            Ok(ScriptStatus {
+                error: Some(error), ..
+            }) => Status::InvalidPattern(error),
+            Ok(ScriptStatus { count, current, .. }) => Status::Matches {
+                count,
+                current: Some(current).filter(|&c| c > 0),
+            },
+            Err(e) => {
+                eprintln!("Could not read find results: {}", e);
+                Status::Idle
+            }
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Status::Idle => String::new(),
+            Status::Matches { count: 0, .. } => "No matches".to_string(),
+            // The search stopped counting, so the count isn't the real one.
+            Status::Matches { count, current } if *count > MAX_MATCHES => match current {
+                Some(current) => format!("{} of more than {}", current, MAX_MATCHES),
+                None => format!("More than {} matches", MAX_MATCHES),
+            },
+            Status::Matches {
+                count,
+                current: Some(current),
+            } => format!("{} of {}", current, count),
+            Status::Matches { count: 1, .. } => "1 match".to_string(),
+            Status::Matches { count, .. } => format!("{} matches", count),
+            Status::InvalidPattern(_) => "Invalid pattern".to_string(),
+        }
+    }
+}
+
+/// Finds, steps through and clears matches of a [`Query`] by wrapping them in `<mark>`s.
+///
+/// Takes `action` (`search`, `next`, `previous` or `finish`) and, for `search`, the fields of
+/// the query. Returns a JSON object with either `count` and `current` or an `error`.
+pub const SCRIPT: &str = r#"
+// The `<mark>`s of each match, one per text node the match runs through.
+const find = globalThis.__find ??= { matches: [], current: -1 };
+
+const status = () => JSON.stringify({ count: find.matches.length, current: find.current + 1 });
+
+const clear = () => {
+    for (const mark of find.matches.flat()) {
+        const parent = mark.parentNode;
+        if (parent) {
+            parent.replaceChild(document.createTextNode(mark.textContent), mark);
+            parent.normalize();
+        }
+    }
+    find.matches = [];
+    find.current = -1;
+};
+
+const select = (index) => {
+    if (find.matches.length === 0)
+        return;
+
+    for (const mark of find.matches[find.current] ?? [])
+        mark.classList.remove("current");
+    find.current = (index + find.matches.length) % find.matches.length;
+
+    const marks = find.matches[find.current];
+    for (const mark of marks)
+        mark.classList.add("current");
+    marks[0].scrollIntoView({ block: "center" });
+};
+
+if (action === "next") {
+    select(find.current + 1);
+    return status();
+}
+
+if (action === "previous") {
+    select(find.current - 1);
+    return status();
+}
+
+clear();
+
+if (action === "finish" || text === "")
+    return status();
+
+if (!document.getElementById("docviewer-find-style")) {
+    const style = document.createElement("style");
+    style.id = "docviewer-find-style";
+    style.textContent = `
+        mark.docviewer-find { background: none; color: inherit; }
+        body.docviewer-find-all mark.docviewer-find { background: #f9f06b; color: #000; }
+        mark.docviewer-find.current, body.docviewer-find-all mark.docviewer-find.current {
+            background: #ffa348; color: #000;
+        }
+    `;
+    document.head.append(style);
+}
+
+document.body.classList.toggle("docviewer-find-all", highlightAll);
+
+let source = regex ? text : text.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
+// `\b` only knows ASCII letters, even with the `u` flag.
+if (wholeWord)
+    source = `(?<![\\p{L}\\p{N}_])(?:${source})(?![\\p{L}\\p{N}_])`;
+
+let pattern;
+try {
+    pattern = new RegExp(source, caseSensitive ? "gu" : "giu");
+} catch (e) {
+    return JSON.stringify({ error: e.message });
+}
+
+// The running header and footer only repeat the page name.
+const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+    acceptNode: (node) => node.parentElement.closest("script, style, .man-header, .man-footer")
+        ? NodeFilter.FILTER_REJECT
+        : NodeFilter.FILTER_ACCEPT,
+});
+
+// Text is matched a block at a time, so a match can run through inline elements but not
+// from one paragraph into the next.
+const blockOf = (node) => {
+    let elem = node.parentElement;
+    while (elem !== document.body && getComputedStyle(elem).display.startsWith("inline"))
+        elem = elem.parentElement;
+    return elem;
+};
+
+const blocks = [];
+let lastBlock = null;
+while (walker.nextNode()) {
+    const node = walker.currentNode;
+    const block = blockOf(node);
+    if (block !== lastBlock)
+        blocks.push([]);
+    blocks[blocks.length - 1].push(node);
+    lastBlock = block;
+}
+
+// The parts of the text from `start` to `end` in each node, as `[node, start, end]`.
+const pieces = (nodes, start, end) => {
+    const found = [];
+    let offset = 0;
+    for (const node of nodes) {
+        const from = Math.max(start - offset, 0);
+        const to = Math.min(end - offset, node.data.length);
+        if (from < to)
+            found.push([node, from, to]);
+        offset += node.data.length;
+    }
+    return found;
+};
+
+// One more than can be shown tells the find bar that there are more.
+const matches = [];
+search: for (const nodes of blocks) {
+    const text = nodes.map((node) => node.data).join("");
+    for (const match of text.matchAll(pattern)) {
+        if (match[0] === "")
+            continue;
+        matches.push(pieces(nodes, match.index, match.index + match[0].length));
+        if (matches.length > maxMatches)
+            break search;
+    }
+}
+
+// Wrapping from the end keeps the offsets of earlier pieces valid.
+find.matches = matches.map(() => []);
+for (let i = matches.length - 1; i >= 0; i--) {
+    for (const [node, start, end] of [...matches[i]].reverse()) {
+        const range = document.createRange();
+        range.setStart(node, start);
+        range.setEnd(node, end);
+
+        const mark = document.createElement("mark");
+        mark.className = "docviewer-find";
+        range.surroundContents(mark);
+        find.matches[i].unshift(mark);
+    }
+}
+
+select(0);
+return status();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(count: u32, current: Option<u32>) -> Status {
+        Status::Matches { count, current }
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(Status::Idle.label(), "");
+        assert_eq!(matches(0, None).label(), "No matches");
+        assert_eq!(matches(1, None).label(), "1 match");
+        assert_eq!(matches(5, None).label(), "5 matches");
+        assert_eq!(matches(5, Some(2)).label(), "2 of 5");
+        assert_eq!(
+            Status::InvalidPattern("unterminated group".to_string()).label(),
+            "Invalid pattern"
+        );
+    }
+
+    #[test]
+    fn labels_for_truncated_searches() {
+        assert_eq!(matches(MAX_MATCHES, None).label(), "1000 matches");
+        assert_eq!(
+            matches(MAX_MATCHES + 1, None).label(),
+            "More than 1000 matches"
+        );
+        assert_eq!(
+            matches(MAX_MATCHES + 1, Some(3)).label(),
+            "3 of more than 1000"
+        );
+        // What WebKit counts when it gives up.
+        assert_eq!(matches(u32::MAX, None).label(), "More than 1000 matches");
+    }
+
+    #[test]
+    fn script_results() {
+        assert_eq!(
+            Status::from_script(r#"{"count": 4, "current": 1}"#),
+            matches(4, Some(1))
+        );
+        assert_eq!(
+            Status::from_script(r#"{"count": 4, "current": 0}"#),
+            matches(4, None)
+        );
+        assert_eq!(
+            Status::from_script(r#"{"error": "Invalid regular expression"}"#),
+            Status::InvalidPattern("Invalid regular expression".to_string())
+        );
+        assert_eq!(Status::from_script("not json"), Status::Idle);
+    }
+}
@@ -0,0 +1,201 @@
+//! API references generated by gi-docgen, like the GTK, GLib and libadwaita docs.
+//!
+//! gi-docgen writes an `index.json` search index next to the HTML, which lists every
+//! documented symbol along with enough information to work out its page.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    html,
+    symbols::{Symbol, SymbolKind},
+    xdg,
+};
+
+/// The directory under each XDG data directory that holds one subdirectory per project.
+const DOC_DIR: &str = "doc";
+
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// The GObject introspection namespace, like `Gtk`.
+    pub namespace: String,
+    pub version: String,
+    pub symbols: Vec<Symbol>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    NotGenerated(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+            Error::Json(path, e) => write!(f, "Could not parse {}: {}", path.display(), e),
+            Error::NotGenerated(path) => {
+                write!(f, "{} was not generated by gi-docgen", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Deserialize)]
+struct IndexFile {
+    meta: Meta,
+    symbols: Vec<IndexSymbol>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    ns: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    generator: String,
+}
+
+#[derive(Deserialize)]
+struct IndexSymbol {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    /// The type a member belongs to.
+    type_name: Option<String>,
+    /// The type a class method's class structure is for.
+    struct_for: Option<String>,
+    /// The C name of a type.
+    ctype: Option<String>,
+    /// The C name of a function or constant.
+    ident: Option<String>,
+    /// The page of extra content, relative to the project directory.
+    href: Option<String>,
+    #[serde(default)]
+    summary: String,
+    deprecated: Option<serde::de::IgnoredAny>,
+}
+
+/// Finds and loads every installed gi-docgen project, skipping any that fail to load.
+pub fn projects() -> Vec<Project> {
+    let mut projects = Vec::new();
+
+    for data_dir in xdg::data_dirs() {
+        let Ok(entries) = fs::read_dir(data_dir.join(DOC_DIR)) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !dir.join("index.json").is_file() {
+                continue;
+            }
+
+            match load(&dir) {
+                Ok(project) => projects.push(project),
+                Err(e) => eprintln!("Skipping gi-docgen project: {}", e),
+            }
+        }
+    }
+
+    projects.sort_by(|a, b| (&a.namespace, &a.version).cmp(&(&b.namespace, &b.version)));
+    projects
+}
+
+/// Loads the project in `dir` from its `index.json`.
+pub fn load(dir: &Path) -> Result<Project, Error> {
+    let path = dir.join("index.json");
+
+    let text = fs::read_to_string(&path).map_err(|e| Error::Io(path.clone(), e))?;
+    let index: IndexFile = serde_json::from_str(&text).map_err(|e| Error::Json(path.clone(), e))?;
+
+    if index.meta.generator != "gi-docgen" {
+        return Err(Error::NotGenerated(path));
+    }
+
+    let namespace = index.meta.ns;
+    let symbols = index
+        .symbols
+        .into_iter()
+        .filter_map(|symbol| to_symbol(&namespace, dir, symbol))
+        .collect();
+
+    Ok(Project {
+        namespace,
+        version: index.meta.version,
+        symbols,
+    })
+}
+
+/// Works out a symbol's qualified name and page the way gi-docgen's own search does.
+fn to_symbol(namespace: &str, dir: &Path, symbol: IndexSymbol) -> Option<Symbol> {
+    let name = &symbol.name;
+    let owner = symbol.type_name.as_deref().unwrap_or_default();
+
+    let (kind, page, qualified) = match symbol.kind.as_str() {
+        "alias" => (SymbolKind::Alias, format!("alias.{}", name), None),
+        "bitfield" => (SymbolKind::Flags, format!("flags.{}", name), None),
+        "callback" => (SymbolKind::Callback, format!("callback.{}", name), None),
+        "class" => (SymbolKind::Class, format!("class.{}", name), None),
+        "domain" => (SymbolKind::Error, format!("error.{}", name), None),
+        "enum" => (SymbolKind::Enum, format!("enum.{}", name), None),
+        "interface" => (SymbolKind::Interface, format!("iface.{}", name), None),
+        "record" => (SymbolKind::Record, format!("struct.{}", name), None),
+        "union" => (SymbolKind::Union, format!("union.{}", name), None),
+        "constant" => (SymbolKind::Constant, format!("const.{}", name), None),
+        "function" => (SymbolKind::Function, format!("func.{}", name), None),
+        "function_macro" => (SymbolKind::Macro, format!("func.{}", name), None),
+        "class_method" => {
+            let owner = symbol.struct_for.as_deref().unwrap_or(owner);
+            let page = format!("class_method.{}.{}", owner, name);
+            (
+                SymbolKind::ClassMethod,
+                page,
+                Some(format!("{}.{}", owner, name)),
+            )
+        }
+        "ctor" => member(SymbolKind::Constructor, "ctor", owner, name, "."),
+        "method" => member(SymbolKind::Method, "method", owner, name, "."),
+        "type_func" => member(SymbolKind::TypeFunction, "type_func", owner, name, "."),
+        "vfunc" => member(SymbolKind::VirtualMethod, "vfunc", owner, name, "."),
+        "property" => member(SymbolKind::Property, "property", owner, name, ":"),
+        "signal" => member(SymbolKind::Signal, "signal", owner, name, "::"),
+        "content" => {
+            let href = symbol.href.as_deref()?;
+            let uri = html::file_uri(&dir.join(href));
+            return Some(Symbol::new(name, SymbolKind::Other, &symbol.summary, uri));
+        }
+        _ => return None,
+    };
+
+    let qualified = qualified.unwrap_or_else(|| name.clone());
+    let uri = html::file_uri(&dir.join(format!("{}.html", page)));
+
+    let mut out = Symbol::new(
+        &format!("{}.{}", namespace, qualified),
+        kind,
+        &symbol.summary,
+        uri,
+    );
+    out.ident = symbol.ident.or(symbol.ctype);
+    out.deprecated = symbol.deprecated.is_some();
+    Some(out)
+}
+
+fn member(
+    kind: SymbolKind,
+    prefix: &str,
+    owner: &str,
+    name: &str,
+    separator: &str,
+) -> (SymbolKind, String, Option<String>) {
+    let page = format!("{}.{}.{}", prefix, owner, name);
+    (kind, page, Some(format!("{}{}{}", owner, separator, name)))
+}
@@ -0,0 +1,434 @@
+//! Every document visited, kept as a JSON Lines file under `$XDG_DATA_HOME`.
+//!
+//! Visits are appended as they happen, so recording one never rewrites the file.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    store::{self, Error},
+    xdg,
+};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// How many visits to keep; older ones are dropped when the file is next loaded.
+const MAX_VISITS: usize = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentKind {
+    ManPage,
+    Info,
+    Html,
+    Other,
+}
+
+impl DocumentKind {
+    pub fn from_uri(uri: &str) -> Self {
+        let scheme = uri.split_once(':').map(|(scheme, _)| scheme);
+
+        match scheme {
+            Some("man") => DocumentKind::ManPage,
+            Some("info") => DocumentKind::Info,
+            Some("file" | "docfile") => DocumentKind::Html,
+            _ => DocumentKind::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DocumentKind::ManPage => "Man page",
+            DocumentKind::Info => "Info",
+            DocumentKind::Html => "HTML",
+            DocumentKind::Other => "Web page",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Visit {
+    pub uri: String,
+    pub title: String,
+    /// Seconds since the Unix epoch.
+    pub time: i64,
+    pub kind: DocumentKind,
+}
+
+impl Visit {
+    /// The time of day of the visit, like `14:05`.
+    pub fn local_time(&self) -> String {
+        glib::DateTime::from_unix_local(self.time)
+            .and_then(|date| date.format("%H:%M"))
+            .map(|s| s.to_string())
+            .unwrap_or_default()
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        self.title.to_lowercase().contains(query) || self.uri.to_lowercase().contains(query)
+    }
+}
+
+/// The visits on one calendar day, in local time.
+#[derive(Debug, Clone)]
+pub struct Day<'a> {
+    /// Like `2024-05-31`.
+    pub key: String,
+    /// Like `Today` or `Friday, 31 May 2024`.
+    pub label: String,
+    pub visits: Vec<&'a Visit>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Oldest first.
+    visits: Vec<Visit>,
+}
+
+impl History {
+    fn path() -> PathBuf {
+        xdg::app_data_dir().join(HISTORY_FILE)
+    }
+
+    /// Reads the recorded visits, skipping lines that can't be parsed.
+    pub fn load() -> Result<History, Error> {
+        let path = Self::path();
+
+        let Some(text) = store::read(&path)? else {
+            return Ok(History::default());
+        };
+
+        let (history, truncated) = Self::parse(&text);
+        if truncated {
+            history.rewrite()?;
+        }
+
+        Ok(history)
+    }
+
+    /// Parses the lines of the history file, keeping the newest [`MAX_VISITS`] visits.
+    ///
+    /// Returns whether older visits were dropped.
+    fn parse(text: &str) -> (History, bool) {
+        let mut visits: Vec<Visit> = text
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        let truncated = visits.len() > MAX_VISITS;
+        if truncated {
+            visits.drain(..visits.len() - MAX_VISITS);
+        }
+
+        (History { visits }, truncated)
+    }
+
+    fn rewrite(&self) -> Result<(), Error> {
+        let path = Self::path();
+
+        let mut text = String::new();
+        for visit in &self.visits {
+            let line = serde_json::to_string(visit).map_err(|e| Error::Json(path.clone(), e))?;
+            text.push_str(&line);
+            text.push('\n');
+        }
+
+        store::write(&path, &text)
+    }
+
+    /// Records a visit to `uri` now, unless it is also the last page visited or a built-in page.
+    ///
+    /// Returns the new visit.
+    pub fn record(&mut self, uri: &str, title: &str) -> Result<Option<Visit>, Error> {
+        if self.visits.last().is_some_and(|last| last.uri == uri) {
+            return Ok(None);
+        }
+
+        if uri.starts_with("docviewer:") || uri.starts_with("about:") {
+            return Ok(None);
+        }
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+
+        let visit = Visit {
+            uri: uri.to_string(),
+            title: title.to_string(),
+            time,
+            kind: DocumentKind::from_uri(uri),
+        };
+
+        let path = Self::path();
+        let mut line = serde_json::to_string(&visit).map_err(|e| Error::Json(path.clone(), e))?;
+        line.push('\n');
+        store::append(&path, line)?;
+
+        self.visits.push(visit.clone());
+        Ok(Some(visit))
+    }
+
+    /// Adds a visit recorded elsewhere, without writing it.
+    pub fn push(&mut self, visit: Visit) {
+        self.visits.push(visit);
+    }
+
+    /// The latest visit to each of the `limit` most recently visited documents.
+    pub fn recent(&self, limit: usize) -> Vec<&Visit> {
+        let mut seen = HashSet::new();
+
+        self.visits
+            .iter()
+            .rev()
+            .filter(|visit| seen.insert(&visit.uri))
+            .take(limit)
+            .collect()
+    }
+
+    /// Visits whose title or URI contains `filter`, ignoring case, grouped by day.
+    ///
+    /// Days and the visits in them are newest first, and a document visited several
+    /// times on one day is only listed once.
+    pub fn days(&self, filter: &str) -> Vec<Day<'_>> {
+        let filter = filter.trim().to_lowercase();
+
+        let today = glib::DateTime::now_local().ok();
+        let yesterday = today.as_ref().and_then(|today| today.add_days(-1).ok());
+
+        let mut days: Vec<Day> = Vec::new();
+
+        for visit in self.visits.iter().rev() {
+            if !filter.is_empty() && !visit.matches(&filter) {
+                continue;
+            }
+
+            let Ok(date) = glib::DateTime::from_unix_local(visit.time) else {
+                continue;
+            };
+            let key = date
+                .format("%Y-%m-%d")
+                .map(|s| s.to_string())
+                .unwrap_or_default();
+
+            if days.last().is_none_or(|day| day.key != key) {
+                let is_same_day = |other: &Option<glib::DateTime>| {
+                    other
+                        .as_ref()
+                        .is_some_and(|other| other.ymd() == date.ymd())
+                };
+
+                let label = if is_same_day(&today) {
+                    "Today".to_string()
+                } else if is_same_day(&yesterday) {
+                    "Yesterday".to_string()
+                } else {
+                    date.format("%A, %e %B %Y")
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_else(|_| key.clone())
+                };
+
+                days.push(Day {
+                    key,
+                    label,
+                    visits: Vec::new(),
+                });
+            }
+
+            let day = days.last_mut().unwrap();
+            if !day.visits.iter().any(|v| v.uri == visit.uri) {
+                day.visits.push(visit);
+            }
+        }
+
+        days
+    }
+
+    /// Visited documents for an address being typed, best first.
+    ///
+    /// Addresses that start with the query rank first, then other matches; among those,
+    /// documents visited more often and more recently win.
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<&Visit> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        // The latest visit to each matching document, and how often it was visited.
+        let mut documents: HashMap<&str, (&Visit, usize)> = HashMap::new();
+        for visit in self.visits.iter().filter(|v| v.matches(&query)) {
+            let entry = documents.entry(&visit.uri).or_insert((visit, 0));
+            entry.0 = visit;
+            entry.1 += 1;
+        }
+
+        let rank = |visit: &Visit| {
+            let uri = visit.uri.to_lowercase();
+            let address = uri.split_once(':').map_or(uri.as_str(), |(_, rest)| rest);
+            let address = address.trim_start_matches('/');
+
+            if uri.starts_with(&query) || address.starts_with(&query) {
+                0
+            } else if visit.title.to_lowercase().starts_with(&query) {
+                1
+            } else {
+                2
+            }
+        };
+
+        let mut matches: Vec<(u8, usize, &Visit)> = documents
+            .into_values()
+            .map(|(visit, count)| (rank(visit), count, visit))
+            .collect();
+
+        matches.sort_by(|(a_rank, a_count, a), (b_rank, b_count, b)| {
+            (a_rank, b_count, b.time).cmp(&(b_rank, a_count, a.time))
+        });
+
+        matches.into_iter().take(limit).map(|(_, _, v)| v).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Noon on 14 November 2023, UTC.
+    const DAY: i64 = 1_699_963_200;
+
+    fn visit(uri: &str, title: &str, time: i64) -> Visit {
+        Visit {
+            uri: uri.to_string(),
+            title: title.to_string(),
+            time,
+            kind: DocumentKind::from_uri(uri),
+        }
+    }
+
+    fn history(visits: &[(&str, &str, i64)]) -> History {
+        History {
+            visits: visits
+                .iter()
+                .map(|(uri, title, time)| visit(uri, title, *time))
+                .collect(),
+        }
+    }
+
+    fn uris<'a>(visits: &[&'a Visit]) -> Vec<&'a str> {
+        visits.iter().map(|v| v.uri.as_str()).collect()
+    }
+
+    #[test]
+    fn days() {
+        let now = glib::DateTime::now_local().unwrap().to_unix();
+        let history = history(&[
+            ("man:ls(1)", "ls", DAY),
+            ("man:cp(1)", "cp", DAY + 60),
+            ("man:ls(1)", "ls", DAY + 120),
+            ("info:find", "find", now),
+        ]);
+
+        let days = history.days("");
+        let key = glib::DateTime::from_unix_local(DAY)
+            .unwrap()
+            .format("%Y-%m-%d")
+            .unwrap()
+            .to_string();
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].label, "Today");
+        assert_eq!(uris(&days[0].visits), ["info:find"]);
+        assert_eq!(days[1].key, key);
+        assert_ne!(days[1].label, "Today");
+        assert_eq!(uris(&days[1].visits), ["man:ls(1)", "man:cp(1)"]);
+        assert_eq!(days[1].visits[0].time, DAY + 120);
+
+        let days = history.days(" CP ");
+        assert_eq!(days.len(), 1);
+        assert_eq!(uris(&days[0].visits), ["man:cp(1)"]);
+    }
+
+    #[test]
+    fn recent() {
+        let history = history(&[
+            ("man:ls(1)", "ls", DAY),
+            ("man:cp(1)", "cp", DAY + 1),
+            ("man:ls(1)", "ls", DAY + 2),
+            ("info:find", "find", DAY + 3),
+        ]);
+
+        assert_eq!(uris(&history.recent(2)), ["info:find", "man:ls(1)"]);
+        assert_eq!(
+            uris(&history.recent(10)),
+            ["info:find", "man:ls(1)", "man:cp(1)"]
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let history = history(&[
+            ("file:///usr/share/doc/also.html", "Also", DAY),
+            ("man:ls(1)", "ls - list", DAY + 1),
+            ("info:(coreutils)ls invocation", "ls invocation", DAY + 2),
+            ("man:lsblk(8)", "lsblk", DAY + 3),
+            ("man:ls(1)", "ls - list", DAY + 4),
+            ("man:cp(1)", "cp", DAY + 5),
+        ]);
+
+        // Addresses first, more visits first, then titles, then anything else.
+        let suggestions = history.suggest("LS", 10);
+        assert_eq!(
+            uris(&suggestions),
+            [
+                "man:ls(1)",
+                "man:lsblk(8)",
+                "info:(coreutils)ls invocation",
+                "file:///usr/share/doc/also.html",
+            ]
+        );
+        assert_eq!(suggestions[0].time, DAY + 4);
+
+        assert_eq!(uris(&history.suggest("ls", 1)), ["man:ls(1)"]);
+        assert_eq!(
+            uris(&history.suggest("usr", 10)),
+            ["file:///usr/share/doc/also.html"]
+        );
+        assert!(history.suggest("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn only_the_newest_visits_are_kept() {
+        let lines: Vec<String> = (0..MAX_VISITS + 2)
+            .map(|i| {
+                let visit = visit(&format!("man:page({})", i), "", DAY + i as i64);
+                serde_json::to_string(&visit).unwrap() + "\n"
+            })
+            .collect();
+
+        let (history, truncated) = History::parse(&("not a visit\n".to_string() + &lines.concat()));
+        assert!(truncated);
+        assert_eq!(history.visits.len(), MAX_VISITS);
+        assert_eq!(history.visits[0].uri, "man:page(2)");
+
+        let (history, truncated) = History::parse(&lines[2..].concat());
+        assert!(!truncated);
+        assert_eq!(history.visits.len(), MAX_VISITS);
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(DocumentKind::from_uri("man:ls(1)"), DocumentKind::ManPage);
+        assert_eq!(DocumentKind::from_uri("info:find"), DocumentKind::Info);
+        assert_eq!(
+            DocumentKind::from_uri("docfile:///a.html.gz"),
+            DocumentKind::Html
+        );
+        assert_eq!(
+            DocumentKind::from_uri("https://example.org"),
+            DocumentKind::Other
+        );
+    }
+}
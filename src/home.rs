@@ -0,0 +1,115 @@
+//! The built-in start page, served as `docviewer:home`.
+
+use std::fmt::Write;
+
+use crate::{bookmarks, history, html};
+
+pub const URI: &str = "docviewer:home";
+
+/// How many recently visited documents the page lists.
+const RECENT_DOCUMENTS: usize = 12;
+
+/// Opens the man page or Info manual typed into one of the quick search forms.
+///
+/// Man page names can be written like man(1) takes them, as `3 printf`.
+const QUICK_SEARCH_SCRIPT: &str = r#"
+for (const form of document.querySelectorAll("form[data-scheme]")) {
+    form.addEventListener("submit", (event) => {
+        event.preventDefault();
+
+        let query = form.elements.query.value.trim();
+        if (form.dataset.scheme === "man")
+            query = query.replace(/^(\d\w*)\s+(\S+)$/, "$2($1)");
+
+        if (query)
+            location.href = form.dataset.scheme + ":" + encodeURIComponent(query);
+    });
+}
+"#;
+
+/// Builds the response for a `docviewer:` URI, given everything after the scheme.
+pub fn page_for_uri(path: &str) -> String {
+    match path.split(['#', '?']).next().unwrap_or_default() {
+        "home" => render_home(),
+        _ => html::error_page(
+            "Page not found",
+            &format!("There is no page called {}.", path),
+        ),
+    }
+}
+
+fn render_home() -> String {
+    let history = history::History::load().unwrap_or_else(|e| {
+        eprintln!("Could not load history for the home page: {}", e);
+        Default::default()
+    });
+    let bookmarks = bookmarks::Bookmarks::load().unwrap_or_else(|e| {
+        eprintln!("Could not load bookmarks for the home page: {}", e);
+        Default::default()
+    });
+
+    let mut body = String::from("<main class=\"home\">\n<h1>Documentation</h1>\n");
+
+    body.push_str(concat!(
+        "<form data-scheme=\"man\">\n",
+        "<label><span>Man page</span> <input name=\"query\" placeholder=\"printf, 3 printf or printf.3\" autofocus></label>\n",
+        "</form>\n",
+        "<form data-scheme=\"info\">\n",
+        "<label><span>Info manual</span> <input name=\"query\" placeholder=\"coreutils\"></label>\n",
+        "</form>\n",
+        "<p><a href=\"info:dir\">Info directory</a></p>\n",
+    ));
+
+    body.push_str("<h2>Recent documents</h2>\n");
+
+    let recent = history.recent(RECENT_DOCUMENTS);
+    if recent.is_empty() {
+        body.push_str("<p class=\"empty\">Documents you read will show up here.</p>\n");
+    } else {
+        body.push_str("<ul>\n");
+        for visit in recent {
+            let title = if visit.title.is_empty() {
+                &visit.uri
+            } else {
+                &visit.title
+            };
+
+            let _ = writeln!(
+                body,
+                "<li><a href=\"{}\">{}</a> <span class=\"kind\">{}</span></li>",
+                html::escape(&visit.uri),
+                html::escape(title),
+                visit.kind.label(),
+            );
+        }
+        body.push_str("</ul>\n");
+    }
+
+    body.push_str("<h2>Bookmarks</h2>\n");
+
+    let folders = bookmarks.folders();
+    if folders.is_empty() {
+        body.push_str("<p class=\"empty\">Pages you bookmark will show up here.</p>\n");
+    }
+
+    for folder in folders {
+        if let Some(name) = folder.name {
+            let _ = writeln!(body, "<h3>{}</h3>", html::escape(name));
+        }
+
+        body.push_str("<ul>\n");
+        for bookmark in folder.bookmarks {
+            let _ = writeln!(
+                body,
+                "<li><a href=\"{}\">{}</a></li>",
+                html::escape(&bookmark.target()),
+                html::escape(&bookmark.title),
+            );
+        }
+        body.push_str("</ul>\n");
+    }
+
+    let _ = write!(body, "</main>\n<script>{}</script>", QUICK_SEARCH_SCRIPT);
+
+    html::document("Home", "home", &body)
+}
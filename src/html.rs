@@ -0,0 +1,218 @@
+//! Small helpers for the HTML that the built-in URI schemes generate.
+
+use std::{fmt::Write, path::Path};
+
+static DOCUMENT_STYLESHEET: &str = include_str!("../share/document.css");
+
+/// Escapes text for use in HTML element content and quoted attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Percent-encodes text for use as one component of a URI path.
+pub fn escape_uri_component(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'(' | b')' => {
+                out.push(byte as char)
+            }
+            byte => {
+                let _ = write!(out, "%{:02X}", byte);
+            }
+        }
+    }
+
+    out
+}
+
+/// A `file:` URI for an absolute path.
+pub fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let components: Vec<String> = path.split('/').map(escape_uri_component).collect();
+
+    format!("file://{}", components.join("/"))
+}
+
+/// Elements whose content is not text.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "head"];
+
+/// The text content of an HTML document or fragment, with runs of whitespace collapsed.
+///
+/// Scripts, styles and the document head are left out. Only common character
+/// references are decoded; this is for indexing and snippets, not for display.
+pub fn text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    let mut skip_until: Option<&str> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skip_until.is_none() {
+            push_text(&mut out, &rest[..start]);
+        }
+        rest = &rest[start..];
+
+        let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+        let tag = rest[1..end].trim_end_matches('>').to_ascii_lowercase();
+        let name = tag.split([' ', '\t', '\n', '/']).find(|s| !s.is_empty());
+        rest = &rest[end..];
+
+        match skip_until {
+            Some(closing) if tag.starts_with('/') && name == Some(closing) => skip_until = None,
+            Some(_) => {}
+            None if tag.starts_with('/') => out.push(' '),
+            None => {
+                skip_until = SKIPPED_ELEMENTS.iter().copied().find(|e| Some(*e) == name);
+                out.push(' ');
+            }
+        }
+    }
+
+    if skip_until.is_none() {
+        push_text(&mut out, rest);
+    }
+
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The content of a document's `<title>` element.
+pub fn title(html: &str) -> Option<String> {
+    let start = html.find("<title>")? + "<title>".len();
+    let end = html[start..].find("</title>")? + start;
+
+    let mut out = String::new();
+    push_text(&mut out, &html[start..end]);
+    Some(out.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Appends text from between tags, decoding character references.
+fn push_text(out: &mut String, text: &str) {
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                entity => {
+                    let code = match entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                    {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+}
+
+/// Turns heading text into a value usable as an element ID and URI fragment.
+pub fn slug(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+
+    out.trim_matches('-').to_string()
+}
+
+/// Hands out unique slugs for the headings of one document.
+#[derive(Debug, Default)]
+pub struct SlugSet {
+    used: std::collections::HashMap<String, usize>,
+}
+
+impl SlugSet {
+    pub fn unique(&mut self, text: &str) -> String {
+        let base = match slug(text) {
+            s if s.is_empty() => "section".to_string(),
+            s => s,
+        };
+
+        let count = self.used.entry(base.clone()).or_insert(0);
+        *count += 1;
+
+        match *count {
+            1 => base,
+            n => format!("{}-{}", base, n),
+        }
+    }
+}
+
+/// Wraps a document body into a complete page using the shared document stylesheet.
+pub fn document(title: &str, body_class: &str, body: &str) -> String {
+    let mut out = String::new();
+
+    let _ = write!(
+        out,
+        concat!(
+            "<!DOCTYPE html>\n",
+            "<html>\n<head>\n",
+            "<meta charset=\"utf-8\">\n",
+            "<title>{}</title>\n",
+            "<style>\n{}</style>\n",
+            "</head>\n",
+            "<body class=\"{}\">\n{}\n</body>\n</html>\n",
+        ),
+        escape(title),
+        DOCUMENT_STYLESHEET,
+        body_class,
+        body,
+    );
+
+    out
+}
+
+/// Page shown in place of a document that could not be loaded.
+pub fn error_page(title: &str, message: &str) -> String {
+    let body = format!(
+        "<main class=\"error\">\n<h1>{}</h1>\n<p>{}</p>\n</main>",
+        escape(title),
+        escape(message),
+    );
+
+    document(title, "error", &body)
+}
@@ -0,0 +1,1097 @@
+//! Reading GNU Info files and rendering their nodes for the `info:` URI scheme.
+//!
+//! Each node is its own page, so moving between nodes goes through the web
+//! view's back/forward list just like following any other link.
+
+use std::{
+    collections::HashSet,
+    env, fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use regex::{Captures, Regex};
+
+use crate::{
+    decompress,
+    html::{self, SlugSet},
+};
+
+/// Directories searched after `INFOPATH`, or instead of it when it is unset.
+const DEFAULT_INFO_PATH: &[&str] = &[
+    "/usr/local/share/info",
+    "/usr/share/info",
+    "/usr/local/info",
+    "/usr/info",
+];
+
+/// Separates nodes and the tables at the end of an Info file.
+const SEPARATOR: char = '\x1f';
+
+/// How much of a manual to read when looking for its directory entries.
+const PREAMBLE_LIMIT: u64 = 16 * 1024;
+
+/// Marks the start and end of special sequences like index entries and images.
+const MARKER_START: &str = "\0\x08[";
+const MARKER_END: &str = "\0\x08]";
+
+/// A node in an Info manual, as written in `(file)node` references.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeRef {
+    pub file: String,
+    pub node: String,
+}
+
+impl NodeRef {
+    pub fn new(file: &str, node: &str) -> NodeRef {
+        let node = node.trim();
+
+        NodeRef {
+            file: file.trim().to_string(),
+            node: if node.is_empty() {
+                "Top".to_string()
+            } else {
+                node.to_string()
+            },
+        }
+    }
+
+    /// Parses a reference as written in Info files: `(file)node`, `(file)` or just `node`,
+    /// which is in `current_file`.
+    pub fn parse(text: &str, current_file: &str) -> NodeRef {
+        let text = text.trim();
+
+        match text.strip_prefix('(').and_then(|t| t.split_once(')')) {
+            Some((file, node)) => NodeRef::new(file, node),
+            None => NodeRef::new(current_file, text),
+        }
+    }
+
+    /// Parses everything after `info:`: `(file)node`, or a bare `file` for its Top node.
+    pub fn from_uri_path(path: &str) -> Option<NodeRef> {
+        let path = path.trim_start_matches("//").trim();
+
+        match path.starts_with('(') {
+            true => Some(NodeRef::parse(path, "")).filter(|r| !r.file.is_empty()),
+            false => (!path.is_empty()).then(|| NodeRef::new(path, "Top")),
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        match self.node.as_str() {
+            "Top" => format!("info:{}", html::escape_uri_component(&self.file)),
+            node => format!(
+                "info:({}){}",
+                html::escape_uri_component(&self.file),
+                html::escape_uri_component(node)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}){}", self.file, self.node)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    FileNotFound(String),
+    NodeNotFound(NodeRef),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::FileNotFound(file) => write!(f, "No Info manual named {}", file),
+            Error::NodeNotFound(node) => write!(f, "No node {} in the manual", node),
+            Error::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Directories holding Info files, from `INFOPATH` or the usual defaults.
+///
+/// An empty component in `INFOPATH`, such as a trailing colon, stands for the defaults.
+pub fn info_path() -> Vec<PathBuf> {
+    let defaults = || {
+        DEFAULT_INFO_PATH
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+    };
+
+    let mut paths = match env::var_os("INFOPATH").filter(|v| !v.is_empty()) {
+        Some(var) => env::split_paths(&var)
+            .flat_map(|component| match component.as_os_str().is_empty() {
+                true => defaults(),
+                false => vec![component],
+            })
+            .collect(),
+        None => defaults(),
+    };
+
+    let mut seen = HashSet::new();
+    paths.retain(|p| p.is_dir() && seen.insert(p.canonicalize().unwrap_or_else(|_| p.clone())));
+    paths
+}
+
+/// Finds the main file of a manual by name, the way info(1) does.
+pub fn find_file(name: &str) -> Option<PathBuf> {
+    if name.starts_with('/') {
+        return decompress::find_variant(Path::new(name));
+    }
+
+    let lower = name.to_lowercase();
+    let candidates = [
+        format!("{}.info", name),
+        name.to_string(),
+        format!("{}-info", name),
+        format!("{}.info", lower),
+        lower.clone(),
+    ];
+
+    info_path().iter().find_map(|dir| {
+        candidates
+            .iter()
+            .filter_map(|candidate| decompress::find_variant(&dir.join(candidate)))
+            .find(|path| path.is_file())
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Node,
+    Anchor,
+}
+
+#[derive(Debug, Clone)]
+struct Tag {
+    kind: TagKind,
+    name: String,
+    offset: usize,
+}
+
+/// A manual, possibly split into several subfiles.
+#[derive(Debug)]
+pub struct InfoFile {
+    /// The name used in references to this manual, like `find`.
+    pub name: String,
+    path: PathBuf,
+    text: String,
+    /// Subfiles from the indirect table, with the offset of each in the whole manual.
+    subfiles: Vec<(String, usize)>,
+    tags: Vec<Tag>,
+}
+
+impl InfoFile {
+    pub fn open(name: &str) -> Result<InfoFile, Error> {
+        let path = find_file(name).ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        let text = read_file(&path)?;
+
+        let mut file = InfoFile {
+            name: name.to_string(),
+            path,
+            text,
+            subfiles: Vec::new(),
+            tags: Vec::new(),
+        };
+        file.read_tables();
+
+        Ok(file)
+    }
+
+    /// Reads the indirect table and the tag table, whichever are present.
+    fn read_tables(&mut self) {
+        for part in self.text.split(SEPARATOR) {
+            let part = part.trim_start_matches(['\n', '\x0c']);
+
+            if let Some(table) = part.strip_prefix("Indirect:") {
+                self.subfiles = table
+                    .lines()
+                    .filter_map(|line| line.rsplit_once(": "))
+                    .filter_map(|(file, offset)| {
+                        Some((file.to_string(), offset.trim().parse().ok()?))
+                    })
+                    .collect();
+            } else if let Some(table) = part.strip_prefix("Tag Table:") {
+                self.tags = table.lines().filter_map(parse_tag).collect();
+            }
+        }
+    }
+
+    /// Finds a node or anchor, returning the node and the anchor within it, if any.
+    pub fn node(&self, name: &str) -> Result<(Node, Option<String>), Error> {
+        let not_found = || Error::NodeNotFound(NodeRef::new(&self.name, name));
+
+        let tag = self.tags.iter().find(|tag| tag.name == name).or_else(|| {
+            self.tags
+                .iter()
+                .find(|tag| tag.name.eq_ignore_ascii_case(name))
+        });
+
+        // Anchors belong to the last node that starts before them.
+        let (node_name, anchor) = match tag {
+            Some(Tag {
+                kind: TagKind::Anchor,
+                offset,
+                name: anchor,
+            }) => {
+                let node = self
+                    .tags
+                    .iter()
+                    .filter(|t| t.kind == TagKind::Node && t.offset <= *offset)
+                    .max_by_key(|t| t.offset)
+                    .ok_or_else(not_found)?;
+
+                (
+                    node.name.as_str(),
+                    Some((anchor.clone(), offset - node.offset)),
+                )
+            }
+            Some(tag) => (tag.name.as_str(), None),
+            None => (name, None),
+        };
+
+        let mut node = self
+            .find_node(node_name, tag.map(|t| t.offset))?
+            .ok_or_else(not_found)?;
+
+        let anchor = anchor.map(|(anchor, distance)| {
+            node.insert_anchor(&anchor, distance);
+            anchor
+        });
+
+        Ok((node, anchor))
+    }
+
+    /// Every node of the manual, in order, reading subfiles as needed.
+    pub fn nodes(&self) -> Result<Vec<Node>, Error> {
+        if self.subfiles.is_empty() {
+            return Ok(parse_nodes(&self.text).collect());
+        }
+
+        let mut nodes = Vec::new();
+
+        for (subfile, _) in &self.subfiles {
+            nodes.extend(parse_nodes(&read_file(&self.subfile_path(subfile))?));
+        }
+
+        Ok(nodes)
+    }
+
+    /// Looks for a node, starting with the subfile the tag table points at.
+    fn find_node(&self, name: &str, offset: Option<usize>) -> Result<Option<Node>, Error> {
+        if self.subfiles.is_empty() {
+            return Ok(find_in_text(&self.text, name));
+        }
+
+        let mut subfiles: Vec<&(String, usize)> = self.subfiles.iter().collect();
+
+        if let Some(offset) = offset
+            && let Some(i) = subfiles.iter().rposition(|(_, start)| *start <= offset)
+        {
+            let subfile = subfiles.remove(i);
+            subfiles.insert(0, subfile);
+        }
+
+        for (subfile, _) in subfiles {
+            let text = read_file(&self.subfile_path(subfile))?;
+
+            if let Some(node) = find_in_text(&text, name) {
+                return Ok(Some(node));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Subfiles are next to the main file, and may be compressed even when it isn't.
+    fn subfile_path(&self, subfile: &str) -> PathBuf {
+        let dir = self.path.parent().unwrap_or(Path::new("/"));
+        decompress::find_variant(&dir.join(subfile)).unwrap_or_else(|| dir.join(subfile))
+    }
+}
+
+fn parse_tag(line: &str) -> Option<Tag> {
+    let (kind, rest) = match line.split_once(": ")? {
+        ("Node", rest) => (TagKind::Node, rest),
+        ("Ref", rest) => (TagKind::Anchor, rest),
+        _ => return None,
+    };
+
+    let (name, offset) = rest.rsplit_once('\x7f')?;
+
+    Some(Tag {
+        kind,
+        name: name.to_string(),
+        offset: offset.trim().parse().ok()?,
+    })
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+    decompress::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))
+}
+
+/// One node: its header links and its text.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    pub up: Option<String>,
+    pub text: String,
+    /// Bytes from the separator before the node to the start of `text`.
+    text_offset: usize,
+}
+
+impl Node {
+    /// Parses a node from the text after a separator, if it starts with a node header.
+    fn parse(part: &str) -> Option<Node> {
+        let trimmed = part.trim_start_matches(['\n', '\x0c']);
+        let (header, text) = trimmed.split_once('\n').unwrap_or((trimmed, ""));
+
+        let mut node = Node {
+            name: String::new(),
+            next: None,
+            prev: None,
+            up: None,
+            text: text.to_string(),
+            text_offset: 1 + part.len() - text.len(),
+        };
+
+        for field in header.split([',', '\t']) {
+            let Some((key, value)) = field.trim().split_once(':') else {
+                continue;
+            };
+            let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+
+            match key {
+                "Node" => node.name = value.unwrap_or_default(),
+                "Next" => node.next = value,
+                "Prev" | "Previous" => node.prev = value,
+                "Up" => node.up = value,
+                _ => {}
+            }
+        }
+
+        (!node.name.is_empty()).then_some(node)
+    }
+
+    /// The node text without image, anchor and index markers.
+    pub fn plain_text(&self) -> String {
+        let mut out = String::with_capacity(self.text.len());
+        let mut rest = self.text.as_str();
+
+        while let Some(start) = rest.find(MARKER_START) {
+            out.push_str(&rest[..start]);
+            rest = match rest[start..].find(MARKER_END) {
+                Some(end) => &rest[start + end + MARKER_END.len()..],
+                None => "",
+            };
+        }
+
+        out.push_str(rest);
+        out
+    }
+
+    /// Marks the place of an anchor, given its distance from the start of the node.
+    fn insert_anchor(&mut self, name: &str, distance: usize) {
+        let mut position = distance
+            .saturating_sub(self.text_offset)
+            .min(self.text.len());
+        while !self.text.is_char_boundary(position) {
+            position -= 1;
+        }
+
+        // Anchors are usually followed by a heading or paragraph, so keep them at a line start.
+        let position = self.text[..position].rfind('\n').map_or(0, |i| i + 1);
+
+        self.text.insert_str(
+            position,
+            &format!("{}anchor {}{}", MARKER_START, name, MARKER_END),
+        );
+    }
+}
+
+fn parse_nodes(text: &str) -> impl Iterator<Item = Node> {
+    text.split(SEPARATOR)
+        .filter(|part| part.contains("Node:"))
+        .filter_map(Node::parse)
+}
+
+fn find_in_text(text: &str, name: &str) -> Option<Node> {
+    parse_nodes(text)
+        .find(|node| node.name == name)
+        .or_else(|| parse_nodes(text).find(|node| node.name.eq_ignore_ascii_case(name)))
+}
+
+static INLINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?m)",
+        // `* Node::` menu entries.
+        r"^\*[ ](?P<menu>[^:\n]+?)::",
+        // `* Label: (file)Node.` menu entries.
+        r"|^\*[ ](?P<label>[^:\n]+?):[ \t]+(?P<target>(?:\([^)\n]*\))?[^.,\t\n]*)(?P<end>\.|,|\t|$)",
+        // `*Note Node::` cross references.
+        r"|\*(?P<note>[Nn]ote)[ \n]+(?P<note_node>[^:]{1,200}?)::",
+        // `*Note Label: (file)Node.` cross references.
+        r"|\*(?P<note2>[Nn]ote)[ \n]+(?P<note_label>[^:]{1,200}?):[ \n]+",
+        r"(?P<note_target>(?:\([^)]*\))?[^.,]*)(?P<note_end>[.,])",
+        // Images, anchors and index markers.
+        r"|\x00\x08\[(?P<marker>[^\x00]*)\x00\x08\]",
+    ))
+    .unwrap()
+});
+
+static IMAGE_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(\w+)="((?:[^"\\]|\\.)*)""#).unwrap());
+
+/// Characters that underline headings, from chapters down to subsubsections.
+const HEADING_UNDERLINES: &[char] = &['*', '=', '-', '.'];
+
+/// Renders a node into a complete HTML document.
+pub fn render_node(file: &InfoFile, node: &Node, anchor: Option<&str>) -> String {
+    let link = |rel: &str, label: &str, target: &Option<String>| match target {
+        Some(target) => format!(
+            "<span>{}: <a rel=\"{}\" href=\"{}\">{}</a></span>",
+            label,
+            rel,
+            html::escape(&NodeRef::parse(target, &file.name).uri()),
+            html::escape(target),
+        ),
+        None => String::new(),
+    };
+
+    let nav = format!(
+        "<nav class=\"info-nav\">{}{}{}</nav>",
+        link("next", "Next", &node.next),
+        link("prev", "Prev", &node.prev),
+        link("up", "Up", &node.up),
+    );
+
+    let top = NodeRef::new(&file.name, "Top").uri();
+    let scroll = match anchor {
+        Some(anchor) => format!(
+            "document.getElementById(\"{}\")?.scrollIntoView();",
+            html::slug(anchor)
+        ),
+        None => String::new(),
+    };
+
+    let body = format!(
+        concat!(
+            "{nav}\n<article class=\"info-node\">\n{text}</article>\n{nav}\n",
+            "<script>\n{scroll}\n",
+            "document.addEventListener('keydown', (event) => {{\n",
+            "    if (event.ctrlKey || event.altKey || event.metaKey) return;\n",
+            "    const rel = {{ n: 'next', p: 'prev', u: 'up' }}[event.key];\n",
+            "    const link = rel && document.querySelector(`a[rel=\"${{rel}}\"]`);\n",
+            "    if (link) location.href = link.href;\n",
+            "    else if (event.key === 't') location.href = \"{top}\";\n",
+            "    else if (event.key === 'l') history.back();\n",
+            "    else if (event.key === 'r') history.forward();\n",
+            "}});\n",
+            "</script>",
+        ),
+        nav = nav,
+        text = render_text(file, &node.text),
+        scroll = scroll,
+        top = top,
+    );
+
+    let title = format!("{} ({})", node.name, file.name);
+    html::document(&title, "info", &body)
+}
+
+/// Turns node text into headings and preformatted blocks with links.
+fn render_text(file: &InfoFile, text: &str) -> String {
+    let mut out = String::new();
+    let mut slugs = SlugSet::default();
+    let mut block = String::new();
+
+    // makeinfo marks the menus of index nodes.
+    let is_index = text.contains(&format!("{}index{}", MARKER_START, MARKER_END));
+
+    let flush = |block: &mut String, out: &mut String| {
+        let trimmed = block.trim_matches('\n');
+        if !trimmed.is_empty() {
+            out.push_str(&format!(
+                "<pre>{}</pre>\n",
+                render_inline(file, trimmed, is_index)
+            ));
+        }
+        block.clear();
+    };
+
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let underline = lines.peek().and_then(|next| heading_level(line, next));
+
+        match underline {
+            Some(level) => {
+                lines.next();
+                flush(&mut block, &mut out);
+
+                let title = line.trim();
+                out.push_str(&format!(
+                    "<h{level} id=\"{}\">{}</h{level}>\n",
+                    slugs.unique(title),
+                    html::escape(title),
+                    level = level,
+                ));
+            }
+            None => {
+                block.push_str(line);
+                block.push('\n');
+            }
+        }
+    }
+
+    flush(&mut block, &mut out);
+    out
+}
+
+/// The heading level of `line` if `next` underlines it.
+fn heading_level(line: &str, next: &str) -> Option<usize> {
+    let c = next.chars().next()?;
+    let level = HEADING_UNDERLINES.iter().position(|u| *u == c)? + 1;
+
+    let underlined = next.chars().all(|n| n == c)
+        && next.chars().count() == line.chars().count()
+        && !line.trim().is_empty();
+
+    underlined.then_some(level)
+}
+
+/// Menu entries of index nodes get the `index-entry` class, for the outline.
+fn render_inline(file: &InfoFile, text: &str, is_index: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+
+    let href = |target: &str| {
+        let target = target.split_whitespace().collect::<Vec<_>>().join(" ");
+        html::escape(&NodeRef::parse(&target, &file.name).uri())
+    };
+
+    for caps in INLINE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&html::escape(&text[last..whole.start()]));
+        last = whole.end();
+
+        let group = |name: &str| caps.name(name).map_or("", |m| m.as_str());
+
+        if let Some(menu) = caps.name("menu") {
+            let menu = menu.as_str();
+            out.push_str(&format!(
+                "* <a href=\"{}\">{}</a>::",
+                href(menu),
+                html::escape(menu)
+            ));
+        } else if let Some(label) = caps.name("label") {
+            out.push_str(&format!(
+                "* <a{} href=\"{}\">{}</a>: <span class=\"target\">{}</span>{}",
+                if is_index {
+                    " class=\"index-entry\""
+                } else {
+                    ""
+                },
+                href(group("target")),
+                html::escape(label.as_str()),
+                html::escape(group("target")),
+                html::escape(group("end")),
+            ));
+        } else if let Some(note) = caps.name("note").or(caps.name("note2")) {
+            let see = if note.as_str().starts_with('N') {
+                "See"
+            } else {
+                "see"
+            };
+            let (label, target, end) = match caps.name("note_node") {
+                Some(node) => (node.as_str(), node.as_str(), ""),
+                None => (group("note_label"), group("note_target"), group("note_end")),
+            };
+
+            out.push_str(&format!(
+                "{} <a href=\"{}\">{}</a>{}",
+                see,
+                href(target),
+                html::escape(label),
+                end,
+            ));
+        } else if let Some(marker) = caps.name("marker") {
+            out.push_str(&render_marker(file, marker.as_str()));
+        }
+    }
+
+    out.push_str(&html::escape(&text[last..]));
+    out
+}
+
+/// Renders the special sequences makeinfo leaves in the text; index markers disappear.
+fn render_marker(file: &InfoFile, marker: &str) -> String {
+    if let Some(name) = marker.strip_prefix("anchor ") {
+        return format!("<span id=\"{}\"></span>", html::slug(name));
+    }
+
+    let Some(attributes) = marker.strip_prefix("image ") else {
+        return String::new();
+    };
+
+    let attribute = |key: &str| {
+        IMAGE_ATTRIBUTE
+            .captures_iter(attributes)
+            .find(|caps: &Captures| &caps[1] == key)
+            .map(|caps| caps[2].replace("\\\"", "\"").replace("\\\\", "\\"))
+    };
+
+    let Some(src) = attribute("src") else {
+        return String::new();
+    };
+    let dir = file.path.parent().unwrap_or(Path::new("/"));
+
+    format!(
+        "<img src=\"docfile://{}\" alt=\"{}\">",
+        html::escape(&dir.join(src).to_string_lossy()),
+        html::escape(
+            &attribute("alt")
+                .or_else(|| attribute("text"))
+                .unwrap_or_default()
+        ),
+    )
+}
+
+/// A manual listed in the Info directory.
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub label: String,
+    pub target: NodeRef,
+    pub description: String,
+}
+
+/// A heading of the Info directory and the manuals under it.
+#[derive(Debug, Clone)]
+pub struct DirCategory {
+    pub name: String,
+    pub entries: Vec<DirEntry>,
+}
+
+/// The top-level `(dir)Top` node, merged from every `dir` file in the Info path.
+///
+/// Manuals that no `dir` file mentions are added from their own
+/// `INFO-DIR-SECTION` blocks, so systems without install-info still list them.
+#[derive(Debug, Clone, Default)]
+pub struct Directory {
+    pub categories: Vec<DirCategory>,
+}
+
+static DIR_ENTRY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"^\*\s*(?P<label>[^:]+?):\s*",
+        r"(?P<target>\([^)]*\)[^.,\t]*)[.,\t]?",
+        r"\s*(?P<description>.*)$",
+    ))
+    .unwrap()
+});
+
+impl Directory {
+    pub fn load() -> Directory {
+        let mut directory = Directory::default();
+        let paths = info_path();
+
+        for dir in &paths {
+            let Some(path) = decompress::find_variant(&dir.join("dir")) else {
+                continue;
+            };
+            let Ok(text) = decompress::read_to_string(&path) else {
+                continue;
+            };
+
+            let menu = find_in_text(&text, "Top")
+                .map(|node| node.text)
+                .unwrap_or_default();
+            directory.add_menu(&menu, "* Menu:", None);
+        }
+
+        let listed: HashSet<String> = directory
+            .categories
+            .iter()
+            .flat_map(|category| &category.entries)
+            .map(|entry| manual_name(&entry.target.file))
+            .collect();
+
+        for dir in &paths {
+            let Ok(files) = fs::read_dir(dir) else {
+                continue;
+            };
+
+            for file in files.flatten() {
+                let file_name = file.file_name();
+                let file_name = file_name.to_string_lossy();
+
+                if !is_main_file(&file_name) || listed.contains(&manual_name(&file_name)) {
+                    continue;
+                }
+
+                let Ok(preamble) = read_preamble(&file.path()) else {
+                    continue;
+                };
+                directory.add_menu(
+                    &preamble,
+                    "START-INFO-DIR-ENTRY",
+                    Some("END-INFO-DIR-ENTRY"),
+                );
+            }
+        }
+
+        for category in &mut directory.categories {
+            category
+                .entries
+                .sort_by_key(|entry| entry.label.to_lowercase());
+        }
+        directory
+            .categories
+            .sort_by_key(|category| category.name.to_lowercase());
+
+        directory
+    }
+
+    /// Adds the entries of a menu, either the one in a `dir` node or the
+    /// `START-INFO-DIR-ENTRY` blocks in a manual's preamble.
+    fn add_menu(&mut self, text: &str, start: &str, end: Option<&str>) {
+        let mut category = "Miscellaneous".to_string();
+        let mut in_menu = false;
+        let mut last: Option<(String, usize)> = None;
+
+        for line in text.lines() {
+            if let Some(name) = line.strip_prefix("INFO-DIR-SECTION ") {
+                category = name.trim().to_string();
+                continue;
+            }
+
+            if !in_menu {
+                in_menu = line.starts_with(start);
+                continue;
+            }
+
+            if end.is_some_and(|end| line.starts_with(end)) {
+                in_menu = false;
+                last = None;
+            } else if let Some(caps) = DIR_ENTRY.captures(line) {
+                let entry = DirEntry {
+                    label: caps["label"].trim().to_string(),
+                    target: NodeRef::parse(&caps["target"], ""),
+                    description: caps["description"].trim().to_string(),
+                };
+
+                last = self.add(&category, entry);
+            } else if line.starts_with([' ', '\t']) {
+                // Long descriptions continue on indented lines.
+                if let Some((category, index)) = &last
+                    && let Some(entry) = self.category_mut(category).entries.get_mut(*index)
+                {
+                    entry.description.push(' ');
+                    entry.description.push_str(line.trim());
+                }
+            } else if !line.trim().is_empty() && end.is_none() {
+                category = line.trim().to_string();
+                last = None;
+            }
+        }
+    }
+
+    /// Adds an entry unless it is already listed, returning where it went.
+    fn add(&mut self, category: &str, entry: DirEntry) -> Option<(String, usize)> {
+        let category = self.category_mut(category);
+
+        let duplicate = category
+            .entries
+            .iter()
+            .any(|e| e.label.eq_ignore_ascii_case(&entry.label) && e.target == entry.target);
+
+        if duplicate {
+            return None;
+        }
+
+        category.entries.push(entry);
+        Some((category.name.clone(), category.entries.len() - 1))
+    }
+
+    fn category_mut(&mut self, name: &str) -> &mut DirCategory {
+        let index = match self
+            .categories
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))
+        {
+            Some(index) => index,
+            None => {
+                self.categories.push(DirCategory {
+                    name: name.to_string(),
+                    entries: Vec::new(),
+                });
+                self.categories.len() - 1
+            }
+        };
+
+        &mut self.categories[index]
+    }
+}
+
+/// The manual a file or reference belongs to: `find.info-1.gz` and `find` are both `find`.
+fn manual_name(file_name: &str) -> String {
+    let name = decompress::strip_extension(file_name);
+    let name = name.strip_suffix(".info").unwrap_or(name);
+    name.to_lowercase()
+}
+
+/// Whether a file in an Info directory is the main file of a manual, not a subfile or image.
+fn is_main_file(file_name: &str) -> bool {
+    let name = decompress::strip_extension(file_name);
+
+    let is_subfile = name
+        .rsplit_once('-')
+        .is_some_and(|(_, n)| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+
+    !is_subfile && !name.starts_with("dir") && (name.ends_with(".info") || !name.contains('.'))
+}
+
+/// Reads the start of a manual, which holds its directory entries.
+fn read_preamble(path: &Path) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    decompress::open(path)?
+        .take(PREAMBLE_LIMIT)
+        .read_to_end(&mut bytes)?;
+
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(text.split(SEPARATOR).next().unwrap_or_default().to_string())
+}
+
+/// Renders the merged directory as the `(dir)Top` node.
+pub fn render_directory(directory: &Directory) -> String {
+    let mut body = String::from("<article class=\"info-node\">\n<h1>Info Directory</h1>\n");
+    let mut slugs = SlugSet::default();
+
+    for category in &directory.categories {
+        body.push_str(&format!(
+            "<h2 id=\"{}\">{}</h2>\n<dl>\n",
+            slugs.unique(&category.name),
+            html::escape(&category.name)
+        ));
+
+        for entry in &category.entries {
+            body.push_str(&format!(
+                "<dt><a href=\"{}\">{}</a></dt><dd>{}</dd>\n",
+                html::escape(&entry.target.uri()),
+                html::escape(&entry.label),
+                html::escape(&entry.description),
+            ));
+        }
+
+        body.push_str("</dl>\n");
+    }
+
+    body.push_str("</article>");
+    html::document("Info Directory", "info", &body)
+}
+
+/// Builds the response for an `info:` URI, given everything after the scheme.
+pub fn page_for_uri(path: &str) -> String {
+    let result = NodeRef::from_uri_path(path)
+        .ok_or_else(|| Error::FileNotFound(path.to_string()))
+        .and_then(|target| {
+            if target.file == "dir" && target.node == "Top" {
+                return Ok(render_directory(&Directory::load()));
+            }
+
+            let file = InfoFile::open(&target.file)?;
+            let (node, anchor) = file.node(&target.node)?;
+            Ok(render_node(&file, &node, anchor.as_deref()))
+        });
+
+    result.unwrap_or_else(|e| {
+        let title = match e {
+            Error::FileNotFound(_) => "Manual not found",
+            Error::NodeNotFound(_) => "Node not found",
+            Error::Io(..) => "Could not open manual",
+        };
+        html::error_page(title, &e.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREAMBLE: &str = "This is foo.info, produced by makeinfo.\n";
+    const TOP: &str = "\x1f\nFile: foo.info,  Node: Top,  Next: Intro,  Up: (dir)\n\nTop text.\n";
+    const INTRO: &str =
+        "\x1f\nFile: foo.info,  Node: Intro,  Prev: Top,  Up: Top\n\nFirst line.\nSecond line.\n";
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("docviewer-info-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn info_file(path: PathBuf) -> InfoFile {
+        let mut file = InfoFile {
+            name: "foo".to_string(),
+            text: read_file(&path).unwrap(),
+            path,
+            subfiles: Vec::new(),
+            tags: Vec::new(),
+        };
+        file.read_tables();
+        file
+    }
+
+    /// Where the anchor `second` sits in the whole manual, given where its node starts.
+    fn anchor_offset(node_offset: usize) -> usize {
+        node_offset + INTRO.find("Second").unwrap()
+    }
+
+    #[test]
+    fn headers() {
+        let node = Node::parse(&INTRO[1..]).unwrap();
+
+        assert_eq!(node.name, "Intro");
+        assert_eq!(node.prev.as_deref(), Some("Top"));
+        assert_eq!(node.up.as_deref(), Some("Top"));
+        assert_eq!(node.next, None);
+        assert_eq!(node.text, "\nFirst line.\nSecond line.\n");
+
+        assert!(Node::parse("\nTag Table:\nNode: Top\x7f10\n").is_none());
+    }
+
+    #[test]
+    fn tags() {
+        let tag = parse_tag("Node: Top\x7f42").unwrap();
+        assert_eq!(
+            (tag.kind, tag.name.as_str(), tag.offset),
+            (TagKind::Node, "Top", 42)
+        );
+
+        let tag = parse_tag("Ref: a: b\x7f7").unwrap();
+        assert_eq!(
+            (tag.kind, tag.name.as_str(), tag.offset),
+            (TagKind::Anchor, "a: b", 7)
+        );
+
+        assert!(parse_tag("(Indirect)").is_none());
+        assert!(parse_tag("Node: Top").is_none());
+    }
+
+    #[test]
+    fn single_file_with_a_tag_table() {
+        let dir = temp_dir("single");
+        let top = PREAMBLE.len();
+        let intro = top + TOP.len();
+        let text = format!(
+            "{PREAMBLE}{TOP}{INTRO}\x1f\nTag Table:\nNode: Top\x7f{top}\nNode: Intro\x7f{intro}\n\
+             Ref: second\x7f{}\n\x1f\nEnd Tag Table\n",
+            anchor_offset(intro),
+        );
+        fs::write(dir.join("foo.info"), text).unwrap();
+        let file = info_file(dir.join("foo.info"));
+
+        let (node, anchor) = file.node("intro").unwrap();
+        assert_eq!((node.name.as_str(), anchor), ("Intro", None));
+
+        let (node, anchor) = file.node("second").unwrap();
+        assert_eq!(node.name, "Intro");
+        assert_eq!(anchor.as_deref(), Some("second"));
+        assert_eq!(
+            node.text,
+            "\nFirst line.\n\0\x08[anchor second\0\x08]Second line.\n"
+        );
+        assert_eq!(node.plain_text(), "\nFirst line.\nSecond line.\n");
+
+        assert!(matches!(file.node("Missing"), Err(Error::NodeNotFound(_))));
+    }
+
+    #[test]
+    fn indirect_files() {
+        let dir = temp_dir("indirect");
+        fs::write(dir.join("foo.info-1"), format!("{PREAMBLE}{TOP}")).unwrap();
+        fs::write(dir.join("foo.info-2"), format!("{PREAMBLE}{INTRO}")).unwrap();
+
+        // Offsets count as if the subfiles, minus their preambles, were one file.
+        let top = 100;
+        let intro = top + TOP.len();
+        let text = format!(
+            "{PREAMBLE}\x1f\nIndirect:\nfoo.info-1: {top}\nfoo.info-2: {intro}\n\
+             \x1f\nTag Table:\n(Indirect)\nNode: Top\x7f{top}\nNode: Intro\x7f{intro}\n\
+             Ref: second\x7f{}\n\x1f\nEnd Tag Table\n",
+            anchor_offset(intro),
+        );
+        fs::write(dir.join("foo.info"), text).unwrap();
+        let file = info_file(dir.join("foo.info"));
+
+        assert_eq!(
+            file.subfiles,
+            [
+                ("foo.info-1".to_string(), top),
+                ("foo.info-2".to_string(), intro)
+            ]
+        );
+
+        let names: Vec<String> = file.nodes().unwrap().into_iter().map(|n| n.name).collect();
+        assert_eq!(names, ["Top", "Intro"]);
+
+        assert_eq!(file.node("Top").unwrap().0.text, "\nTop text.\n");
+        let (node, anchor) = file.node("second").unwrap();
+        assert_eq!(node.name, "Intro");
+        assert_eq!(anchor.as_deref(), Some("second"));
+        assert!(
+            node.text
+                .contains("\0\x08[anchor second\0\x08]Second line.")
+        );
+    }
+
+    #[test]
+    fn subfiles_may_be_compressed() {
+        let dir = temp_dir("compressed");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        io::Write::write_all(&mut encoder, format!("{PREAMBLE}{TOP}").as_bytes()).unwrap();
+        fs::write(dir.join("foo.info-1.gz"), encoder.finish().unwrap()).unwrap();
+        fs::write(
+            dir.join("foo.info"),
+            format!("{PREAMBLE}\x1f\nIndirect:\nfoo.info-1: 100\n"),
+        )
+        .unwrap();
+
+        let file = info_file(dir.join("foo.info"));
+        assert_eq!(file.node("Top").unwrap().0.text, "\nTop text.\n");
+    }
+
+    #[test]
+    fn references() {
+        assert_eq!(
+            NodeRef::parse("(coreutils)ls invocation", "find"),
+            NodeRef::new("coreutils", "ls invocation")
+        );
+        assert_eq!(
+            NodeRef::parse("(coreutils)", "find"),
+            NodeRef::new("coreutils", "Top")
+        );
+        assert_eq!(
+            NodeRef::parse("Options", "find"),
+            NodeRef::new("find", "Options")
+        );
+
+        assert_eq!(
+            NodeRef::from_uri_path("find"),
+            Some(NodeRef::new("find", "Top"))
+        );
+        assert_eq!(NodeRef::from_uri_path("()Top"), None);
+        assert_eq!(NodeRef::new("find", "Top").uri(), "info:find");
+    }
+}
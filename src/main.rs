@@ -1,44 +1,52 @@
-use std::{collections::HashSet, convert::identity};
+mod bookmarks;
+mod cli;
+mod decompress;
+mod devhelp;
+mod find;
+mod gidocgen;
+mod history;
+mod home;
+mod html;
+mod info;
+mod man;
+mod outline;
+mod roff;
+mod schemes;
+mod search;
+mod session;
+mod settings;
+mod store;
+mod symbols;
+mod whatis;
+mod xdg;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    convert::identity,
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+};
 
 use adw::{gdk::Display, prelude::*};
 use glib::VariantDict;
-use relm4::{MessageBroker, prelude::*, typed_view::list::*};
-use serde::Deserialize;
+use gtk::gio;
+use relm4::{
+    actions::{AccelsPlus, ActionName, ActionablePlus, EmptyType, RelmAction, RelmActionGroup},
+    prelude::*,
+    typed_view::list::*,
+};
 use webkit6::{prelude::*, LoadEvent, WebView};
 
 use gtk::Orientation;
 
 type Nothing = ();
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct ManPageID {
-    name: String,
-    sections: HashSet<String>,
-}
-
-#[derive(Deserialize, Debug, Clone, Hash)]
-struct HTMLHeading {
-    tag_name: String,
-    inner_text: String,
-    index: usize,
-    id: Option<String>,
-}
-
-impl HTMLHeading {
-    fn indent_levels(&self) -> usize {
-        match self.tag_name.to_lowercase().as_str() {
-            "h1" => 0,
-            "h2" => 1,
-            "h3" => 2,
-            "h4" => 3,
-            "h5" => 4,
-            "h6" => 5,
-            _ => 0,
-        }
-    }
-}
+type Outline = Vec<outline::Entry>;
 
-type Outline = Vec<HTMLHeading>;
+/// A page's outline, `None` until the page has posted one, or why it couldn't be read.
+type PageOutline = Result<Option<Outline>, outline::Error>;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 struct NavigationState {
@@ -52,12 +60,56 @@ enum WebPaneMsg {
     GoForward,
     UpdateNavState,
     UpdatedURI(String),
-    SelectedHeading(HTMLHeading),
+    SelectedHeading(outline::Entry),
     LoadFinished,
+    Find(find::Query),
+    FindNext,
+    FindPrevious,
+    FinishFind,
+    Zoom(Zoom),
+    Reload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zoom {
+    In,
+    Out,
+    Reset,
+}
+
+/// The zoom levels that zooming in and out steps through.
+const ZOOM_LEVELS: &[f64] = &[
+    0.3, 0.5, 0.67, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0,
+];
+
+impl Zoom {
+    fn apply(self, level: f64) -> f64 {
+        match self {
+            Zoom::In => ZOOM_LEVELS
+                .iter()
+                .copied()
+                .find(|&l| l > level + 0.01)
+                .unwrap_or(level),
+            Zoom::Out => ZOOM_LEVELS
+                .iter()
+                .rev()
+                .copied()
+                .find(|&l| l < level - 0.01)
+                .unwrap_or(level),
+            Zoom::Reset => 1.0,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
-struct WebPaneModel {}
+struct WebPaneModel {
+    /// Where to scroll once a restored page has loaded.
+    pending_scroll: Option<f64>,
+    /// The search in the find bar, while it is open.
+    find_query: Option<find::Query>,
+    /// Whether the search is run by [`find::SCRIPT`] instead of the `FindController`.
+    find_uses_script: bool,
+}
 
 fn get_nav_state(webview: &WebView) -> NavigationState {
     NavigationState {
@@ -68,7 +120,7 @@ fn get_nav_state(webview: &WebView) -> NavigationState {
 
 #[relm4::component(pub, async)]
 impl AsyncComponent for WebPaneModel {
-    type Init = String;
+    type Init = session::TabState;
 
     type Input = WebPaneMsg;
     type Output = TabMsg;
@@ -98,6 +150,8 @@ impl AsyncComponent for WebPaneModel {
                 let _ = sender.output(TabMsg::UpdateLoadState(event));
                 sender.input(WebPaneMsg::UpdateNavState);
 
+                // The new page posts its own outline once it is parsed.
+                if event == LoadEvent::Committed { let _ = sender.output(TabMsg::UpdateOutline(Ok(None))); }
                 if event == LoadEvent::Finished { sender.input(WebPaneMsg::LoadFinished) }
             },
 
@@ -108,19 +162,87 @@ impl AsyncComponent for WebPaneModel {
     }
 
     async fn init(
-        uri: Self::Init,
+        state: Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
-        let model = WebPaneModel {};
+        let model = WebPaneModel {
+            pending_scroll: Some(state.scroll_y).filter(|&y| y > 0.0),
+            find_query: None,
+            find_uses_script: false,
+        };
 
         let widgets = view_output!();
 
         let settings = webkit6::prelude::WebViewExt::settings(&widgets.webview).unwrap();
         settings.set_enable_developer_extras(true);
 
+        // Reports the scroll offset of the page once scrolling pauses.
+        let scroll_script = /* js */ r#"
+            let scrollTimeout = null;
+
+            window.addEventListener('scroll', () => {
+                clearTimeout(scrollTimeout);
+                scrollTimeout = setTimeout(() => {
+                    window.webkit.messageHandlers.scroll.postMessage(window.scrollY);
+                }, 250);
+            }, { passive: true });
+        "#;
+
+        let content_manager = widgets.webview.user_content_manager().unwrap();
+        content_manager.register_script_message_handler("scroll", None);
+        content_manager.add_script(&webkit6::UserScript::new(
+            scroll_script,
+            webkit6::UserContentInjectedFrames::TopFrame,
+            webkit6::UserScriptInjectionTime::End,
+            &[],
+            &[],
+        ));
+
+        let scroll_sender = sender.clone();
+        content_manager.connect_script_message_received(Some("scroll"), move |_, value| {
+            let _ = scroll_sender.output(TabMsg::UpdateScroll(value.to_double()));
+        });
+
+        content_manager.register_script_message_handler("outline", None);
+        content_manager.register_script_message_handler("heading", None);
+        content_manager.add_script(&webkit6::UserScript::new(
+            outline::SCRIPT,
+            webkit6::UserContentInjectedFrames::TopFrame,
+            webkit6::UserScriptInjectionTime::End,
+            &[],
+            &[],
+        ));
+
+        let outline_sender = sender.clone();
+        content_manager.connect_script_message_received(Some("outline"), move |_, value| {
+            let outline = serde_json::from_str::<Outline>(&value.to_str())
+                .map(Some)
+                .map_err(|e| outline::Error::Json(Arc::new(e)));
+            if let Err(e) = &outline {
+                eprintln!("{}", e);
+            }
+            let _ = outline_sender.output(TabMsg::UpdateOutline(outline));
+        });
+
+        let heading_sender = sender.clone();
+        content_manager.connect_script_message_received(Some("heading"), move |_, value| {
+            let index = usize::try_from(value.to_int32()).ok();
+            let _ = heading_sender.output(TabMsg::UpdateCurrentHeading(index));
+        });
+
+        if let Some(find_controller) = widgets.webview.find_controller() {
+            let find_sender = sender.clone();
+            find_controller.connect_counted_matches(move |_, count| {
+                let _ = find_sender.output(TabMsg::UpdateFindStatus(find::Status::Matches {
+                    count,
+                    current: None,
+                }));
+            });
+        }
+
         let _ = &widgets.webview.connect_realize(move |webview| {
-            webview.load_uri(&uri);
+            restore_tab(webview, &state);
         });
 
         AsyncComponentParts { model, widgets }
@@ -138,13 +260,14 @@ impl AsyncComponent for WebPaneModel {
         let webview = &widgets.webview;
         match msg {
             WebPaneMsg::UpdatedURI(new_uri) => {
-                webview.load_uri(&new_uri);
+                webview.load_uri(&schemes::document_uri(&new_uri));
                 sender.input(WebPaneMsg::UpdateNavState);
             }
             WebPaneMsg::SelectedHeading(heading) => {
                 let result = self.try_scroll_to_heading(webview, &heading).await;
                 if let Err(e) = result {
                     eprintln!("Error scrolling to heading: {}", e);
+                    let _ = sender.output(TabMsg::OutlineError(e));
                 }
                 sender.input(WebPaneMsg::UpdateNavState);
             }
@@ -157,100 +280,244 @@ impl AsyncComponent for WebPaneModel {
                 sender.input(WebPaneMsg::UpdateNavState);
             }
             WebPaneMsg::LoadFinished => {
-                let headings = self.get_page_headings(webview).await;
+                if let Some(y) = self.pending_scroll.take()
+                    && let Err(e) = self.scroll_to(webview, y).await
+                {
+                    eprintln!("Error restoring scroll position: {}", e);
+                }
 
-                let _ = sender.output(TabMsg::UpdateOutline(match headings {
-                    Ok(headings) => Some(headings),
-                    Err(e) => {
-                        eprintln!("Error getting headings for {:?}: {}", webview.uri(), e);
-                        None
-                    }
-                }));
+                // Search the new page for whatever the find bar holds.
+                if let Some(query) = self.find_query.clone() {
+                    self.find(webview, query, &sender).await;
+                }
 
                 sender.input(WebPaneMsg::UpdateNavState);
             },
             WebPaneMsg::UpdateNavState => {
                 let _ = sender.output(TabMsg::UpdateNavState(get_nav_state(webview)));
+
+                if let Some(history) = webview.session_state().and_then(|state| state.serialize()) {
+                    let _ =
+                        sender.output(TabMsg::UpdateHistory(session::History(history.to_vec())));
+                }
+            },
+            WebPaneMsg::Find(query) => {
+                self.find(webview, query, &sender).await;
             },
+            WebPaneMsg::FindNext => {
+                if self.find_uses_script {
+                    self.run_find_script(webview, "next", &sender).await;
+                } else if let Some(find_controller) = webview.find_controller() {
+                    find_controller.search_next();
+                }
+            }
+            WebPaneMsg::FindPrevious => {
+                if self.find_uses_script {
+                    self.run_find_script(webview, "previous", &sender).await;
+                } else if let Some(find_controller) = webview.find_controller() {
+                    find_controller.search_previous();
+                }
+            }
+            WebPaneMsg::FinishFind => {
+                self.finish_find(webview).await;
+                self.find_query = None;
+            }
+            WebPaneMsg::Zoom(zoom) => {
+                webview.set_zoom_level(zoom.apply(webview.zoom_level()));
+            }
+            WebPaneMsg::Reload => {
+                webview.reload();
+            }
+        }
+    }
+}
+
+/// Loads a tab's page, bringing back its back/forward list if it had one.
+fn restore_tab(webview: &WebView, state: &session::TabState) {
+    if let Some(history) = &state.history {
+        let bytes = glib::Bytes::from(&history.0);
+        webview.restore_session_state(&webkit6::WebViewSessionState::new(&bytes));
+
+        if let Some(item) = webview
+            .back_forward_list()
+            .and_then(|list| list.current_item())
+        {
+            webview.go_to_back_forward_list_item(&item);
+            return;
         }
     }
+
+    webview.load_uri(&schemes::document_uri(&state.uri));
 }
 
 impl WebPaneModel {
-    async fn get_page_headings(&self, webview: &WebView) -> Result<Outline, webkit6::glib::Error> {
-        let script = /* js */ r#"
-            let headings = Array.from(document.querySelectorAll('h1, h2, h3, h4, h5, h6'))
+    /// Searches the page for `query`, replacing the last search.
+    ///
+    /// Whole-word and regular expression searches fall back to plain text outside man pages.
+    async fn find(
+        &mut self,
+        webview: &WebView,
+        query: find::Query,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        self.finish_find(webview).await;
 
-            globalThis.__headings = headings;
+        self.find_uses_script = query.needs_script();
+        self.find_query = Some(query.clone());
 
-            return JSON.stringify(
-                headings.map((elem, index) => ({
-                    "tag_name": elem.localName,
-                    "inner_text": elem.innerText,
-                    "index": index,
-                    "id": elem.id
-                }))
-            )
-        "#;
+        if query.text.is_empty() {
+            let _ = sender.output(TabMsg::UpdateFindStatus(find::Status::Idle));
+        } else if self.find_uses_script {
+            self.run_find_script(webview, "search", sender).await;
+        } else if let Some(find_controller) = webview.find_controller() {
+            let options = query.webkit_options().bits();
+
+            // WebKit highlights as many matches as it is asked to find.
+            let max_highlighted = if query.highlight_all {
+                find::MAX_MATCHES
+            } else {
+                1
+            };
+
+            find_controller.search(&query.text, options, max_highlighted);
+            find_controller.count_matches(&query.text, options, find::MAX_MATCHES);
+        }
+    }
+
+    async fn finish_find(&mut self, webview: &WebView) {
+        if self.find_uses_script {
+            self.call_find_script(webview, "finish").await;
+        } else if let Some(find_controller) = webview.find_controller() {
+            find_controller.search_finish();
+        }
+
+        self.find_uses_script = false;
+    }
+
+    /// Runs `action` of [`find::SCRIPT`] and reports the matches to the find bar.
+    async fn run_find_script(
+        &self,
+        webview: &WebView,
+        action: &str,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        if let Some(status) = self.call_find_script(webview, action).await {
+            let _ = sender.output(TabMsg::UpdateFindStatus(status));
+        }
+    }
+
+    async fn call_find_script(&self, webview: &WebView, action: &str) -> Option<find::Status> {
+        let query = self.find_query.clone().unwrap_or_default();
+
+        let args = VariantDict::new(None);
+        args.insert("action", action);
+        args.insert("text", query.text);
+        args.insert("caseSensitive", query.case_sensitive);
+        args.insert("wholeWord", query.whole_word);
+        args.insert("regex", query.regex);
+        args.insert("highlightAll", query.highlight_all);
+        args.insert("maxMatches", find::MAX_MATCHES);
 
         let result = webview
-            .call_async_javascript_function_future(script, None, None, None)
+            .call_async_javascript_function_future(find::SCRIPT, Some(&args.end()), None, None)
             .await;
 
-        result.map(|val| {
-            let val_str = val.to_str().to_string();
-            serde_json::from_str(&val_str).expect("JSON deserialization failed")
-        })
+        match result {
+            Ok(val) => Some(find::Status::from_script(&val.to_str())),
+            Err(e) => {
+                eprintln!("Error searching the page: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn scroll_to(&self, webview: &WebView, y: f64) -> Result<(), webkit6::glib::Error> {
+        let args = VariantDict::new(None);
+        args.insert("y", y);
+
+        webview
+            .call_async_javascript_function_future(
+                "window.scrollTo(0, y)",
+                Some(&args.end()),
+                None,
+                None,
+            )
+            .await
+            .map(|_| ())
     }
 
     async fn try_scroll_to_heading(
         &self,
         webview: &WebView,
-        heading: &HTMLHeading,
-    ) -> Result<(), webkit6::glib::Error> {
+        heading: &outline::Entry,
+    ) -> Result<(), outline::Error> {
+        // Returns whether the heading is still in the page.
         let script = /* js */ r#"
             console.log(`Scrolling to heading with index: ${index}, id: ${id}`);
 
+            // Opens the collapsed sections the heading is in.
+            const reveal = (elem) => {
+                for (let details = elem.closest("details"); details;
+                     details = details.parentElement?.closest("details"))
+                    details.open = true;
+
+                elem.scrollIntoView();
+            };
+
             if (id !== "") {
                 const elem = document.getElementById(id);
 
                 window.location.hash = '#' + id;
 
                 if (elem)
-                    elem.scrollIntoView();
-                else
-                    console.error("Element with id " + id + " not found");
+                    reveal(elem);
+                return elem !== null;
             } else {
-                const elem = globalThis.__headings[index];
-                elem.scrollIntoView();
+                const elem = globalThis.__outline?.headings.get(index);
+
+                if (elem)
+                    reveal(elem);
+                return elem !== undefined;
             }
         "#;
 
         let index = heading.index as u64;
-        let id = heading.id.clone().unwrap_or_default();
+        let id = heading.anchor.clone().unwrap_or_default();
 
         let args = VariantDict::new(None);
 
         args.insert("index", index);
         args.insert("id", id);
 
-        let res = webview
+        let found = webview
             .call_async_javascript_function_future(script, Some(&args.end()), None, None)
-            .await;
-
-        res.map(|_| ())
+            .await
+            .map_err(outline::Error::Script)?
+            .to_boolean();
+
+        if found {
+            Ok(())
+        } else {
+            Err(outline::Error::StaleOutline(heading.text.clone()))
+        }
     }
 }
 
 #[derive(Debug)]
 struct TabModel {
+    index: DynamicIndex,
     uri: String,
     web_pane: AsyncController<WebPaneModel>,
     current_title: Option<String>,
+    history: Option<session::History>,
+    scroll_y: f64,
     progress_visible: bool,
     load_progress: f64,
     nav_state: NavigationState,
-    outline: Option<Outline>,
+    outline: PageOutline,
+    current_heading: Option<usize>,
+    find_status: find::Status,
+    pinned: bool,
 }
 
 #[derive(Debug)]
@@ -262,15 +529,34 @@ enum TabMsg {
     UpdateLoadState(LoadEvent),
     UpdateNavState(NavigationState),
     UpdateLoadProgress(f64),
-    UpdateOutline(Option<Outline>),
+    UpdateOutline(PageOutline),
+    /// Going to a heading of the outline failed.
+    OutlineError(outline::Error),
     UpdateURI(String),
-    SelectedHeading(HTMLHeading),
+    UpdateHistory(session::History),
+    UpdateScroll(f64),
+    /// The index of the heading the page is scrolled to.
+    UpdateCurrentHeading(Option<usize>),
+    SelectedHeading(outline::Entry),
+    Find(find::Query),
+    FindNext,
+    FindPrevious,
+    FinishFind,
+    UpdateFindStatus(find::Status),
+    Zoom(Zoom),
+    Reload,
+    UpdatePinned(bool),
 }
 
 #[derive(Debug)]
 enum TabResponse {
     SelectTab(DynamicIndex),
-    UpdateOutline(Option<Outline>),
+    NavBar(DynamicIndex, NavBarMsg),
+    UpdateNavState(DynamicIndex, NavigationState),
+    UpdateOutline(DynamicIndex, PageOutline),
+    UpdateCurrentHeading(DynamicIndex, Option<usize>),
+    OutlineError(DynamicIndex, outline::Error),
+    Visited { uri: String, title: String },
 }
 
 fn is_progress_visible(event: LoadEvent) -> bool {
@@ -285,29 +571,35 @@ fn is_progress_visible(event: LoadEvent) -> bool {
 
 #[relm4::factory(async)]
 impl AsyncFactoryComponent for TabModel {
-    type Init = String;
+    type Init = session::TabState;
     type Input = TabMsg;
     type Output = TabResponse;
     type CommandOutput = Nothing;
     type ParentWidget = adw::TabView;
 
     async fn init_model(
-        uri: Self::Init,
-        _index: &DynamicIndex,
+        state: Self::Init,
+        index: &DynamicIndex,
         sender: AsyncFactorySender<Self>,
     ) -> Self {
         let web_pane = WebPaneModel::builder()
-            .launch(uri.clone())
+            .launch(state.clone())
             .forward(sender.input_sender(), identity);
 
         Self {
+            index: index.clone(),
             web_pane,
-            uri,
-            current_title: None,
+            uri: state.uri,
+            current_title: state.title,
+            history: state.history,
+            scroll_y: state.scroll_y,
             nav_state: Default::default(),
             load_progress: 0.0,
             progress_visible: false,
-            outline: None,
+            outline: Ok(None),
+            current_heading: None,
+            find_status: Default::default(),
+            pinned: state.pinned,
         }
     }
 
@@ -333,10 +625,16 @@ impl AsyncFactoryComponent for TabModel {
         #[local_ref]
         returned_widget -> adw::TabPage {
             #[watch]
-            set_title: &self.current_title.as_ref().map_or("(no title)", |s| s),
+            set_title: self.current_title.as_ref().map_or("(no title)", |s| s),
+
+            connect_selected_notify[sender, index] => move |tab_page| {
+                if tab_page.is_selected() {
+                    sender.output(TabResponse::SelectTab(index.clone())).expect("Receiver does not exist");
+                }
+            },
 
-            connect_selected_notify[sender, index] => move |_tab_page| {
-                sender.output(TabResponse::SelectTab(index.clone())).expect("Receiver does not exist");
+            connect_pinned_notify[sender] => move |tab_page| {
+                sender.input(TabMsg::UpdatePinned(tab_page.is_pinned()));
             },
         }
     }
@@ -353,95 +651,242 @@ impl AsyncFactoryComponent for TabModel {
             }
             TabMsg::UpdateTitle(s) => {
                 self.current_title = s.clone();
-                NAV_BAR_BROKER.send(NavBarMsg::UpdatedTitle(s.clone()));
+                let _ = sender.output(TabResponse::NavBar(
+                    self.index.clone(),
+                    NavBarMsg::UpdatedTitle(s),
+                ));
             }
             TabMsg::UpdateURI(uri) => {
                 self.uri = uri.clone();
-                NAV_BAR_BROKER.send(NavBarMsg::UpdatedURI(uri));
+                // The new page reports its own offset once it is scrolled.
+                self.scroll_y = 0.0;
+                let _ = sender.output(TabResponse::NavBar(
+                    self.index.clone(),
+                    NavBarMsg::UpdatedURI(uri),
+                ));
             }
             TabMsg::UpdateNavState(state) => {
                 self.nav_state = state;
-                NAV_BAR_BROKER.send(NavBarMsg::UpdatedNavState(state));
+                let _ = sender.output(TabResponse::UpdateNavState(self.index.clone(), state));
             }
             TabMsg::UpdateLoadState(event) => {
                 self.progress_visible = is_progress_visible(event);
-                NAV_BAR_BROKER.send(NavBarMsg::UpdatedProgressVisible(self.progress_visible));
+                let _ = sender.output(TabResponse::NavBar(
+                    self.index.clone(),
+                    NavBarMsg::UpdatedProgressVisible(self.progress_visible),
+                ));
+
+                // By now the title has usually arrived too.
+                if event == LoadEvent::Finished {
+                    let _ = sender.output(TabResponse::Visited {
+                        uri: self.uri.clone(),
+                        title: self.current_title.clone().unwrap_or_default(),
+                    });
+                }
             }
             TabMsg::UpdateLoadProgress(progress) => {
                 self.load_progress = progress;
-                NAV_BAR_BROKER.send(NavBarMsg::UpdatedLoadingProgress(progress));
+                let _ = sender.output(TabResponse::NavBar(
+                    self.index.clone(),
+                    NavBarMsg::UpdatedLoadingProgress(progress),
+                ));
             }
             TabMsg::UpdateOutline(outline) => {
                 self.outline = outline.clone();
-                OUTLINE_SIDEBAR_BROKER.send(OutlineSidebarMsg::UpdatedOutline(outline));
+                let _ = sender.output(TabResponse::UpdateOutline(self.index.clone(), outline));
+            }
+            TabMsg::OutlineError(e) => {
+                let _ = sender.output(TabResponse::OutlineError(self.index.clone(), e));
+            }
+            TabMsg::UpdateCurrentHeading(heading) => {
+                self.current_heading = heading;
+                let _ = sender.output(TabResponse::UpdateCurrentHeading(
+                    self.index.clone(),
+                    heading,
+                ));
             }
             TabMsg::UpdatedURI(uri) => {
                 self.uri = uri.clone();
                 self.web_pane.emit(WebPaneMsg::UpdatedURI(uri));
             }
+            TabMsg::UpdateHistory(history) => {
+                self.history = Some(history);
+            }
+            TabMsg::UpdateScroll(y) => {
+                self.scroll_y = y;
+            }
             TabMsg::SelectedHeading(heading) => {
                 self.web_pane.emit(WebPaneMsg::SelectedHeading(heading));
             }
+            TabMsg::Find(query) => {
+                self.web_pane.emit(WebPaneMsg::Find(query));
+            }
+            TabMsg::FindNext => {
+                self.web_pane.emit(WebPaneMsg::FindNext);
+            }
+            TabMsg::FindPrevious => {
+                self.web_pane.emit(WebPaneMsg::FindPrevious);
+            }
+            TabMsg::FinishFind => {
+                self.web_pane.emit(WebPaneMsg::FinishFind);
+            }
+            TabMsg::UpdateFindStatus(status) => {
+                self.find_status = status.clone();
+                let _ = sender.output(TabResponse::NavBar(
+                    self.index.clone(),
+                    NavBarMsg::UpdatedFindStatus(status),
+                ));
+            }
+            TabMsg::Zoom(zoom) => {
+                self.web_pane.emit(WebPaneMsg::Zoom(zoom));
+            }
+            TabMsg::Reload => {
+                self.web_pane.emit(WebPaneMsg::Reload);
+            }
+            TabMsg::UpdatePinned(pinned) => {
+                self.pinned = pinned;
+            }
         }
     }
 }
 
-#[derive(Debug)]
-struct OutlineItem {
-    value: HTMLHeading,
+impl TabModel {
+    fn state(&self) -> session::TabState {
+        session::TabState {
+            uri: self.uri.clone(),
+            title: self.current_title.clone(),
+            history: self.history.clone(),
+            scroll_y: self.scroll_y,
+            pinned: self.pinned,
+        }
+    }
+
+    /// Whether `page` is the tab page showing this tab.
+    fn is_shown_in(&self, page: &adw::TabPage) -> bool {
+        self.web_pane.widget().parent().as_ref() == Some(&page.child())
+    }
 }
 
-struct OutlineItemWidgets {
-    label: gtk::Label,
+type OutlineNode = outline::Node<outline::Entry>;
+
+/// The rows for `nodes`, which a tree model expands into their children with [`tree_children`].
+fn tree_rows<T: Clone + 'static>(nodes: &[outline::Node<T>]) -> Vec<glib::BoxedAnyObject> {
+    nodes
+        .iter()
+        .cloned()
+        .map(glib::BoxedAnyObject::new)
+        .collect()
 }
 
-impl RelmListItem for OutlineItem {
-    type Root = gtk::Box;
+/// The children of a row made by [`tree_rows`], or `None` if it has none.
+fn tree_children<T: Clone + 'static>(item: &glib::Object) -> Option<gio::ListModel> {
+    let node = item.downcast_ref::<glib::BoxedAnyObject>()?;
+    let node = node.borrow::<outline::Node<T>>();
 
-    type Widgets = OutlineItemWidgets;
+    if node.children.is_empty() {
+        return None;
+    }
+
+    let store = gio::ListStore::new::<glib::BoxedAnyObject>();
+    store.extend_from_slice(&tree_rows(&node.children));
+    Some(store.upcast())
+}
+
+/// Shows each entry of the outline behind an expander for the entries under it.
+fn outline_item_factory() -> gtk::SignalListItemFactory {
+    let factory = gtk::SignalListItemFactory::new();
+
+    factory.connect_setup(|_, list_item| {
+        let Some(list_item) = list_item.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
 
-    fn setup(_list_item: &gtk::ListItem) -> (Self::Root, Self::Widgets) {
         relm4::view! {
-            root_box = gtk::Box {
+            expander = gtk::TreeExpander {
                 set_margin_horizontal: 2,
+                set_indent_for_icon: true,
 
-                #[name = "label"]
-                gtk::Label { },
-            },
+                #[wrap(Some)]
+                set_child = &gtk::Label {
+                    set_xalign: 0.0,
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                },
+            }
         }
 
-        let widgets = OutlineItemWidgets { label };
+        list_item.set_child(Some(&expander));
+    });
 
-        (root_box, widgets)
-    }
+    factory.connect_bind(|_, list_item| {
+        let Some(list_item) = list_item.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let Some(expander) = list_item.child().and_downcast::<gtk::TreeExpander>() else {
+            return;
+        };
+        let Some(row) = list_item.item().and_downcast::<gtk::TreeListRow>() else {
+            return;
+        };
 
-    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
-        let OutlineItemWidgets { label } = widgets;
+        if let Some(label) = expander.child().and_downcast::<gtk::Label>()
+            && let Some(node) = row.item().and_downcast::<glib::BoxedAnyObject>()
+        {
+            let entry = &node.borrow::<OutlineNode>().item;
+            label.set_label(&entry.text);
 
-        let margin_left: usize = 2 + self.value.indent_levels() * 10;
+            if entry.kind == outline::Kind::Option {
+                label.add_css_class("monospace");
+            } else {
+                label.remove_css_class("monospace");
+            }
+        }
 
-        label.set_label(&self.value.inner_text);
-        label.set_margin_start(margin_left.try_into().unwrap_or(0));
-    }
-}
+        expander.set_list_row(Some(&row));
+    });
+
+    factory.connect_unbind(|_, list_item| {
+        if let Some(expander) = list_item
+            .downcast_ref::<gtk::ListItem>()
+            .and_then(|list_item| list_item.child())
+            .and_downcast::<gtk::TreeExpander>()
+        {
+            expander.set_list_row(None);
+        }
+    });
 
-static OUTLINE_SIDEBAR_BROKER: MessageBroker<OutlineSidebarMsg> = MessageBroker::new();
+    factory
+}
 
 #[derive(Debug)]
 struct OutlineSidebarModel {
     outline: Option<Outline>,
-    list_view_wrapper: TypedListView<OutlineItem, gtk::SingleSelection>,
+    /// Why the page's outline couldn't be read.
+    error: Option<outline::Error>,
+    nodes: Vec<OutlineNode>,
+    /// The index of the heading the page is scrolled to.
+    current_heading: Option<usize>,
+    /// The top-level headings.
+    headings: gio::ListStore,
+    tree: gtk::TreeListModel,
+    selection_model: gtk::SingleSelection,
+    list_view: gtk::ListView,
+    /// Set while the outline selects the current heading itself, so the page isn't sent there.
+    following_page: Rc<Cell<bool>>,
 }
 
 #[derive(Debug)]
 enum OutlineSidebarMsg {
-    UpdatedOutline(Option<Outline>),
+    UpdatedOutline(PageOutline),
+    /// A position in the tree as it is currently expanded.
     SelectItem(u32),
+    UpdatedCurrentHeading(Option<usize>),
+    ExpandAll,
+    CollapseAll,
 }
 
 #[derive(Debug)]
 enum OutlineSidebarResponse {
-    SelectHeading(HTMLHeading),
+    SelectHeading(outline::Entry),
 }
 
 #[relm4::component(async)]
@@ -460,16 +905,46 @@ impl SimpleAsyncComponent for OutlineSidebarModel {
                 },
 
                 set_decoration_layout: Some(""),
+
+                pack_start = &gtk::Button {
+                    set_icon_name: "pan-down-symbolic",
+                    set_tooltip_text: Some("Expand All"),
+
+                    #[watch]
+                    set_sensitive: model.outline.is_some(),
+
+                    connect_clicked => OutlineSidebarMsg::ExpandAll,
+                },
+
+                pack_start = &gtk::Button {
+                    set_icon_name: "pan-end-symbolic",
+                    set_tooltip_text: Some("Collapse All"),
+
+                    #[watch]
+                    set_sensitive: model.outline.is_some(),
+
+                    connect_clicked => OutlineSidebarMsg::CollapseAll,
+                },
             },
 
             gtk::ScrolledWindow {
                 #[wrap(Some)]
-                set_child = match model.outline {
-                    Some(_) => {
+                set_child = match (&model.outline, &model.error) {
+                    (Some(_), _) => {
                         #[local_ref]
                         *list_view -> gtk::ListView { }
                     },
-                    None => {
+                    (None, Some(_)) => {
+                        adw::StatusPage {
+                            set_icon_name: Some("dialog-warning-symbolic"),
+                            set_title: "Outline Unavailable",
+                            add_css_class: "compact",
+
+                            #[watch]
+                            set_description: model.error.as_ref().map(ToString::to_string).as_deref(),
+                        }
+                    },
+                    (None, None) => {
                         gtk::Label {
                             set_hexpand: true,
                             set_vexpand: true,
@@ -491,23 +966,46 @@ impl SimpleAsyncComponent for OutlineSidebarModel {
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
-        let list_view_wrapper: TypedListView<OutlineItem, gtk::SingleSelection> =
-            TypedListView::new();
-
-        let sender_clone = sender.clone();
+        let headings = gio::ListStore::new::<glib::BoxedAnyObject>();
 
-        list_view_wrapper.selection_model.connect_selection_changed(
-            move |model, _position, _n_items| {
-                sender_clone.input(OutlineSidebarMsg::SelectItem(model.selected()));
-            },
+        let tree = gtk::TreeListModel::new(
+            headings.clone(),
+            false,
+            true,
+            tree_children::<outline::Entry>,
         );
 
+        let selection_model = gtk::SingleSelection::builder()
+            .model(&tree)
+            .autoselect(false)
+            .can_unselect(true)
+            .build();
+
+        let sender_clone = sender.clone();
+        let following_page = Rc::new(Cell::new(false));
+        let selection_following = following_page.clone();
+
+        // Replacing the headings unselects the row that was selected.
+        selection_model.connect_selection_changed(move |model, _position, _n_items| {
+            let selected = model.selected();
+            if selected != gtk::INVALID_LIST_POSITION && !selection_following.get() {
+                sender_clone.input(OutlineSidebarMsg::SelectItem(selected));
+            }
+        });
+
         let model = OutlineSidebarModel {
             outline: None,
-            list_view_wrapper,
+            error: None,
+            nodes: Vec::new(),
+            current_heading: None,
+            headings,
+            tree,
+            selection_model: selection_model.clone(),
+            list_view: gtk::ListView::new(Some(selection_model), Some(outline_item_factory())),
+            following_page,
         };
 
-        let list_view = &model.list_view_wrapper.view;
+        let list_view = &model.list_view;
 
         let widgets = view_output!();
 
@@ -519,63 +1017,163 @@ impl SimpleAsyncComponent for OutlineSidebarModel {
 
         match message {
             OutlineSidebarMsg::UpdatedOutline(outline) => {
-                self.list_view_wrapper.clear();
-
-                if let Some(items) = &outline {
-                    for value in items {
-                        self.list_view_wrapper.append(OutlineItem {
-                            value: value.clone(),
-                        });
+                let (outline, error) = match outline {
+                    Ok(outline) => (outline, None),
+                    Err(e) => (None, Some(e)),
+                };
+
+                // Pages that change as they are read shouldn't undo what was collapsed.
+                let collapsed: HashSet<usize> = self
+                    .rows()
+                    .filter(|row| row.is_expandable() && !row.is_expanded())
+                    .filter_map(|row| row_heading(&row))
+                    .map(|heading| heading.index)
+                    .collect();
+
+                self.nodes =
+                    outline::build_tree(outline.iter().flatten().cloned(), |entry| entry.level);
+                self.headings
+                    .splice(0, self.headings.n_items(), &tree_rows(&self.nodes));
+
+                for position in (0..self.tree.n_items()).rev() {
+                    if let Some(row) = self.tree.row(position)
+                        && row_heading(&row)
+                            .is_some_and(|heading| collapsed.contains(&heading.index))
+                    {
+                        row.set_expanded(false);
                     }
                 }
 
                 self.outline = outline;
+                self.error = error;
+                self.show_current_heading();
             }
 
-            OutlineSidebarMsg::SelectItem(index) => {
-                if let Some(outline) = &self.outline {
-                    match outline.get(index as usize) {
-                        Some(heading) => {
-                            let _ = sender
-                                .output(OutlineSidebarResponse::SelectHeading(heading.clone()));
-                        }
-                        None => {
-                            eprintln!("Invalid heading index: {}", index);
-                        }
+            OutlineSidebarMsg::SelectItem(position) => {
+                // The row can go away with the outline it was selected in.
+                if let Some(heading) = self.heading_at(position) {
+                    let _ = sender.output(OutlineSidebarResponse::SelectHeading(heading));
+                }
+            }
+
+            OutlineSidebarMsg::UpdatedCurrentHeading(heading) => {
+                self.current_heading = heading;
+                self.show_current_heading();
+            }
+
+            OutlineSidebarMsg::ExpandAll => {
+                // Expanding a row puts its children right after it, so they are reached next.
+                let mut position = 0;
+                while let Some(row) = self.tree.row(position) {
+                    row.set_expanded(true);
+                    position += 1;
+                }
+            }
+
+            OutlineSidebarMsg::CollapseAll => {
+                // Going backwards, collapsing a row only removes rows already visited.
+                for position in (0..self.tree.n_items()).rev() {
+                    if let Some(row) = self.tree.row(position) {
+                        row.set_expanded(false);
                     }
-                } else {
-                    unreachable!("Selected item without an outline")
                 }
             }
         }
     }
 }
 
-static NAV_BAR_BROKER: MessageBroker<NavBarMsg> = MessageBroker::new();
+fn row_heading(row: &gtk::TreeListRow) -> Option<outline::Entry> {
+    let node = row.item().and_downcast::<glib::BoxedAnyObject>()?;
+    let heading = node.borrow::<OutlineNode>().item.clone();
+    Some(heading)
+}
+
+impl OutlineSidebarModel {
+    fn heading_at(&self, position: u32) -> Option<outline::Entry> {
+        row_heading(&self.tree.row(position)?)
+    }
+
+    /// The rows as currently expanded.
+    fn rows(&self) -> impl Iterator<Item = gtk::TreeListRow> + '_ {
+        (0..self.tree.n_items()).filter_map(|position| self.tree.row(position))
+    }
+
+    /// Selects the heading the page is scrolled to and scrolls the outline to it.
+    ///
+    /// A heading in a collapsed branch stays hidden, and the nearest heading above it that is
+    /// shown is selected instead.
+    fn show_current_heading(&self) {
+        let path = self.current_heading.and_then(|index| {
+            outline::find_path(&self.nodes, &|heading: &outline::Entry| {
+                heading.index == index
+            })
+        });
+
+        let Some(path) = path else {
+            self.selection_model.unselect_all();
+            return;
+        };
+
+        let Some(mut row) = self.tree.child_row(path[0] as u32) else {
+            return;
+        };
+        for &position in &path[1..] {
+            if !row.is_expanded() {
+                break;
+            }
+            let Some(child) = row.child_row(position as u32) else {
+                return;
+            };
+            row = child;
+        }
+
+        self.following_page.set(true);
+        self.list_view
+            .scroll_to(row.position(), gtk::ListScrollFlags::SELECT, None);
+        self.following_page.set(false);
+    }
+}
 
 #[tracker::track]
 #[derive(Debug)]
 struct NavBarModel {
     title: Option<String>,
     uri: String,
-    nav_state: NavigationState,
     uri_editable: bool,
-    sidebar_visible: bool,
     progress_visible: bool,
     load_progress: f64,
+    /// The targets of every bookmark.
+    bookmarks: HashSet<String>,
+    has_suggestions: bool,
+    #[do_not_track]
+    suggestions: TypedListView<history::Visit, gtk::SingleSelection>,
+    find_visible: bool,
+    find_status: find::Status,
+    #[do_not_track]
+    find_query: find::Query,
 }
 
 #[derive(Debug)]
 enum NavBarMsg {
     StartEditingURI,
     CancelEditingURI,
+    EditedURI(String),
     SetNewURI(String),
+    UpdatedSuggestions(Vec<history::Visit>),
+    MoveSuggestion(i32),
+    ActivateSuggestion(u32),
     UpdatedTitle(Option<String>),
-    UpdatedSidebarVisibility(bool),
-    UpdatedNavState(NavigationState),
     UpdatedURI(String),
     UpdatedProgressVisible(bool),
     UpdatedLoadingProgress(f64),
+    UpdatedBookmarks(HashSet<String>),
+    ShowFind,
+    HideFind,
+    EditedFindText(String),
+    ToggledFindOption(find::FindOption, bool),
+    FindNext,
+    FindPrevious,
+    UpdatedFindStatus(find::Status),
 }
 
 #[relm4::component(async)]
@@ -602,59 +1200,64 @@ impl SimpleAsyncComponent for NavBarModel {
 
                         #[name="back"]
                         gtk::Button::from_icon_name("go-previous-symbolic") {
-                            connect_clicked[sender] => move |_| {
-                                let _ = sender.output(AppMsg::GoBack);
-                            },
-
-                            #[watch]
-                            set_sensitive: model.nav_state.can_go_back,
+                            set_tooltip_text: Some("Back"),
+                            ActionablePlus::set_stateless_action::<GoBackAction>: &(),
                         },
 
                         #[name="forward"]
                         gtk::Button::from_icon_name("go-next-symbolic") {
-                            connect_clicked[sender] => move |_| {
-                                let _ = sender.output(AppMsg::GoForward);
-                            },
-
-                            #[watch]
-                            set_sensitive: model.nav_state.can_go_forward,
+                            set_tooltip_text: Some("Forward"),
+                            ActionablePlus::set_stateless_action::<GoForwardAction>: &(),
                         },
                     },
 
-                    gtk::Button::from_icon_name("bookmark-outline-symbolic") {
-                        connect_clicked[sender] => move |_| {
-                            //let _ = sender.output(AppMsg::Bookmark);
+                    gtk::Button {
+                        #[watch]
+                        set_icon_name: if model.is_bookmarked() {
+                            "bookmark-filled-symbolic"
+                        } else {
+                            "bookmark-outline-symbolic"
                         },
+
+                        #[watch]
+                        set_tooltip_text: Some(if model.is_bookmarked() {
+                            "Remove bookmark"
+                        } else {
+                            "Bookmark this page"
+                        }),
+
+                        ActionablePlus::set_stateless_action::<ToggleBookmarkAction>: &(),
                     },
                 },
 
                 pack_end = &gtk::Box {
                     set_spacing: 5,
 
+                    gtk::MenuButton {
+                        set_icon_name: "open-menu-symbolic",
+                        set_tooltip_text: Some("Main menu"),
+                        set_menu_model: Some(&main_menu),
+                    },
+
                     #[name="new_tab"]
                     gtk::Button::from_icon_name("tab-new-symbolic") {
-                        connect_clicked[sender] => move |_| {
-                            let _ = sender.output(AppMsg::NewTab);
-                        },
+                        set_tooltip_text: Some("New tab"),
+                        ActionablePlus::set_stateless_action::<NewTabAction>: &(),
                     },
 
+                    // The action's state keeps the button in step with the outline.
                     #[name="sidebar_toggle"]
                     gtk::ToggleButton {
                         set_icon_name: "sidebar-show-right-symbolic",
-
-                        #[watch]
-                        set_active: model.sidebar_visible,
-
-                        connect_clicked[sender] => move |button| {
-                            let _ = sender.output(AppMsg::UpdateSidebarVisibility(button.is_active()));
-                        },
+                        set_tooltip_text: Some("Outline"),
+                        ActionablePlus::set_stateless_action::<ToggleOutlineAction>: &(),
                     },
                 },
 
                 set_show_title: true,
 
                 #[wrap(Some)]
-                set_title_widget = &adw::Bin {
+                set_title_widget = &gtk::Box {
                     if model.uri_editable {
                         #[name="uri_entry"]
                         gtk::Entry {
@@ -665,8 +1268,9 @@ impl SimpleAsyncComponent for NavBarModel {
 
                             set_activates_default: true,
 
-                            #[watch]
-                            #[block_signal(changed)]
+                            // Only when the page changes, so typing isn't overwritten.
+                            #[track = "model.changed(NavBarModel::uri() | NavBarModel::uri_editable())"]
+                            #[block_signal(changed, edited)]
                             set_text: &model.uri,
 
                             #[track = "model.changed(NavBarModel::uri_editable())"]
@@ -676,6 +1280,25 @@ impl SimpleAsyncComponent for NavBarModel {
                                 sender.input(NavBarMsg::SetNewURI(entry.text().to_string()))
                             } @changed,
 
+                            connect_changed[sender] => move |entry| {
+                                sender.input(NavBarMsg::EditedURI(entry.text().to_string()))
+                            } @edited,
+
+                            add_controller = gtk::EventControllerKey {
+                                set_propagation_phase: gtk::PropagationPhase::Capture,
+
+                                connect_key_pressed[sender] => move |_, key, _, _| {
+                                    let step = match key {
+                                        gtk::gdk::Key::Down => 1,
+                                        gtk::gdk::Key::Up => -1,
+                                        _ => return glib::Propagation::Proceed,
+                                    };
+
+                                    sender.input(NavBarMsg::MoveSuggestion(step));
+                                    glib::Propagation::Stop
+                                },
+                            },
+
                             connect_state_flags_changed[sender] => move |entry, old_flags| {
                                 if old_flags.contains(gtk::StateFlags::FOCUSED)
                                         && !entry.state_flags().contains(gtk::StateFlags::FOCUSED) {
@@ -695,45 +1318,199 @@ impl SimpleAsyncComponent for NavBarModel {
                                 set_subtitle: &model.uri,
                             },
 
-                            connect_clicked => NavBarMsg::StartEditingURI,
+                            ActionablePlus::set_stateless_action::<FocusAddressAction>: &(),
                         }
                     },
+
+                    gtk::Popover {
+                        set_position: gtk::PositionType::Bottom,
+                        set_has_arrow: false,
+                        // Keeps the focus, and so the suggestions, in the entry.
+                        set_autohide: false,
+                        set_can_focus: false,
+
+                        #[watch]
+                        set_visible: model.uri_editable && model.has_suggestions,
+
+                        #[wrap(Some)]
+                        set_child = &gtk::ScrolledWindow {
+                            set_hscrollbar_policy: gtk::PolicyType::Never,
+                            set_propagate_natural_height: true,
+                            set_min_content_width: 400,
+                            set_max_content_height: 400,
+
+                            #[local_ref]
+                            suggestion_list -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavBarMsg::ActivateSuggestion(position));
+                                },
+                            },
+                        },
+                    },
                 },
 
             },
 
-            gtk::ProgressBar {
-                set_hexpand: true,
+            #[name="find_bar"]
+            gtk::SearchBar {
+                set_show_close_button: true,
 
-                #[watch]
-                set_visible: model.progress_visible,
+                #[track = "model.changed(NavBarModel::find_visible())"]
+                #[block_signal(find_toggled)]
+                set_search_mode: model.find_visible,
 
-                #[watch]
-                set_fraction: model.load_progress,
-            },
-        }
-    }
+                connect_search_mode_enabled_notify[sender] => move |bar| {
+                    if !bar.is_search_mode() {
+                        sender.input(NavBarMsg::HideFind);
+                    }
+                } @find_toggled,
 
-    async fn init(
-        init: Self::Init,
-        root: Self::Root,
-        sender: AsyncComponentSender<Self>,
-    ) -> AsyncComponentParts<Self> {
-        let model = NavBarModel {
-            title: None,
-            uri: init,
-            uri_editable: false,
-            nav_state: Default::default(),
-            sidebar_visible: true,
-            progress_visible: false,
-            load_progress: 0.0,
-            tracker: Default::default(),
-        };
+                #[wrap(Some)]
+                set_child = &gtk::Box {
+                    set_spacing: 10,
 
-        let widgets = view_output!();
+                    gtk::Box {
+                        add_css_class: relm4::css::LINKED,
 
-        AsyncComponentParts { model, widgets }
-    }
+                        #[name="find_entry"]
+                        gtk::SearchEntry {
+                            set_placeholder_text: Some("Find in page"),
+                            set_width_chars: 30,
+
+                            #[track = "model.changed(NavBarModel::find_visible()) && model.find_visible"]
+                            grab_focus: (),
+
+                            connect_search_changed[sender] => move |entry| {
+                                sender.input(NavBarMsg::EditedFindText(entry.text().to_string()));
+                            },
+
+                            connect_activate => NavBarMsg::FindNext,
+                            connect_next_match => NavBarMsg::FindNext,
+                            connect_previous_match => NavBarMsg::FindPrevious,
+                            connect_stop_search => NavBarMsg::HideFind,
+                        },
+
+                        gtk::Button::from_icon_name("go-up-symbolic") {
+                            set_tooltip_text: Some("Previous match"),
+                            ActionablePlus::set_stateless_action::<FindPreviousAction>: &(),
+                        },
+
+                        gtk::Button::from_icon_name("go-down-symbolic") {
+                            set_tooltip_text: Some("Next match"),
+                            ActionablePlus::set_stateless_action::<FindNextAction>: &(),
+                        },
+                    },
+
+                    gtk::Label {
+                        add_css_class: "dim-label",
+                        add_css_class: "numeric",
+
+                        #[watch]
+                        set_label: &model.find_status.label(),
+
+                        #[watch]
+                        set_tooltip_text: match &model.find_status {
+                            find::Status::InvalidPattern(e) => Some(e.as_str()),
+                            _ => None,
+                        },
+                    },
+
+                    gtk::CheckButton::with_label("Match case") {
+                        connect_toggled[sender] => move |button| {
+                            sender.input(NavBarMsg::ToggledFindOption(find::FindOption::CaseSensitive, button.is_active()));
+                        },
+                    },
+
+                    gtk::CheckButton::with_label("Whole words") {
+                        connect_toggled[sender] => move |button| {
+                            sender.input(NavBarMsg::ToggledFindOption(find::FindOption::WholeWord, button.is_active()));
+                        },
+                    },
+
+                    gtk::CheckButton::with_label("Regular expression") {
+                        connect_toggled[sender] => move |button| {
+                            sender.input(NavBarMsg::ToggledFindOption(find::FindOption::Regex, button.is_active()));
+                        },
+                    },
+
+                    gtk::CheckButton::with_label("Highlight all") {
+                        set_active: true,
+
+                        connect_toggled[sender] => move |button| {
+                            sender.input(NavBarMsg::ToggledFindOption(find::FindOption::HighlightAll, button.is_active()));
+                        },
+                    },
+                },
+            },
+
+            gtk::ProgressBar {
+                set_hexpand: true,
+
+                #[watch]
+                set_visible: model.progress_visible,
+
+                #[watch]
+                set_fraction: model.load_progress,
+            },
+        }
+    }
+
+    menu! {
+        main_menu: {
+            section! {
+                "New Tab" => NewTabAction,
+                "New Window" => NewWindowAction,
+                "Reopen Closed Tab" => ReopenClosedTabAction,
+                "Find in Page" => FindAction,
+            },
+            section! {
+                "Zoom In" => ZoomInAction,
+                "Zoom Out" => ZoomOutAction,
+                "Reset Zoom" => ZoomResetAction,
+            },
+            section! {
+                "Preferences" => PreferencesAction,
+                "Keyboard Shortcuts" => ShortcutsAction,
+                "Quit" => QuitAction,
+            },
+        }
+    }
+
+    async fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let model = NavBarModel {
+            title: None,
+            uri: init,
+            uri_editable: false,
+            progress_visible: false,
+            load_progress: 0.0,
+            bookmarks: HashSet::new(),
+            has_suggestions: false,
+            suggestions: TypedListView::new(),
+            find_visible: false,
+            find_status: find::Status::Idle,
+            find_query: Default::default(),
+            tracker: Default::default(),
+        };
+
+        // Nothing is picked until the arrow keys move into the list.
+        model.suggestions.selection_model.set_autoselect(false);
+        model.suggestions.selection_model.set_can_unselect(true);
+
+        let suggestion_list = &model.suggestions.view;
+
+        let widgets = view_output!();
+
+        widgets.find_bar.connect_entry(&widgets.find_entry);
+
+        AsyncComponentParts { model, widgets }
+    }
 
     async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
         self.reset();
@@ -746,20 +1523,36 @@ impl SimpleAsyncComponent for NavBarModel {
             }
             NavBarMsg::CancelEditingURI => {
                 self.set_uri_editable(false);
+                self.clear_suggestions();
             }
-            NavBarMsg::SetNewURI(uri) => {
+            NavBarMsg::EditedURI(text) => {
+                let _ = sender.output(AppMsg::SuggestURIs(text));
+            }
+            NavBarMsg::SetNewURI(text) => {
+                let uri = self.selected_suggestion().unwrap_or(text);
+
                 self.set_uri_editable(false);
+                self.clear_suggestions();
                 self.set_uri(uri.clone());
                 let _ = sender.output(AppMsg::UpdateURI(uri));
             }
-            NavBarMsg::UpdatedTitle(title) => {
-                self.set_title(title.clone());
+            NavBarMsg::UpdatedSuggestions(visits) => {
+                if self.uri_editable {
+                    self.suggestions.clear();
+                    self.suggestions.extend_from_iter(visits);
+                    self.set_has_suggestions(!self.suggestions.is_empty());
+                }
             }
-            NavBarMsg::UpdatedSidebarVisibility(visible) => {
-                self.set_sidebar_visible(visible);
+            NavBarMsg::MoveSuggestion(step) => {
+                self.move_suggestion(step);
+            }
+            NavBarMsg::ActivateSuggestion(position) => {
+                if let Some(visit) = self.suggestions.get(position) {
+                    sender.input(NavBarMsg::SetNewURI(visit.borrow().uri.clone()));
+                }
             }
-            NavBarMsg::UpdatedNavState(state) => {
-                self.set_nav_state(state);
+            NavBarMsg::UpdatedTitle(title) => {
+                self.set_title(title.clone());
             }
             NavBarMsg::UpdatedProgressVisible(progress) => {
                 self.set_progress_visible(progress);
@@ -770,18 +1563,823 @@ impl SimpleAsyncComponent for NavBarModel {
             NavBarMsg::UpdatedURI(uri) => {
                 self.set_uri(uri);
             }
+            NavBarMsg::UpdatedBookmarks(bookmarks) => {
+                self.set_bookmarks(bookmarks);
+            }
+            NavBarMsg::ShowFind => {
+                // Marked as changed even when already open, so the entry takes the focus again.
+                self.update_find_visible(|visible| *visible = true);
+            }
+            NavBarMsg::HideFind => {
+                self.set_find_visible(false);
+                self.set_find_status(find::Status::Idle);
+                let _ = sender.output(AppMsg::FinishFind);
+            }
+            NavBarMsg::EditedFindText(text) => {
+                self.find_query.text = text;
+                let _ = sender.output(AppMsg::Find(self.find_query.clone()));
+            }
+            NavBarMsg::ToggledFindOption(option, active) => {
+                self.find_query.set(option, active);
+                if !self.find_query.text.is_empty() {
+                    let _ = sender.output(AppMsg::Find(self.find_query.clone()));
+                }
+            }
+            NavBarMsg::FindNext => {
+                if !self.find_query.text.is_empty() {
+                    let _ = sender.output(AppMsg::FindNext);
+                }
+            }
+            NavBarMsg::FindPrevious => {
+                if !self.find_query.text.is_empty() {
+                    let _ = sender.output(AppMsg::FindPrevious);
+                }
+            }
+            NavBarMsg::UpdatedFindStatus(status) => {
+                if self.find_visible {
+                    self.set_find_status(status);
+                }
+            }
+        }
+    }
+}
+
+impl NavBarModel {
+    fn is_bookmarked(&self) -> bool {
+        self.bookmarks.contains(&self.uri)
+    }
+
+    fn clear_suggestions(&mut self) {
+        self.suggestions.clear();
+        self.set_has_suggestions(false);
+    }
+
+    fn selected_suggestion(&self) -> Option<String> {
+        let position = self.suggestions.selection_model.selected();
+        let visit = self.suggestions.get(position)?;
+        let uri = visit.borrow().uri.clone();
+        Some(uri)
+    }
+
+    /// Selects the next or previous suggestion; moving up past the first goes back to the entry.
+    fn move_suggestion(&self, step: i32) {
+        let count = self.suggestions.len();
+        if count == 0 {
+            return;
+        }
+
+        let selection = &self.suggestions.selection_model;
+        let selected = Some(selection.selected()).filter(|&p| p != gtk::INVALID_LIST_POSITION);
+
+        let next = match (selected, step > 0) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(p), true) => (p + 1).min(count - 1),
+            (Some(0), false) => gtk::INVALID_LIST_POSITION,
+            (Some(p), false) => p - 1,
+        };
+
+        selection.set_selected(next);
+        if next != gtk::INVALID_LIST_POSITION {
+            self.suggestions
+                .view
+                .scroll_to(next, gtk::ListScrollFlags::NONE, None);
+        }
+    }
+}
+
+/// How many visited documents the address entry suggests.
+const MAX_URI_SUGGESTIONS: usize = 8;
+
+pub struct SuggestionWidgets {
+    title: gtk::Label,
+    uri: gtk::Label,
+}
+
+impl RelmListItem for history::Visit {
+    type Root = gtk::Box;
+
+    type Widgets = SuggestionWidgets;
+
+    fn setup(_list_item: &gtk::ListItem) -> (Self::Root, Self::Widgets) {
+        relm4::view! {
+            root_box = gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_margin_horizontal: 6,
+                set_margin_vertical: 3,
+
+                #[name = "title"]
+                gtk::Label {
+                    set_xalign: 0.0,
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                },
+
+                #[name = "uri"]
+                gtk::Label {
+                    set_xalign: 0.0,
+                    set_ellipsize: gtk::pango::EllipsizeMode::Middle,
+                    add_css_class: "dim-label",
+                    add_css_class: "caption",
+                },
+            },
+        }
+
+        let widgets = SuggestionWidgets { title, uri };
+
+        (root_box, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        let title = if self.title.is_empty() {
+            &self.uri
+        } else {
+            &self.title
+        };
+
+        widgets.title.set_label(title);
+        widgets.uri.set_label(&self.uri);
+    }
+}
+
+/// The kinds of documentation the navigation sidebar can browse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum NavMode {
+    ManPages,
+    Texinfo,
+    HtmlDocs,
+    Bookmarks,
+    History,
+}
+
+impl NavMode {
+    fn from_position(position: u32) -> NavMode {
+        match position {
+            1 => NavMode::Texinfo,
+            2 => NavMode::HtmlDocs,
+            3 => NavMode::Bookmarks,
+            4 => NavMode::History,
+            _ => NavMode::ManPages,
+        }
+    }
+}
+
+/// A row in one of the navigation sidebar's trees: a group or a document.
+#[derive(Debug, Clone)]
+enum NavItem {
+    Group {
+        /// Identifies the group when the tree is rebuilt, so it stays expanded or collapsed.
+        key: String,
+        /// Pango markup.
+        title: String,
+        /// A page for the group itself, like the start page of a book.
+        uri: Option<String>,
+        count: usize,
+        /// Whether the group starts out expanded.
+        expanded: bool,
+    },
+    Link {
+        label: String,
+        uri: String,
+        description: Option<String>,
+    },
+    /// A link that can be edited and deleted.
+    Bookmark {
+        label: String,
+        target: String,
+        description: Option<String>,
+    },
+}
+
+type NavNode = outline::Node<NavItem>;
+
+impl NavNode {
+    fn group(key: String, title: String, uri: Option<String>, expanded: bool) -> Self {
+        outline::Node {
+            item: NavItem::Group {
+                key,
+                title,
+                uri,
+                count: 0,
+                expanded,
+            },
+            children: Vec::new(),
+        }
+    }
+
+    fn link(label: String, uri: String, description: Option<String>) -> Self {
+        outline::Node {
+            item: NavItem::Link {
+                label,
+                uri,
+                description,
+            },
+            children: Vec::new(),
+        }
+    }
+
+    /// Fills a group with `children`.
+    fn with_children(mut self, children: Vec<NavNode>) -> Self {
+        if let NavItem::Group { count, .. } = &mut self.item {
+            *count = children.len();
+        }
+        self.children = children;
+        self
+    }
+}
+
+relm4::new_action_group!(BookmarkActionGroup, "bookmark");
+relm4::new_stateful_action!(EditBookmarkAction, BookmarkActionGroup, "edit", String, ());
+relm4::new_stateful_action!(
+    DeleteBookmarkAction,
+    BookmarkActionGroup,
+    "delete",
+    String,
+    ()
+);
+
+/// How many symbol search results the HTML docs view lists.
+const MAX_SYMBOL_RESULTS: usize = 200;
+
+/// The widgets of a row in the navigation sidebar, inside its expander.
+struct NavItemWidgets {
+    row: gtk::Box,
+    label: gtk::Label,
+    count: gtk::Label,
+    edit: gtk::Button,
+    delete: gtk::Button,
+}
+
+impl NavItemWidgets {
+    fn of(expander: &gtk::TreeExpander) -> Option<Self> {
+        let row = expander.child().and_downcast::<gtk::Box>()?;
+        let label = row.first_child().and_downcast::<gtk::Label>()?;
+        let count = label.next_sibling().and_downcast::<gtk::Label>()?;
+        let edit = count.next_sibling().and_downcast::<gtk::Button>()?;
+        let delete = edit.next_sibling().and_downcast::<gtk::Button>()?;
+
+        Some(NavItemWidgets {
+            row,
+            label,
+            count,
+            edit,
+            delete,
+        })
+    }
+
+    fn bind(&self, item: &NavItem) {
+        let is_bookmark = matches!(item, NavItem::Bookmark { .. });
+        self.edit.set_visible(is_bookmark);
+        self.delete.set_visible(is_bookmark);
+
+        match item {
+            NavItem::Group { title, count, .. } => {
+                self.label.set_markup(title);
+
+                self.count.set_label(&count.to_string());
+                self.count.set_visible(true);
+
+                self.row.set_tooltip_text(None);
+            }
+            NavItem::Link {
+                label, description, ..
+            } => {
+                self.label.set_label(label);
+                self.count.set_visible(false);
+                self.row.set_tooltip_text(description.as_deref());
+            }
+            NavItem::Bookmark {
+                label,
+                target,
+                description,
+            } => {
+                self.label.set_label(label);
+                self.count.set_visible(false);
+
+                self.edit.set_action::<EditBookmarkAction>(target.clone());
+                self.delete
+                    .set_action::<DeleteBookmarkAction>(target.clone());
+
+                self.row.set_tooltip_text(description.as_deref());
+            }
         }
     }
 }
 
+/// Shows each row of a navigation tree behind an expander for the rows under it.
+fn nav_item_factory() -> gtk::SignalListItemFactory {
+    let factory = gtk::SignalListItemFactory::new();
+
+    factory.connect_setup(|_, list_item| {
+        let Some(list_item) = list_item.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+
+        relm4::view! {
+            expander = gtk::TreeExpander {
+                set_indent_for_icon: true,
+
+                #[wrap(Some)]
+                set_child = &gtk::Box {
+                    set_spacing: 6,
+                    set_margin_end: 6,
+
+                    gtk::Label {
+                        set_hexpand: true,
+                        set_xalign: 0.0,
+                        set_ellipsize: gtk::pango::EllipsizeMode::End,
+                    },
+
+                    gtk::Label {
+                        add_css_class: "dim-label",
+                    },
+
+                    gtk::Button::from_icon_name("document-edit-symbolic") {
+                        add_css_class: "flat",
+                        set_tooltip_text: Some("Edit bookmark"),
+                    },
+
+                    gtk::Button::from_icon_name("user-trash-symbolic") {
+                        add_css_class: "flat",
+                        set_tooltip_text: Some("Delete bookmark"),
+                    },
+                },
+            }
+        }
+
+        list_item.set_child(Some(&expander));
+    });
+
+    factory.connect_bind(|_, list_item| {
+        let Some(list_item) = list_item.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let Some(expander) = list_item.child().and_downcast::<gtk::TreeExpander>() else {
+            return;
+        };
+        let Some(row) = list_item.item().and_downcast::<gtk::TreeListRow>() else {
+            return;
+        };
+
+        if let Some(widgets) = NavItemWidgets::of(&expander)
+            && let Some(item) = row_nav_item(&row)
+        {
+            widgets.bind(&item);
+        }
+
+        expander.set_list_row(Some(&row));
+    });
+
+    factory.connect_unbind(|_, list_item| {
+        if let Some(expander) = list_item
+            .downcast_ref::<gtk::ListItem>()
+            .and_then(|list_item| list_item.child())
+            .and_downcast::<gtk::TreeExpander>()
+        {
+            expander.set_list_row(None);
+        }
+    });
+
+    factory
+}
+
+fn row_nav_item(row: &gtk::TreeListRow) -> Option<NavItem> {
+    let node = row.item().and_downcast::<glib::BoxedAnyObject>()?;
+    let item = node.borrow::<NavNode>().item.clone();
+    Some(item)
+}
+
+/// One of the navigation sidebar's trees of groups and documents.
 #[derive(Debug)]
-struct NavSidebar {}
+struct NavTree {
+    /// The top-level rows.
+    roots: gio::ListStore,
+    tree: gtk::TreeListModel,
+    view: gtk::ListView,
+}
+
+impl NavTree {
+    fn new() -> Self {
+        let roots = gio::ListStore::new::<glib::BoxedAnyObject>();
+        let tree = gtk::TreeListModel::new(roots.clone(), false, false, tree_children::<NavItem>);
+        let selection = gtk::NoSelection::new(Some(tree.clone()));
+
+        NavTree {
+            roots,
+            tree,
+            view: gtk::ListView::new(Some(selection), Some(nav_item_factory())),
+        }
+    }
+
+    fn row(&self, position: u32) -> Option<gtk::TreeListRow> {
+        self.tree.row(position)
+    }
+
+    /// Replaces the rows.
+    ///
+    /// Groups that were shown before stay expanded or collapsed as they were, and new ones
+    /// start out the way their item says.
+    fn set(&self, nodes: &[NavNode]) {
+        let expanded: HashMap<String, bool> = (0..self.tree.n_items())
+            .filter_map(|position| self.tree.row(position))
+            .filter_map(|row| match row_nav_item(&row)? {
+                NavItem::Group { key, .. } => Some((key, row.is_expanded())),
+                _ => None,
+            })
+            .collect();
+
+        self.roots
+            .splice(0, self.roots.n_items(), &tree_rows(nodes));
+
+        // Expanding a row puts its children right after it, so they are reached next.
+        let mut position = 0;
+        while let Some(row) = self.tree.row(position) {
+            if let Some(NavItem::Group {
+                key,
+                expanded: default,
+                ..
+            }) = row_nav_item(&row)
+            {
+                row.set_expanded(expanded.get(&key).copied().unwrap_or(default));
+            }
+            position += 1;
+        }
+    }
+
+    fn clear(&self) {
+        self.roots.remove_all();
+    }
+}
+
+pub struct SearchHitWidgets {
+    title: gtk::Label,
+    source: gtk::Label,
+    snippet: gtk::Label,
+}
+
+impl RelmListItem for search::Hit {
+    type Root = gtk::Box;
+
+    type Widgets = SearchHitWidgets;
+
+    fn setup(_list_item: &gtk::ListItem) -> (Self::Root, Self::Widgets) {
+        relm4::view! {
+            root_box = gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 3,
+                set_margin_horizontal: 6,
+                set_margin_vertical: 3,
+
+                gtk::Box {
+                    set_spacing: 6,
+
+                    #[name = "title"]
+                    gtk::Label {
+                        set_hexpand: true,
+                        set_xalign: 0.0,
+                        set_ellipsize: gtk::pango::EllipsizeMode::End,
+                        add_css_class: "heading",
+                    },
+
+                    #[name = "source"]
+                    gtk::Label {
+                        add_css_class: "dim-label",
+                        add_css_class: "caption",
+                    },
+                },
+
+                #[name = "snippet"]
+                gtk::Label {
+                    set_xalign: 0.0,
+                    set_wrap: true,
+                    set_wrap_mode: gtk::pango::WrapMode::WordChar,
+                    set_lines: 3,
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                    add_css_class: "caption",
+                },
+            },
+        }
+
+        let widgets = SearchHitWidgets {
+            title,
+            source,
+            snippet,
+        };
+
+        (root_box, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.title.set_label(&self.title);
+        widgets.source.set_label(self.source.label());
+        widgets.snippet.set_markup(&self.snippet);
+    }
+}
+
+/// What the list area of the navigation sidebar shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NavPage {
+    Browse(NavMode),
+    Search,
+    Loading,
+}
+
+/// How many full-text search results the sidebar lists.
+const MAX_SEARCH_RESULTS: usize = 50;
+
+/// Seconds to wait after documentation changes before updating the search index, since
+/// installing a package changes many files.
+const SEARCH_INDEX_UPDATE_DELAY: u32 = 5;
+
+/// The catalogs and the search index, loaded once and shared by the sidebars of every window.
+#[derive(Default)]
+struct Documentation {
+    loading: bool,
+    catalog: Option<Rc<man::Catalog>>,
+    whatis: Option<Rc<whatis::Database>>,
+    info_directory: Option<Rc<info::Directory>>,
+    books: Option<(Rc<Vec<devhelp::Book>>, Rc<symbols::Index>)>,
+    /// Shared with the threads that run searches.
+    search_index: Option<Arc<search::Index>>,
+    updating_search_index: bool,
+    /// Documentation changed while the index was being updated.
+    search_index_outdated: bool,
+    search_index_update_scheduled: bool,
+    /// By directory, for the directories in [`search::watched_dirs`].
+    monitors: HashMap<PathBuf, gio::FileMonitor>,
+    /// The sidebars to pass what is loaded on to.
+    sidebars: Vec<relm4::Sender<NavSidebarMsg>>,
+}
+
+/// Passes what has been loaded so far on to a new sidebar, and later whatever else is loaded.
+///
+/// The first sidebar starts loading everything.
+fn subscribe_to_documentation(sidebar: relm4::Sender<NavSidebarMsg>) {
+    let start = DOCUMENTATION.with_borrow_mut(|documentation| {
+        if let Some(catalog) = &documentation.catalog {
+            sidebar.emit(NavSidebarMsg::LoadedCatalog(catalog.clone()));
+        }
+        if let Some(database) = &documentation.whatis {
+            sidebar.emit(NavSidebarMsg::LoadedWhatis(database.clone()));
+        }
+        if let Some(directory) = &documentation.info_directory {
+            sidebar.emit(NavSidebarMsg::LoadedInfoDirectory(directory.clone()));
+        }
+        if let Some((books, symbols)) = &documentation.books {
+            sidebar.emit(NavSidebarMsg::LoadedBooks(books.clone(), symbols.clone()));
+        }
+        if let Some(index) = &documentation.search_index {
+            sidebar.emit(NavSidebarMsg::LoadedSearchIndex(index.clone()));
+        }
+
+        documentation.sidebars.push(sidebar);
+        !std::mem::replace(&mut documentation.loading, true)
+    });
+
+    if start {
+        load_documentation();
+    }
+}
+
+/// Records something that finished loading and passes it on to every sidebar.
+fn loaded_documentation(
+    record: impl FnOnce(&mut Documentation),
+    message: impl Fn() -> NavSidebarMsg,
+) {
+    DOCUMENTATION.with_borrow_mut(|documentation| {
+        record(documentation);
+        // Sidebars of closed windows are gone.
+        documentation
+            .sidebars
+            .retain(|sidebar| sidebar.send(message()).is_ok());
+    });
+}
+
+fn load_documentation() {
+    glib::spawn_future_local(async move {
+        match gio::spawn_blocking(man::Catalog::scan).await {
+            Ok(catalog) => {
+                let catalog = Rc::new(catalog);
+                loaded_documentation(
+                    |documentation| documentation.catalog = Some(catalog.clone()),
+                    || NavSidebarMsg::LoadedCatalog(catalog.clone()),
+                );
+            }
+            Err(_) => eprintln!("Man page catalog scan panicked"),
+        }
+    });
+
+    glib::spawn_future_local(async move {
+        match gio::spawn_blocking(whatis::Database::load).await {
+            Ok(database) => {
+                let database = Rc::new(database);
+                loaded_documentation(
+                    |documentation| documentation.whatis = Some(database.clone()),
+                    || NavSidebarMsg::LoadedWhatis(database.clone()),
+                );
+            }
+            Err(_) => eprintln!("whatis database load panicked"),
+        }
+    });
+
+    glib::spawn_future_local(async move {
+        match gio::spawn_blocking(info::Directory::load).await {
+            Ok(directory) => {
+                let directory = Rc::new(directory);
+                loaded_documentation(
+                    |documentation| documentation.info_directory = Some(directory.clone()),
+                    || NavSidebarMsg::LoadedInfoDirectory(directory.clone()),
+                );
+            }
+            Err(_) => eprintln!("Info directory scan panicked"),
+        }
+    });
+
+    glib::spawn_future_local(async move {
+        let scan = || {
+            let books = devhelp::books();
+            let symbols = symbols::Index::build(gidocgen::projects(), &books);
+            (books, symbols)
+        };
+
+        match gio::spawn_blocking(scan).await {
+            Ok((books, symbols)) => {
+                let (books, symbols) = (Rc::new(books), Rc::new(symbols));
+                loaded_documentation(
+                    |documentation| documentation.books = Some((books.clone(), symbols.clone())),
+                    || NavSidebarMsg::LoadedBooks(books.clone(), symbols.clone()),
+                );
+            }
+            Err(_) => eprintln!("HTML docs scan panicked"),
+        }
+    });
+
+    update_search_index();
+}
+
+/// Brings the search index up to date with the installed documentation on another thread.
+///
+/// Searches keep using the current index until the update is done, and only one update runs
+/// at a time.
+fn update_search_index() {
+    let current = DOCUMENTATION.with_borrow_mut(|documentation| {
+        documentation.search_index_update_scheduled = false;
+
+        if documentation.updating_search_index {
+            documentation.search_index_outdated = true;
+            return None;
+        }
+        documentation.updating_search_index = true;
+
+        Some(documentation.search_index.clone())
+    });
+    let Some(current) = current else {
+        return;
+    };
+
+    glib::spawn_future_local(async move {
+        let update = move || {
+            let mut index = match current {
+                Some(index) => search::Index::clone(&index),
+                None => search::Index::load(),
+            };
+
+            if index.update()
+                && let Err(e) = index.save()
+            {
+                eprintln!("Could not save the search index: {}", e);
+            }
+            (index, search::watched_dirs())
+        };
+
+        let result = gio::spawn_blocking(update).await;
+
+        let outdated = DOCUMENTATION.with_borrow_mut(|documentation| {
+            documentation.updating_search_index = false;
+            std::mem::take(&mut documentation.search_index_outdated)
+        });
+
+        match result {
+            Ok((index, dirs)) => {
+                let index = Arc::new(index);
+                loaded_documentation(
+                    |documentation| documentation.search_index = Some(index.clone()),
+                    || NavSidebarMsg::LoadedSearchIndex(index.clone()),
+                );
+                watch_documentation(dirs);
+            }
+            Err(_) => eprintln!("Search index update panicked"),
+        }
+
+        if outdated {
+            update_search_index();
+        }
+    });
+}
+
+/// Watches `dirs` for documentation being installed, changed or removed, and stops
+/// watching directories that are no longer among them.
+fn watch_documentation(dirs: Vec<PathBuf>) {
+    DOCUMENTATION.with_borrow_mut(|documentation| {
+        documentation.monitors.retain(|dir, _| dirs.contains(dir));
+
+        for dir in dirs {
+            if documentation.monitors.contains_key(&dir) {
+                continue;
+            }
+
+            let monitor = match gio::File::for_path(&dir)
+                .monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE)
+            {
+                Ok(monitor) => monitor,
+                Err(e) => {
+                    eprintln!("Could not watch {}: {}", dir.display(), e);
+                    continue;
+                }
+            };
+
+            monitor.connect_changed(|_, _, _, event| {
+                if event != gio::FileMonitorEvent::AttributeChanged {
+                    documentation_changed();
+                }
+            });
+
+            documentation.monitors.insert(dir, monitor);
+        }
+    });
+}
+
+/// Updates the search index after [`SEARCH_INDEX_UPDATE_DELAY`], unless that is already planned.
+fn documentation_changed() {
+    let scheduled = DOCUMENTATION.with_borrow_mut(|documentation| {
+        std::mem::replace(&mut documentation.search_index_update_scheduled, true)
+    });
+
+    if !scheduled {
+        glib::timeout_add_seconds_local_once(SEARCH_INDEX_UPDATE_DELAY, update_search_index);
+    }
+}
 
 #[derive(Debug)]
-enum NavSidebarMsg {}
+struct NavSidebar {
+    mode: NavMode,
+    catalog: Option<Rc<man::Catalog>>,
+    whatis: Option<Rc<whatis::Database>>,
+    apropos_query: String,
+    apropos_regex: bool,
+    apropos_error: Option<String>,
+    info_directory: Option<Rc<info::Directory>>,
+    books: Option<Rc<Vec<devhelp::Book>>>,
+    symbols: Rc<symbols::Index>,
+    symbol_query: String,
+    search_index: Option<Arc<search::Index>>,
+    searching: bool,
+    search_query: String,
+    bookmarks: bookmarks::Bookmarks,
+    history: history::History,
+    history_filter: String,
+    man_pages: NavTree,
+    info_manuals: NavTree,
+    html_books: NavTree,
+    saved_bookmarks: NavTree,
+    visited: NavTree,
+    search_results: TypedListView<search::Hit, gtk::NoSelection>,
+}
 
 #[derive(Debug)]
-enum NavSidebarResponse {}
+enum NavSidebarMsg {
+    SelectMode(u32),
+    LoadedCatalog(Rc<man::Catalog>),
+    LoadedWhatis(Rc<whatis::Database>),
+    Apropos(String),
+    SetAproposRegex(bool),
+    LoadedInfoDirectory(Rc<info::Directory>),
+    LoadedBooks(Rc<Vec<devhelp::Book>>, Rc<symbols::Index>),
+    SearchSymbols(String),
+    LoadedSearchIndex(Arc<search::Index>),
+    ToggleSearch(bool),
+    Search(String),
+    FoundResults(String, Vec<search::Hit>),
+    UpdatedBookmarks(bookmarks::Bookmarks),
+    EditBookmark(String),
+    DeleteBookmark(String),
+    LoadedHistory(history::History),
+    Visited(history::Visit),
+    FilterHistory(String),
+    ActivateItem(NavMode, u32),
+    ActivateResult(u32),
+}
+
+#[derive(Debug)]
+enum NavSidebarResponse {
+    OpenURI(String),
+    EditBookmark {
+        target: String,
+        title: String,
+        folder: String,
+        tags: Vec<String>,
+    },
+    DeleteBookmark(String),
+}
 
 #[relm4::component(async)]
 impl SimpleAsyncComponent for NavSidebar {
@@ -800,74 +2398,789 @@ impl SimpleAsyncComponent for NavSidebar {
                         "Man pages",
                         "Texinfo",
                         "HTML docs",
+                        "Bookmarks",
+                        "History",
                     ]),
+
+                    connect_selected_notify[sender] => move |dropdown| {
+                        sender.input(NavSidebarMsg::SelectMode(dropdown.selected()));
+                    },
                 },
 
                 pack_start = &gtk::Box {
                     #[name="search_start"]
                     gtk::ToggleButton {
                         set_icon_name: "edit-find-symbolic",
+                        set_tooltip_text: Some("Search all documentation"),
+
+                        connect_toggled[sender, search_entry] => move |button| {
+                            sender.input(NavSidebarMsg::ToggleSearch(button.is_active()));
+
+                            if button.is_active() {
+                                search_entry.grab_focus();
+                            }
+                        },
                     },
                 }
             },
 
-            gtk::Box {
-                set_orientation: Orientation::Vertical,
-                set_spacing: 3,
-                set_hexpand: true,
-                set_align: gtk::Align::Fill,
-
-                gtk::Label { set_label: "Nav entry 1" },
-                gtk::Label { set_label: "Nav entry 2" },
-                gtk::Label { set_label: "Nav entry 3" },
-                gtk::Label { set_label: "Nav entry 4" },
-                gtk::Label { set_label: "Nav entry 5" },
-                gtk::Label { set_label: "Nav entry 6" },
-            },
-        }
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+
+                #[name="search_entry"]
+                gtk::SearchEntry {
+                    set_margin_all: 6,
+                    set_placeholder_text: Some("Search all documentation"),
+
+                    #[watch]
+                    set_visible: model.searching,
+
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(NavSidebarMsg::Search(entry.text().to_string()));
+                    },
+
+                    connect_stop_search[search_start] => move |_| {
+                        search_start.set_active(false);
+                    },
+                },
+
+                gtk::Box {
+                    set_spacing: 6,
+                    set_margin_all: 6,
+
+                    #[watch]
+                    set_visible: !model.searching && model.mode == NavMode::ManPages,
+
+                    gtk::SearchEntry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("Search names and descriptions"),
+
+                        connect_search_changed[sender] => move |entry| {
+                            sender.input(NavSidebarMsg::Apropos(entry.text().to_string()));
+                        },
+                    },
+
+                    gtk::ToggleButton {
+                        set_label: ".*",
+                        set_tooltip_text: Some("Regular expression"),
+
+                        connect_toggled[sender] => move |button| {
+                            sender.input(NavSidebarMsg::SetAproposRegex(button.is_active()));
+                        },
+                    },
+                },
+
+                gtk::Label {
+                    set_margin_horizontal: 6,
+                    set_xalign: 0.0,
+                    set_wrap: true,
+                    add_css_class: "error",
+                    add_css_class: "caption",
+
+                    #[watch]
+                    set_visible: !model.searching
+                        && model.mode == NavMode::ManPages
+                        && model.apropos_error.is_some(),
+                    #[watch]
+                    set_label: model.apropos_error.as_deref().unwrap_or_default(),
+                },
+
+                gtk::SearchEntry {
+                    set_margin_all: 6,
+                    set_placeholder_text: Some("Search symbols"),
+
+                    #[watch]
+                    set_visible: !model.searching && model.mode == NavMode::HtmlDocs,
+
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(NavSidebarMsg::SearchSymbols(entry.text().to_string()));
+                    },
+                },
+
+                gtk::SearchEntry {
+                    set_margin_all: 6,
+                    set_placeholder_text: Some("Filter history"),
+
+                    #[watch]
+                    set_visible: !model.searching && model.mode == NavMode::History,
+
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(NavSidebarMsg::FilterHistory(entry.text().to_string()));
+                    },
+                },
+
+                gtk::ScrolledWindow {
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+                    set_vexpand: true,
+
+                    #[wrap(Some)]
+                    set_child = match model.page() {
+                        NavPage::Browse(NavMode::ManPages) => {
+                            #[local_ref]
+                            *man_pages -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavSidebarMsg::ActivateItem(NavMode::ManPages, position));
+                                },
+                            }
+                        },
+                        NavPage::Browse(NavMode::Texinfo) => {
+                            #[local_ref]
+                            *info_manuals -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavSidebarMsg::ActivateItem(NavMode::Texinfo, position));
+                                },
+                            }
+                        },
+                        NavPage::Browse(NavMode::HtmlDocs) => {
+                            #[local_ref]
+                            *html_books -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavSidebarMsg::ActivateItem(NavMode::HtmlDocs, position));
+                                },
+                            }
+                        },
+                        NavPage::Browse(NavMode::Bookmarks) => {
+                            #[local_ref]
+                            *saved_bookmarks -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavSidebarMsg::ActivateItem(NavMode::Bookmarks, position));
+                                },
+                            }
+                        },
+                        NavPage::Browse(NavMode::History) => {
+                            #[local_ref]
+                            *visited -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavSidebarMsg::ActivateItem(NavMode::History, position));
+                                },
+                            }
+                        },
+                        NavPage::Search => {
+                            #[local_ref]
+                            *search_results -> gtk::ListView {
+                                add_css_class: "navigation-sidebar",
+                                set_single_click_activate: true,
+
+                                connect_activate[sender] => move |_, position| {
+                                    sender.input(NavSidebarMsg::ActivateResult(position));
+                                },
+                            }
+                        },
+                        NavPage::Loading => {
+                            gtk::Spinner {
+                                set_halign: gtk::Align::Center,
+                                set_valign: gtk::Align::Center,
+                                set_spinning: true,
+                            }
+                        },
+                    }
+                },
+            },
+        }
+    }
+
+    async fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let model = NavSidebar {
+            mode: NavMode::ManPages,
+            catalog: None,
+            whatis: None,
+            apropos_query: String::new(),
+            apropos_regex: false,
+            apropos_error: None,
+            info_directory: None,
+            books: None,
+            symbols: Rc::default(),
+            symbol_query: String::new(),
+            search_index: None,
+            searching: false,
+            search_query: String::new(),
+            bookmarks: bookmarks::Bookmarks::default(),
+            history: history::History::default(),
+            history_filter: String::new(),
+            man_pages: NavTree::new(),
+            info_manuals: NavTree::new(),
+            html_books: NavTree::new(),
+            saved_bookmarks: NavTree::new(),
+            visited: NavTree::new(),
+            search_results: TypedListView::new(),
+        };
+
+        let man_pages = &model.man_pages.view;
+        let info_manuals = &model.info_manuals.view;
+        let html_books = &model.html_books.view;
+        let saved_bookmarks = &model.saved_bookmarks.view;
+        let visited = &model.visited.view;
+        let search_results = &model.search_results.view;
+
+        let widgets = view_output!();
+
+        let edit_sender = sender.clone();
+        let edit_action: RelmAction<EditBookmarkAction> =
+            RelmAction::new_with_target_value(move |_, target| {
+                edit_sender.input(NavSidebarMsg::EditBookmark(target));
+            });
+
+        let delete_sender = sender.clone();
+        let delete_action: RelmAction<DeleteBookmarkAction> =
+            RelmAction::new_with_target_value(move |_, target| {
+                delete_sender.input(NavSidebarMsg::DeleteBookmark(target));
+            });
+
+        let mut bookmark_actions = RelmActionGroup::<BookmarkActionGroup>::new();
+        bookmark_actions.add_action(edit_action);
+        bookmark_actions.add_action(delete_action);
+        bookmark_actions.register_for_widget(&root);
+
+        subscribe_to_documentation(sender.input_sender().clone());
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
+        match msg {
+            NavSidebarMsg::SelectMode(position) => {
+                self.mode = NavMode::from_position(position);
+
+                // The history list is only kept up to date while it is shown.
+                if self.mode == NavMode::History {
+                    self.fill(NavMode::History);
+                }
+            }
+            NavSidebarMsg::LoadedCatalog(catalog) => {
+                self.catalog = Some(catalog);
+                self.fill(NavMode::ManPages);
+            }
+            NavSidebarMsg::LoadedWhatis(database) => {
+                self.whatis = Some(database);
+                self.fill(NavMode::ManPages);
+            }
+            NavSidebarMsg::Apropos(query) => {
+                self.apropos_query = query;
+                self.apropos_error = None;
+                self.fill(NavMode::ManPages);
+            }
+            NavSidebarMsg::SetAproposRegex(regex) => {
+                self.apropos_regex = regex;
+                self.apropos_error = None;
+                self.fill(NavMode::ManPages);
+            }
+            NavSidebarMsg::LoadedInfoDirectory(directory) => {
+                self.info_directory = Some(directory);
+                self.fill(NavMode::Texinfo);
+            }
+            NavSidebarMsg::LoadedBooks(books, symbols) => {
+                self.books = Some(books);
+                self.symbols = symbols;
+                self.fill(NavMode::HtmlDocs);
+            }
+            NavSidebarMsg::SearchSymbols(query) => {
+                self.symbol_query = query;
+                self.fill(NavMode::HtmlDocs);
+            }
+            NavSidebarMsg::LoadedSearchIndex(index) => {
+                self.search_index = Some(index);
+                self.search(sender);
+            }
+            NavSidebarMsg::ToggleSearch(searching) => {
+                self.searching = searching;
+            }
+            NavSidebarMsg::Search(query) => {
+                self.search_query = query;
+                self.search(sender);
+            }
+            NavSidebarMsg::FoundResults(query, hits) => {
+                // Results for a query the user has typed past are stale.
+                if query == self.search_query {
+                    self.search_results.clear();
+                    self.search_results.extend_from_iter(hits);
+                }
+            }
+            NavSidebarMsg::UpdatedBookmarks(bookmarks) => {
+                self.bookmarks = bookmarks;
+                self.fill(NavMode::Bookmarks);
+            }
+            NavSidebarMsg::EditBookmark(target) => {
+                self.edit_bookmark(&target, sender);
+            }
+            NavSidebarMsg::DeleteBookmark(target) => {
+                let _ = sender.output(NavSidebarResponse::DeleteBookmark(target));
+            }
+            NavSidebarMsg::LoadedHistory(history) => {
+                self.history = history;
+                self.fill(NavMode::History);
+            }
+            NavSidebarMsg::Visited(visit) => {
+                self.history.push(visit);
+
+                if self.mode == NavMode::History {
+                    self.fill(NavMode::History);
+                }
+            }
+            NavSidebarMsg::FilterHistory(filter) => {
+                self.history_filter = filter;
+                self.fill(NavMode::History);
+            }
+            NavSidebarMsg::ActivateResult(position) => {
+                if let Some(hit) = self.search_results.get(position) {
+                    let uri = hit.borrow().uri.clone();
+                    let _ = sender.output(NavSidebarResponse::OpenURI(uri));
+                }
+            }
+            NavSidebarMsg::ActivateItem(mode, position) => {
+                let Some(row) = self.tree(mode).row(position) else {
+                    return;
+                };
+
+                match row_nav_item(&row) {
+                    Some(NavItem::Group { uri, .. }) => {
+                        // Groups with a page of their own open it as they expand.
+                        let expanding = !row.is_expanded();
+                        row.set_expanded(expanding);
+
+                        if expanding && let Some(uri) = uri {
+                            let _ = sender.output(NavSidebarResponse::OpenURI(uri));
+                        }
+                    }
+                    Some(NavItem::Link { uri, .. } | NavItem::Bookmark { target: uri, .. }) => {
+                        let _ = sender.output(NavSidebarResponse::OpenURI(uri));
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+impl NavSidebar {
+    fn page(&self) -> NavPage {
+        let loaded = match self.mode {
+            _ if self.searching => self.search_index.is_some(),
+            NavMode::ManPages if !self.apropos_query.trim().is_empty() => self.whatis.is_some(),
+            NavMode::ManPages => self.catalog.is_some(),
+            NavMode::Texinfo => self.info_directory.is_some(),
+            NavMode::HtmlDocs => self.books.is_some(),
+            NavMode::Bookmarks | NavMode::History => true,
+        };
+
+        match (loaded, self.searching) {
+            (false, _) => NavPage::Loading,
+            (true, true) => NavPage::Search,
+            (true, false) => NavPage::Browse(self.mode),
+        }
+    }
+
+    /// Runs the search query on another thread, so ranking a large index doesn't block the UI.
+    fn search(&mut self, sender: AsyncComponentSender<Self>) {
+        let Some(index) = self.search_index.clone() else {
+            return;
+        };
+
+        let query = self.search_query.clone();
+        if query.trim().is_empty() {
+            self.search_results.clear();
+            return;
+        }
+
+        glib::spawn_future_local(async move {
+            let search_query = query.clone();
+            let search = move || index.search(&search_query, MAX_SEARCH_RESULTS);
+
+            match gio::spawn_blocking(search).await {
+                Ok(hits) => sender.input(NavSidebarMsg::FoundResults(query, hits)),
+                Err(_) => eprintln!("Search panicked"),
+            }
+        });
+    }
+
+    fn tree(&self, mode: NavMode) -> &NavTree {
+        match mode {
+            NavMode::ManPages => &self.man_pages,
+            NavMode::Texinfo => &self.info_manuals,
+            NavMode::HtmlDocs => &self.html_books,
+            NavMode::Bookmarks => &self.saved_bookmarks,
+            NavMode::History => &self.visited,
+        }
+    }
+
+    /// Asks for a bookmark's new title, folder and tags.
+    fn edit_bookmark(&self, target: &str, sender: AsyncComponentSender<Self>) {
+        let Some(bookmark) = self.bookmarks.get(target) else {
+            return;
+        };
+
+        relm4::view! {
+            dialog = adw::AlertDialog {
+                set_heading: Some("Edit Bookmark"),
+
+                add_response: ("cancel", "Cancel"),
+                add_response: ("save", "Save"),
+                set_response_appearance: ("save", adw::ResponseAppearance::Suggested),
+                set_default_response: Some("save"),
+                set_close_response: "cancel",
+
+                #[wrap(Some)]
+                set_extra_child = &gtk::ListBox {
+                    add_css_class: "boxed-list",
+                    set_selection_mode: gtk::SelectionMode::None,
+
+                    #[name = "title"]
+                    adw::EntryRow {
+                        set_title: "Title",
+                        set_text: &bookmark.title,
+                        set_activates_default: true,
+                    },
+
+                    #[name = "folder"]
+                    adw::EntryRow {
+                        set_title: "Folder",
+                        set_text: bookmark.folder.as_deref().unwrap_or_default(),
+                        set_activates_default: true,
+                    },
+
+                    #[name = "tags"]
+                    adw::EntryRow {
+                        set_title: "Tags, separated by commas",
+                        set_text: &bookmark.tags.join(", "),
+                        set_activates_default: true,
+                    },
+                },
+            }
+        }
+
+        let target = target.to_string();
+        dialog.connect_response(Some("save"), move |_, _| {
+            let _ = sender.output(NavSidebarResponse::EditBookmark {
+                target: target.clone(),
+                title: title.text().to_string(),
+                folder: folder.text().to_string(),
+                tags: tags.text().split(',').map(str::to_string).collect(),
+            });
+        });
+
+        dialog.present(relm4::main_application().active_window().as_ref());
+    }
+
+    /// Rebuilds the tree for `mode`.
+    fn fill(&mut self, mode: NavMode) {
+        let nodes: Vec<NavNode> = match mode {
+            NavMode::ManPages if !self.apropos_query.trim().is_empty() => {
+                let Some(database) = &self.whatis else { return };
+
+                let pattern = match whatis::Pattern::new(&self.apropos_query, self.apropos_regex) {
+                    Ok(pattern) => pattern,
+                    Err(e) => {
+                        self.apropos_error = Some(e.to_string());
+                        self.man_pages.clear();
+                        return;
+                    }
+                };
+
+                database
+                    .search(&pattern)
+                    .into_iter()
+                    .map(|group| {
+                        let title = format!(
+                            "<b>{}</b>  {}",
+                            glib::markup_escape_text(group.section),
+                            man::section_title(group.section)
+                        );
+                        let entries = group.entries.iter().map(|entry| {
+                            NavNode::link(
+                                format!("{} — {}", entry.page.name, entry.description),
+                                entry.page.uri(),
+                                Some(entry.description.clone()),
+                            )
+                        });
+
+                        NavNode::group(format!("apropos/{}", group.section), title, None, true)
+                            .with_children(entries.collect())
+                    })
+                    .collect()
+            }
+            NavMode::ManPages => {
+                let Some(catalog) = &self.catalog else { return };
+
+                catalog
+                    .sections
+                    .iter()
+                    .map(|group| {
+                        let title = format!(
+                            "<b>{}</b>  {}",
+                            glib::markup_escape_text(&group.section),
+                            man::section_title(&group.section)
+                        );
+                        let pages = group.pages.iter().map(|name| {
+                            let uri = man::ManPageRef::new(name, Some(&group.section)).uri();
+                            NavNode::link(name.clone(), uri, None)
+                        });
+
+                        NavNode::group(group.section.clone(), title, None, false)
+                            .with_children(pages.collect())
+                    })
+                    .collect()
+            }
+            NavMode::Texinfo => {
+                let Some(directory) = &self.info_directory else {
+                    return;
+                };
+
+                directory
+                    .categories
+                    .iter()
+                    .map(|category| {
+                        let title = glib::markup_escape_text(&category.name).to_string();
+                        let entries = category.entries.iter().map(|entry| {
+                            NavNode::link(
+                                entry.label.clone(),
+                                entry.target.uri(),
+                                Some(entry.description.clone()).filter(|d| !d.is_empty()),
+                            )
+                        });
+
+                        NavNode::group(category.name.clone(), title, None, false)
+                            .with_children(entries.collect())
+                    })
+                    .collect()
+            }
+            NavMode::HtmlDocs if !self.symbol_query.trim().is_empty() => {
+                let results = self.symbols.search(&self.symbol_query, MAX_SYMBOL_RESULTS);
+
+                results
+                    .into_iter()
+                    .map(|symbol| {
+                        let mut description = symbol.kind.label().to_string();
+                        if symbol.deprecated {
+                            description.push_str(", deprecated");
+                        }
+                        if !symbol.summary.is_empty() {
+                            description = format!("{}\n{}", description, symbol.summary);
+                        }
+
+                        NavNode::link(symbol.name.clone(), symbol.uri.clone(), Some(description))
+                    })
+                    .collect()
+            }
+            NavMode::HtmlDocs => {
+                let Some(books) = &self.books else { return };
+
+                books
+                    .iter()
+                    .map(|book| {
+                        let title = glib::markup_escape_text(&book.title).to_string();
+
+                        NavNode::group(book.id.clone(), title, Some(book.uri.clone()), false)
+                            .with_children(chapter_nodes(&book.id, &book.chapters))
+                    })
+                    .collect()
+            }
+            NavMode::Bookmarks => {
+                let mut nodes = Vec::new();
+
+                for folder in self.bookmarks.folders() {
+                    let bookmarks = folder.bookmarks.iter().map(|bookmark| {
+                        let mut description = bookmark.target();
+                        if !bookmark.tags.is_empty() {
+                            description =
+                                format!("{}\nTags: {}", description, bookmark.tags.join(", "));
+                        }
+
+                        outline::Node {
+                            item: NavItem::Bookmark {
+                                label: bookmark.title.clone(),
+                                target: bookmark.target(),
+                                description: Some(description),
+                            },
+                            children: Vec::new(),
+                        }
+                    });
+
+                    // Bookmarks outside any folder are listed at the top level.
+                    match folder.name {
+                        Some(name) => {
+                            let title = glib::markup_escape_text(name).to_string();
+                            nodes.push(
+                                NavNode::group(name.to_string(), title, None, true)
+                                    .with_children(bookmarks.collect()),
+                            );
+                        }
+                        None => nodes.extend(bookmarks),
+                    }
+                }
+
+                nodes
+            }
+            NavMode::History => self
+                .history
+                .days(&self.history_filter)
+                .into_iter()
+                .map(|day| {
+                    let title = glib::markup_escape_text(&day.label).to_string();
+                    let visits = day.visits.iter().map(|visit| {
+                        let label = if visit.title.is_empty() {
+                            visit.uri.clone()
+                        } else {
+                            visit.title.clone()
+                        };
+                        let description = format!(
+                            "{}\n{}, {}",
+                            visit.uri,
+                            visit.kind.label(),
+                            visit.local_time()
+                        );
+
+                        NavNode::link(label, visit.uri.clone(), Some(description))
+                    });
+
+                    NavNode::group(day.key, title, None, true).with_children(visits.collect())
+                })
+                .collect(),
+        };
+
+        self.tree(mode).set(&nodes);
     }
+}
 
-    async fn init(
-        _init: Self::Init,
-        root: Self::Root,
-        _sender: AsyncComponentSender<Self>,
-    ) -> AsyncComponentParts<Self> {
-        let model = NavSidebar {};
-        let widgets = view_output!();
-        AsyncComponentParts { model, widgets }
-    }
+/// The nodes for a book's chapters, with chapters that have sections of their own as groups.
+fn chapter_nodes(parent_key: &str, chapters: &[devhelp::Chapter]) -> Vec<NavNode> {
+    chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| {
+            if chapter.children.is_empty() {
+                return NavNode::link(chapter.name.clone(), chapter.uri.clone(), None);
+            }
 
-    async fn update(&mut self, msg: Self::Input, _sender: AsyncComponentSender<Self>) {
-        match msg {}
-    }
+            let key = format!("{}/{}", parent_key, i);
+            let title = glib::markup_escape_text(&chapter.name).to_string();
+            let children = chapter_nodes(&key, &chapter.children);
+
+            NavNode::group(key, title, Some(chapter.uri.clone()), false).with_children(children)
+        })
+        .collect()
 }
 
 #[derive(Debug)]
 enum AppMsg {
     NewTab,
+    CloseTab,
+    CloseTabAt(usize),
+    ReopenClosedTab,
+    DuplicateTab,
+    SetTabPinned(bool),
+    SyncTabOrder,
+    SetMenuTab(Option<usize>),
+    MoveTabToNewWindow,
+    /// A tab was moved to another window, so this one lets it go.
+    RemoveMovedTab(DynamicIndex),
+    TabDetached(usize),
+    TabAttached(usize),
     GoBack,
     GoForward,
+    /// The tab's back and forward state, for the actions if it is selected.
+    UpdateNavState(DynamicIndex, NavigationState),
+    /// A tab's page details, for the navigation bar if it is selected.
+    UpdateNavBar(DynamicIndex, NavBarMsg),
+    FocusAddress,
+    Zoom(Zoom),
+    Reload,
     UpdateSidebarVisibility(bool),
     SelectTab(DynamicIndex),
-    SelectHeading(HTMLHeading),
-    UpdateOutline(Option<Vec<HTMLHeading>>),
+    SelectHeading(outline::Entry),
+    UpdateOutline(DynamicIndex, PageOutline),
+    UpdateCurrentHeading(DynamicIndex, Option<usize>),
+    /// Going to a heading of a tab's outline failed.
+    OutlineError(DynamicIndex, outline::Error),
     UpdateURI(String),
+    ToggleBookmark,
+    EditBookmark {
+        target: String,
+        title: String,
+        folder: String,
+        tags: Vec<String>,
+    },
+    DeleteBookmark(String),
+    /// Bookmarks saved by another window.
+    UpdatedBookmarks(bookmarks::Bookmarks),
+    SaveSession,
+    RecordVisit {
+        uri: String,
+        title: String,
+    },
+    /// A visit recorded by another window.
+    RecordedVisit(history::Visit),
+    SuggestURIs(String),
+    OpenTabs(Vec<String>),
+    ShowPreferences,
+    SetNewTabPage(settings::NewTabPage),
+    SetCustomURI(String),
+    /// Preferences saved by another window.
+    UpdatedSettings(settings::Settings),
+    ShowFind,
+    Find(find::Query),
+    FindNext,
+    FindPrevious,
+    FinishFind,
 }
 
+/// How often the open tabs are saved, so a crash loses little.
+const SESSION_SAVE_INTERVAL: u32 = 30;
+
+/// How many closed tabs can be reopened.
+const MAX_CLOSED_TABS: usize = 20;
+
 #[derive(Debug)]
 struct AppModel {
-    starting_uri: String,
+    settings: settings::Settings,
     tabs: AsyncFactoryVecDeque<TabModel>,
     nav_bar: AsyncController<NavBarModel>,
     nav_sidebar: AsyncController<NavSidebar>,
     current_tab: Option<DynamicIndex>,
     outline_sidebar: AsyncController<OutlineSidebarModel>,
     sidebar_visible: bool,
+    /// Shows errors over the tabs.
+    toasts: adw::ToastOverlay,
+    bookmarks: bookmarks::Bookmarks,
+    history: history::History,
+    window: adw::ApplicationWindow,
+    /// The tabs as last saved.
+    saved_state: Option<session::WindowState>,
+    /// Disabled when the selected tab can't go that way.
+    back_action: gio::SimpleAction,
+    forward_action: gio::SimpleAction,
+    /// Closed tabs with their positions, most recently closed last.
+    closed_tabs: Vec<(usize, session::TabState)>,
+    reopen_action: gio::SimpleAction,
+    /// Shows whether the tab a menu was opened for is pinned.
+    pin_action: gio::SimpleAction,
+    /// The tab whose context menu is open; tab actions apply to the selected tab otherwise.
+    menu_tab: Option<DynamicIndex>,
+    /// Set while the factory removes a tab, so its page is allowed to close.
+    removing_tab: Rc<Cell<bool>>,
 }
 
 #[relm4::component(async)]
 impl SimpleAsyncComponent for AppModel {
-    type Init = String;
+    /// The tabs to restore; the window opens a new tab if there are none.
+    type Init = session::WindowState;
 
     type Input = AppMsg;
     type Output = Nothing;
@@ -905,7 +3218,7 @@ impl SimpleAsyncComponent for AppModel {
                                 set_expand_tabs: true,
 
                                 #[watch]
-                                set_view: Some(&model.tabs.widget()),
+                                set_view: Some(model.tabs.widget()),
                             },
                         },
 
@@ -913,7 +3226,10 @@ impl SimpleAsyncComponent for AppModel {
                             set_sidebar_position: gtk::PackType::End,
 
                             #[wrap(Some)]
-                            set_content = model.tabs.widget(),
+                            #[local_ref]
+                            set_content = toasts -> adw::ToastOverlay {
+                                set_child: Some(model.tabs.widget()),
+                            },
 
                             #[wrap(Some)]
                             set_sidebar = model.outline_sidebar.widget(),
@@ -927,46 +3243,226 @@ impl SimpleAsyncComponent for AppModel {
         }
     }
 
+    menu! {
+        tab_menu: {
+            section! {
+                "Pin Tab" => PinTabAction,
+                "Duplicate Tab" => DuplicateTabAction,
+                "Move to New Window" => MoveTabToNewWindowAction,
+            },
+            section! {
+                "Reopen Closed Tab" => ReopenClosedTabAction,
+                "Close Tab" => CloseTabAction,
+            },
+        }
+    }
+
     async fn init(
-        starting_uri: Self::Init,
+        state: Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
+        let settings = settings::Settings::load().unwrap_or_else(|e| {
+            eprintln!("Could not load preferences: {}", e);
+            Default::default()
+        });
+        let new_tab_uri = settings.new_tab_uri();
+
         let mut tabs = AsyncFactoryVecDeque::builder()
             .launch(adw::TabView::default())
             .forward(sender.input_sender(), |msg| match msg {
                 TabResponse::SelectTab(i) => AppMsg::SelectTab(i),
-                TabResponse::UpdateOutline(o) => AppMsg::UpdateOutline(o),
+                TabResponse::NavBar(i, msg) => AppMsg::UpdateNavBar(i, msg),
+                TabResponse::UpdateNavState(i, state) => AppMsg::UpdateNavState(i, state),
+                TabResponse::UpdateOutline(i, o) => AppMsg::UpdateOutline(i, o),
+                TabResponse::UpdateCurrentHeading(i, h) => AppMsg::UpdateCurrentHeading(i, h),
+                TabResponse::OutlineError(i, e) => AppMsg::OutlineError(i, e),
+                TabResponse::Visited { uri, title } => AppMsg::RecordVisit { uri, title },
             });
 
-        let initial_tab = tabs.guard().push_back(starting_uri.clone());
+        let initial_tab = if state.tabs.is_empty() {
+            tabs.guard().push_back(session::TabState::new(&new_tab_uri))
+        } else {
+            let mut guard = tabs.guard();
+            let indices: Vec<DynamicIndex> = state
+                .tabs
+                .iter()
+                .map(|tab| guard.push_back(tab.clone()))
+                .collect();
+            drop(guard);
+
+            let selected = state.selected.min(indices.len() - 1);
+            let tab_view: &adw::TabView = tabs.widget();
+            tab_view.set_selected_page(&tab_view.nth_page(selected as i32));
+
+            // Pinned tabs were saved first, so pinning them keeps the order.
+            for (position, tab) in state.tabs.iter().enumerate() {
+                if tab.pinned {
+                    tab_view.set_page_pinned(&tab_view.nth_page(position as i32), true);
+                }
+            }
+
+            indices[selected].clone()
+        };
 
         let nav_bar = NavBarModel::builder()
-            .launch_with_broker(starting_uri.clone(), &NAV_BAR_BROKER)
+            .launch(new_tab_uri)
             .forward(sender.input_sender(), identity);
 
         let nav_sidebar = NavSidebar::builder()
             .launch(())
-            .forward(sender.input_sender(), |_| unimplemented!());
-
-        let outline_sidebar = OutlineSidebarModel::builder()
-            .launch_with_broker((), &OUTLINE_SIDEBAR_BROKER)
             .forward(sender.input_sender(), |msg| match msg {
+                NavSidebarResponse::OpenURI(uri) => AppMsg::UpdateURI(uri),
+                NavSidebarResponse::EditBookmark {
+                    target,
+                    title,
+                    folder,
+                    tags,
+                } => AppMsg::EditBookmark {
+                    target,
+                    title,
+                    folder,
+                    tags,
+                },
+                NavSidebarResponse::DeleteBookmark(target) => AppMsg::DeleteBookmark(target),
+            });
+
+        let outline_sidebar = OutlineSidebarModel::builder().launch(()).forward(
+            sender.input_sender(),
+            |msg| match msg {
                 OutlineSidebarResponse::SelectHeading(heading) => AppMsg::SelectHeading(heading),
+            },
+        );
+
+        let bookmarks = bookmarks::Bookmarks::load().unwrap_or_else(|e| {
+            eprintln!("Could not load bookmarks: {}", e);
+            Default::default()
+        });
+
+        let history = history::History::load().unwrap_or_else(|e| {
+            eprintln!("Could not load history: {}", e);
+            Default::default()
+        });
+        nav_sidebar.emit(NavSidebarMsg::LoadedHistory(history.clone()));
+
+        let removing_tab = Rc::new(Cell::new(false));
+        connect_tab_view(tabs.widget(), &sender, removing_tab.clone());
+
+        let back_action = message_action::<GoBackAction>(&sender, || AppMsg::GoBack);
+        let forward_action = message_action::<GoForwardAction>(&sender, || AppMsg::GoForward);
+
+        let reopen_action =
+            message_action::<ReopenClosedTabAction>(&sender, || AppMsg::ReopenClosedTab);
+        reopen_action.gio_action().set_enabled(false);
+
+        let pin_sender = sender.clone();
+        let pin_action: RelmAction<PinTabAction> =
+            RelmAction::new_stateful(&false, move |_, pinned: &mut bool| {
+                *pinned = !*pinned;
+                pin_sender.input(AppMsg::SetTabPinned(*pinned));
             });
 
         let model = AppModel {
-            starting_uri,
+            settings,
             tabs,
             nav_bar,
             nav_sidebar,
             outline_sidebar,
             current_tab: Some(initial_tab),
             sidebar_visible: true,
+            toasts: adw::ToastOverlay::new(),
+            bookmarks,
+            history,
+            window: root.clone(),
+            saved_state: None,
+            back_action: back_action.gio_action().clone(),
+            forward_action: forward_action.gio_action().clone(),
+            closed_tabs: Vec::new(),
+            reopen_action: reopen_action.gio_action().clone(),
+            pin_action: pin_action.gio_action().clone(),
+            menu_tab: None,
+            removing_tab,
         };
 
+        model.publish_bookmarks();
+
+        let toasts = &model.toasts;
+
         let widgets = view_output!();
 
+        model.tabs.widget().set_menu_model(Some(&tab_menu));
+
+        let outline_sender = sender.clone();
+        let toggle_outline: RelmAction<ToggleOutlineAction> =
+            RelmAction::new_stateful(&model.sidebar_visible, move |_, visible: &mut bool| {
+                *visible = !*visible;
+                outline_sender.input(AppMsg::UpdateSidebarVisibility(*visible));
+            });
+
+        let mut window_actions = RelmActionGroup::<WindowActionGroup>::new();
+        window_actions.add_action(message_action::<NewTabAction>(&sender, || AppMsg::NewTab));
+        window_actions.add_action(message_action::<CloseTabAction>(&sender, || {
+            AppMsg::CloseTab
+        }));
+        window_actions.add_action(reopen_action);
+        window_actions.add_action(message_action::<DuplicateTabAction>(&sender, || {
+            AppMsg::DuplicateTab
+        }));
+        window_actions.add_action(pin_action);
+        window_actions.add_action(message_action::<MoveTabToNewWindowAction>(&sender, || {
+            AppMsg::MoveTabToNewWindow
+        }));
+        window_actions.add_action(back_action);
+        window_actions.add_action(forward_action);
+        window_actions.add_action(message_action::<FocusAddressAction>(&sender, || {
+            AppMsg::FocusAddress
+        }));
+        window_actions.add_action(toggle_outline);
+        window_actions.add_action(message_action::<FindAction>(&sender, || AppMsg::ShowFind));
+        window_actions.add_action(message_action::<FindNextAction>(&sender, || {
+            AppMsg::FindNext
+        }));
+        window_actions.add_action(message_action::<FindPreviousAction>(&sender, || {
+            AppMsg::FindPrevious
+        }));
+        window_actions.add_action(message_action::<ZoomInAction>(&sender, || {
+            AppMsg::Zoom(Zoom::In)
+        }));
+        window_actions.add_action(message_action::<ZoomOutAction>(&sender, || {
+            AppMsg::Zoom(Zoom::Out)
+        }));
+        window_actions.add_action(message_action::<ZoomResetAction>(&sender, || {
+            AppMsg::Zoom(Zoom::Reset)
+        }));
+        window_actions.add_action(message_action::<ReloadAction>(&sender, || AppMsg::Reload));
+        window_actions.add_action(message_action::<ToggleBookmarkAction>(&sender, || {
+            AppMsg::ToggleBookmark
+        }));
+        window_actions.add_action(message_action::<PreferencesAction>(&sender, || {
+            AppMsg::ShowPreferences
+        }));
+        window_actions.register_for_widget(&root);
+
+        register_window(&root, sender.input_sender().clone(), state);
+        root.connect_close_request(|window| {
+            forget_window(window);
+            glib::Propagation::Proceed
+        });
+
+        // Documents named on the command line before any window was ready.
+        let pending_uris = PENDING_URIS.take();
+        if !pending_uris.is_empty() {
+            sender.input(AppMsg::OpenTabs(pending_uris));
+        }
+
+        // Stops once the window has closed.
+        glib::timeout_add_seconds_local(SESSION_SAVE_INTERVAL, move || {
+            match sender.input_sender().send(AppMsg::SaveSession) {
+                Ok(()) => glib::ControlFlow::Continue,
+                Err(_) => glib::ControlFlow::Break,
+            }
+        });
+
         AsyncComponentParts { model, widgets }
     }
 
@@ -975,7 +3471,129 @@ impl SimpleAsyncComponent for AppModel {
 
         match msg {
             AppMsg::NewTab => {
-                self.tabs.guard().push_back(self.starting_uri.clone());
+                let index = self
+                    .tabs
+                    .guard()
+                    .push_back(session::TabState::new(&self.settings.new_tab_uri()));
+                self.select_tab(&index);
+            }
+            AppMsg::CloseTab => {
+                if let Some(position) = self.action_tab_position() {
+                    self.close_tab(position);
+                }
+            }
+            AppMsg::CloseTabAt(position) => {
+                self.close_tab(position);
+            }
+            AppMsg::ReopenClosedTab => {
+                let Some((position, state)) = self.closed_tabs.pop() else {
+                    return;
+                };
+                self.reopen_action.set_enabled(!self.closed_tabs.is_empty());
+
+                let position = position.clamp(self.pinned_tab_count(), self.tabs.len());
+                let index = self.tabs.guard().insert(position, state);
+                self.select_tab(&index);
+            }
+            AppMsg::DuplicateTab => {
+                let Some(position) = self.action_tab_position() else {
+                    return;
+                };
+                let Some(tab) = self.tabs.get(position) else {
+                    return;
+                };
+
+                // The copy isn't pinned, so it goes after the pinned tabs.
+                let state = session::TabState {
+                    pinned: false,
+                    ..tab.state()
+                };
+                let position = (position + 1).max(self.pinned_tab_count());
+
+                let index = self.tabs.guard().insert(position, state);
+                self.select_tab(&index);
+            }
+            AppMsg::SetTabPinned(pinned) => {
+                let Some(position) = self.action_tab_position() else {
+                    return;
+                };
+
+                let tab_view = self.tabs.widget();
+                tab_view.set_page_pinned(&tab_view.nth_page(position as i32), pinned);
+                self.sync_tab_order();
+            }
+            AppMsg::SyncTabOrder => {
+                self.sync_tab_order();
+            }
+            AppMsg::SetMenuTab(position) => {
+                self.menu_tab = position
+                    .and_then(|position| self.tabs.get(position))
+                    .map(|tab| tab.index.clone());
+
+                if let Some(position) = position {
+                    let page = self.tabs.widget().nth_page(position as i32);
+                    self.pin_action.set_state(&page.is_pinned().to_variant());
+                }
+            }
+            AppMsg::MoveTabToNewWindow => {
+                let Some(position) = self.action_tab_position() else {
+                    return;
+                };
+                let Some(tab) = self.tabs.get(position) else {
+                    return;
+                };
+
+                let state = session::TabState {
+                    pinned: false,
+                    ..tab.state()
+                };
+                open_window(session::WindowState {
+                    tabs: vec![state],
+                    selected: 0,
+                });
+                self.remove_tab(position);
+            }
+            AppMsg::RemoveMovedTab(index) => {
+                self.remove_tab(index.current_index());
+            }
+            AppMsg::TabDetached(position) => {
+                let Some(tab) = self.tabs.get(position) else {
+                    return;
+                };
+
+                DRAGGED_TAB.set(Some(DraggedTab {
+                    source: sender.input_sender().clone(),
+                    view: self.tabs.widget().clone(),
+                    index: tab.index.clone(),
+                    state: session::TabState {
+                        pinned: false,
+                        ..tab.state()
+                    },
+                }));
+            }
+            AppMsg::TabAttached(position) => {
+                let Some(dragged) = DRAGGED_TAB.take() else {
+                    return;
+                };
+
+                // Dropped back onto this window's tab bar.
+                let tab_view = self.tabs.widget().clone();
+                if dragged.view == tab_view {
+                    self.sync_tab_order();
+                    return;
+                }
+
+                // The page belongs to the factory of the window it came from, so it goes back
+                // there to be removed, and a copy of its tab opens here.
+                let page = tab_view.nth_page(position as i32);
+                RETURNING_PAGE.set(true);
+                tab_view.transfer_page(&page, &dragged.view, dragged.view.n_pinned_pages());
+                RETURNING_PAGE.set(false);
+                dragged.source.emit(AppMsg::RemoveMovedTab(dragged.index));
+
+                let position = position.clamp(self.pinned_tab_count(), self.tabs.len());
+                let index = self.tabs.guard().insert(position, dragged.state);
+                self.select_tab(&index);
             }
             AppMsg::GoBack => {
                 self.send_to_current_tab(TabMsg::GoBack);
@@ -983,33 +3601,162 @@ impl SimpleAsyncComponent for AppModel {
             AppMsg::GoForward => {
                 self.send_to_current_tab(TabMsg::GoForward);
             }
+            AppMsg::UpdateNavState(index, state) => {
+                if self.is_current_tab(&index) {
+                    self.back_action.set_enabled(state.can_go_back);
+                    self.forward_action.set_enabled(state.can_go_forward);
+                }
+            }
+            AppMsg::UpdateNavBar(index, msg) => {
+                if self.is_current_tab(&index) {
+                    self.nav_bar.emit(msg);
+                }
+            }
+            AppMsg::FocusAddress => {
+                self.nav_bar.emit(NavBarMsg::StartEditingURI);
+            }
+            AppMsg::Zoom(zoom) => {
+                self.send_to_current_tab(TabMsg::Zoom(zoom));
+            }
+            AppMsg::Reload => {
+                self.send_to_current_tab(TabMsg::Reload);
+            }
             AppMsg::UpdateSidebarVisibility(visible) => {
                 self.sidebar_visible = visible;
             }
             AppMsg::SelectTab(index) => {
                 self.current_tab = Some(index);
-
-                let cur_tab = self.get_current_tab().unwrap();
-                sender.input(AppMsg::UpdateOutline(cur_tab.outline.clone()));
+                self.sync_with_current_tab();
             }
             AppMsg::SelectHeading(heading) => {
                 self.send_to_current_tab(TabMsg::SelectedHeading(heading));
             }
-            AppMsg::UpdateOutline(outline) => {
-                self.outline_sidebar
-                    .emit(OutlineSidebarMsg::UpdatedOutline(outline));
+            AppMsg::UpdateOutline(index, outline) => {
+                if self.is_current_tab(&index) {
+                    self.outline_sidebar
+                        .emit(OutlineSidebarMsg::UpdatedOutline(outline));
+                }
+            }
+            AppMsg::UpdateCurrentHeading(index, heading) => {
+                if self.is_current_tab(&index) {
+                    self.outline_sidebar
+                        .emit(OutlineSidebarMsg::UpdatedCurrentHeading(heading));
+                }
+            }
+            AppMsg::OutlineError(index, e) => {
+                if self.is_current_tab(&index) {
+                    // Heading titles aren't markup.
+                    let toast = adw::Toast::builder()
+                        .title(e.to_string())
+                        .use_markup(false)
+                        .build();
+                    self.toasts.add_toast(toast);
+                }
             }
             AppMsg::UpdateURI(uri) => {
                 self.send_to_current_tab(TabMsg::UpdatedURI(uri));
             }
+            AppMsg::ToggleBookmark => {
+                let Some(tab) = self.get_current_tab() else {
+                    return;
+                };
+
+                let uri = tab.uri.clone();
+                let title = tab.current_title.clone().unwrap_or_else(|| uri.clone());
+
+                self.bookmarks.toggle(&uri, &title);
+                self.save_bookmarks();
+            }
+            AppMsg::EditBookmark {
+                target,
+                title,
+                folder,
+                tags,
+            } => {
+                self.bookmarks.edit(&target, &title, &folder, &tags);
+                self.save_bookmarks();
+            }
+            AppMsg::DeleteBookmark(target) => {
+                self.bookmarks.remove(&target);
+                self.save_bookmarks();
+            }
+            AppMsg::UpdatedBookmarks(bookmarks) => {
+                self.bookmarks = bookmarks;
+                self.publish_bookmarks();
+            }
+            AppMsg::SaveSession => {
+                self.save_session();
+            }
+            AppMsg::RecordVisit { uri, title } => match self.history.record(&uri, &title) {
+                Ok(Some(visit)) => {
+                    send_to_other_windows(&self.window, || AppMsg::RecordedVisit(visit.clone()));
+                    self.nav_sidebar.emit(NavSidebarMsg::Visited(visit));
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Could not record visit: {}", e),
+            },
+            AppMsg::RecordedVisit(visit) => {
+                self.history.push(visit.clone());
+                self.nav_sidebar.emit(NavSidebarMsg::Visited(visit));
+            }
+            AppMsg::OpenTabs(uris) => {
+                self.open_tabs(uris);
+            }
+            AppMsg::SuggestURIs(text) => {
+                let suggestions = self
+                    .history
+                    .suggest(&text, MAX_URI_SUGGESTIONS)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+
+                self.nav_bar
+                    .emit(NavBarMsg::UpdatedSuggestions(suggestions));
+            }
+            AppMsg::ShowPreferences => {
+                self.show_preferences(sender);
+            }
+            AppMsg::SetNewTabPage(page) => {
+                self.settings.new_tab = page;
+                self.save_settings();
+            }
+            AppMsg::SetCustomURI(uri) => {
+                self.settings.custom_uri = uri.trim().to_string();
+                self.save_settings();
+            }
+            AppMsg::UpdatedSettings(settings) => {
+                self.settings = settings;
+            }
+            AppMsg::ShowFind => {
+                self.nav_bar.emit(NavBarMsg::ShowFind);
+            }
+            AppMsg::Find(query) => {
+                self.send_to_current_tab(TabMsg::Find(query));
+            }
+            AppMsg::FindNext => {
+                self.send_to_current_tab(TabMsg::FindNext);
+            }
+            AppMsg::FindPrevious => {
+                self.send_to_current_tab(TabMsg::FindPrevious);
+            }
+            AppMsg::FinishFind => {
+                self.send_to_current_tab(TabMsg::FinishFind);
+            }
         }
     }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        self.save_session();
+    }
 }
 
 impl AppModel {
     fn send_to_current_tab(&self, msg: <TabModel as AsyncFactoryComponent>::Input) {
-        let cur_index = self.current_tab.as_ref().map(|i| i.current_index());
-        self.tabs.send(cur_index.expect("No current tab"), msg);
+        // Actions can still arrive while the last tab is closing.
+        let Some(index) = &self.current_tab else {
+            return;
+        };
+        self.tabs.send(index.current_index(), msg);
     }
 
     fn get_current_tab(&self) -> Option<&TabModel> {
@@ -1017,9 +3764,425 @@ impl AppModel {
             .as_ref()
             .and_then(|index| self.tabs.get(index.current_index()))
     }
+
+    fn is_current_tab(&self, index: &DynamicIndex) -> bool {
+        self.current_tab.as_ref() == Some(index)
+    }
+
+    /// Shows the selected tab's page in the navigation bar, actions and outline, which only
+    /// follow the selected tab.
+    fn sync_with_current_tab(&self) {
+        let Some(tab) = self.get_current_tab() else {
+            return;
+        };
+
+        for msg in [
+            NavBarMsg::UpdatedTitle(tab.current_title.clone()),
+            NavBarMsg::UpdatedURI(tab.uri.clone()),
+            NavBarMsg::UpdatedProgressVisible(tab.progress_visible),
+            NavBarMsg::UpdatedLoadingProgress(tab.load_progress),
+            NavBarMsg::UpdatedFindStatus(tab.find_status.clone()),
+        ] {
+            self.nav_bar.emit(msg);
+        }
+
+        self.back_action.set_enabled(tab.nav_state.can_go_back);
+        self.forward_action
+            .set_enabled(tab.nav_state.can_go_forward);
+
+        self.outline_sidebar
+            .emit(OutlineSidebarMsg::UpdatedOutline(tab.outline.clone()));
+        self.outline_sidebar
+            .emit(OutlineSidebarMsg::UpdatedCurrentHeading(
+                tab.current_heading,
+            ));
+    }
+
+    /// Opens each URI in a new tab and selects the last one.
+    fn open_tabs(&mut self, mut uris: Vec<String>) {
+        // A lone start page is reused rather than left next to the documents.
+        let is_start_page = self.tabs.len() == 1
+            && self
+                .get_current_tab()
+                .is_some_and(|tab| tab.uri == self.settings.new_tab_uri());
+
+        if is_start_page && !uris.is_empty() {
+            self.send_to_current_tab(TabMsg::UpdatedURI(uris.remove(0)));
+        }
+
+        if uris.is_empty() {
+            return;
+        }
+
+        let mut guard = self.tabs.guard();
+        let indices: Vec<DynamicIndex> = uris
+            .iter()
+            .map(|uri| guard.push_back(session::TabState::new(uri)))
+            .collect();
+        drop(guard);
+
+        if let Some(last) = indices.last() {
+            self.select_tab(last);
+        }
+    }
+
+    fn select_tab(&mut self, index: &DynamicIndex) {
+        let tab_view = self.tabs.widget();
+        tab_view.set_selected_page(&tab_view.nth_page(index.current_index() as i32));
+        self.current_tab = Some(index.clone());
+    }
+
+    /// The position of the tab that tab actions apply to.
+    fn action_tab_position(&self) -> Option<usize> {
+        self.menu_tab
+            .as_ref()
+            .or(self.current_tab.as_ref())
+            .map(DynamicIndex::current_index)
+            .filter(|&position| position < self.tabs.len())
+    }
+
+    fn pinned_tab_count(&self) -> usize {
+        self.tabs.widget().n_pinned_pages() as usize
+    }
+
+    /// Closes the tab at `position`, keeping it to be reopened.
+    fn close_tab(&mut self, position: usize) {
+        let Some(tab) = self.tabs.get(position) else {
+            return;
+        };
+
+        let state = session::TabState {
+            pinned: false,
+            ..tab.state()
+        };
+        self.closed_tabs.push((position, state));
+        if self.closed_tabs.len() > MAX_CLOSED_TABS {
+            self.closed_tabs.remove(0);
+        }
+        self.reopen_action.set_enabled(true);
+
+        self.remove_tab(position);
+    }
+
+    /// Removes the tab at `position`. Removing the last tab opens a new one in its place.
+    fn remove_tab(&mut self, position: usize) {
+        if position >= self.tabs.len() {
+            return;
+        }
+
+        if self.tabs.len() == 1 {
+            let index = self
+                .tabs
+                .guard()
+                .push_back(session::TabState::new(&self.settings.new_tab_uri()));
+            self.select_tab(&index);
+        }
+
+        self.removing_tab.set(true);
+        self.tabs.guard().remove(position);
+        self.removing_tab.set(false);
+
+        // The view has selected a neighbour by now, if the closed tab was selected.
+        let tab_view = self.tabs.widget();
+        self.current_tab = tab_view
+            .selected_page()
+            .and_then(|page| self.tabs.get(tab_view.page_position(&page) as usize))
+            .map(|tab| tab.index.clone());
+    }
+
+    /// Moves tabs in the factory to match the order of their pages, after the view has
+    /// reordered them.
+    fn sync_tab_order(&mut self) {
+        let tab_view = self.tabs.widget().clone();
+        let mut guard = self.tabs.guard();
+
+        for position in 0..tab_view.n_pages() as usize {
+            let page = tab_view.nth_page(position as i32);
+            let current = (position..guard.len())
+                .find(|&i| guard.get(i).is_some_and(|tab| tab.is_shown_in(&page)));
+
+            if let Some(current) = current
+                && current != position
+            {
+                guard.move_to(current, position);
+            }
+        }
+    }
+
+    fn window_state(&self) -> session::WindowState {
+        let tabs = (0..self.tabs.len())
+            .filter_map(|i| self.tabs.get(i))
+            .map(TabModel::state)
+            .collect();
+        let selected = self
+            .current_tab
+            .as_ref()
+            .map_or(0, |index| index.current_index());
+
+        session::WindowState { tabs, selected }
+    }
+
+    /// Writes the open tabs to disk with those of the other windows, unless nothing changed
+    /// since the last save.
+    fn save_session(&mut self) {
+        let state = self.window_state();
+        if self.saved_state.as_ref() == Some(&state) {
+            return;
+        }
+
+        match save_window_state(&self.window, state.clone()) {
+            Ok(()) => self.saved_state = Some(state),
+            Err(e) => eprintln!("Could not save the session: {}", e),
+        }
+    }
+
+    fn save_bookmarks(&self) {
+        if let Err(e) = self.bookmarks.save() {
+            eprintln!("Could not save bookmarks: {}", e);
+        }
+        self.publish_bookmarks();
+        send_to_other_windows(&self.window, || {
+            AppMsg::UpdatedBookmarks(self.bookmarks.clone())
+        });
+    }
+
+    fn save_settings(&self) {
+        if let Err(e) = self.settings.save() {
+            eprintln!("Could not save preferences: {}", e);
+        }
+        send_to_other_windows(&self.window, || {
+            AppMsg::UpdatedSettings(self.settings.clone())
+        });
+    }
+
+    fn show_preferences(&self, sender: AsyncComponentSender<Self>) {
+        let pages: Vec<&str> = settings::NewTabPage::ALL
+            .iter()
+            .map(|page| page.label())
+            .collect();
+
+        relm4::view! {
+            dialog = adw::PreferencesDialog {
+                add = &adw::PreferencesPage {
+                    add = &adw::PreferencesGroup {
+                        set_title: "Tabs",
+
+                        #[name = "new_tab"]
+                        adw::ComboRow {
+                            set_title: "New tabs open",
+                            set_model: Some(&gtk::StringList::new(&pages)),
+                            set_selected: self.settings.new_tab.position(),
+                        },
+
+                        #[name = "custom_uri"]
+                        adw::EntryRow {
+                            set_title: "Custom address",
+                            set_text: &self.settings.custom_uri,
+                            set_show_apply_button: true,
+                            set_visible: self.settings.new_tab == settings::NewTabPage::Custom,
+                        },
+                    },
+                },
+            }
+        }
+
+        let page_sender = sender.clone();
+        let custom_row = custom_uri.clone();
+        new_tab.connect_selected_notify(move |row| {
+            let page = settings::NewTabPage::from_position(row.selected());
+            custom_row.set_visible(page == settings::NewTabPage::Custom);
+            page_sender.input(AppMsg::SetNewTabPage(page));
+        });
+
+        custom_uri.connect_apply(move |row| {
+            sender.input(AppMsg::SetCustomURI(row.text().to_string()));
+        });
+
+        dialog.present(relm4::main_application().active_window().as_ref());
+    }
+
+    /// Tells the navigation bar and sidebar about changed bookmarks.
+    fn publish_bookmarks(&self) {
+        self.nav_bar
+            .emit(NavBarMsg::UpdatedBookmarks(self.bookmarks.targets()));
+        self.nav_sidebar
+            .emit(NavSidebarMsg::UpdatedBookmarks(self.bookmarks.clone()));
+    }
 }
 
 relm4::new_action_group!(WindowActionGroup, "win");
+relm4::new_stateless_action!(NewTabAction, WindowActionGroup, "new-tab");
+relm4::new_stateless_action!(CloseTabAction, WindowActionGroup, "close-tab");
+relm4::new_stateless_action!(
+    ReopenClosedTabAction,
+    WindowActionGroup,
+    "reopen-closed-tab"
+);
+relm4::new_stateless_action!(DuplicateTabAction, WindowActionGroup, "duplicate-tab");
+relm4::new_stateful_action!(PinTabAction, WindowActionGroup, "pin-tab", (), bool);
+relm4::new_stateless_action!(
+    MoveTabToNewWindowAction,
+    WindowActionGroup,
+    "move-tab-to-new-window"
+);
+relm4::new_stateless_action!(GoBackAction, WindowActionGroup, "back");
+relm4::new_stateless_action!(GoForwardAction, WindowActionGroup, "forward");
+relm4::new_stateless_action!(FocusAddressAction, WindowActionGroup, "focus-address");
+relm4::new_stateful_action!(
+    ToggleOutlineAction,
+    WindowActionGroup,
+    "toggle-outline",
+    (),
+    bool
+);
+relm4::new_stateless_action!(FindAction, WindowActionGroup, "find");
+relm4::new_stateless_action!(FindNextAction, WindowActionGroup, "find-next");
+relm4::new_stateless_action!(FindPreviousAction, WindowActionGroup, "find-previous");
+relm4::new_stateless_action!(ZoomInAction, WindowActionGroup, "zoom-in");
+relm4::new_stateless_action!(ZoomOutAction, WindowActionGroup, "zoom-out");
+relm4::new_stateless_action!(ZoomResetAction, WindowActionGroup, "zoom-reset");
+relm4::new_stateless_action!(ReloadAction, WindowActionGroup, "reload");
+relm4::new_stateless_action!(ToggleBookmarkAction, WindowActionGroup, "bookmark");
+relm4::new_stateless_action!(PreferencesAction, WindowActionGroup, "preferences");
+
+relm4::new_action_group!(AppActionGroup, "app");
+relm4::new_stateless_action!(NewWindowAction, AppActionGroup, "new-window");
+relm4::new_stateless_action!(ShortcutsAction, AppActionGroup, "shortcuts");
+relm4::new_stateless_action!(QuitAction, AppActionGroup, "quit");
+
+/// Routes closing, reordering and the context menu of `tab_view` through the window, so its
+/// pages stay in step with the tabs in the factory.
+fn connect_tab_view(
+    tab_view: &adw::TabView,
+    sender: &AsyncComponentSender<AppModel>,
+    removing_tab: Rc<Cell<bool>>,
+) {
+    // A page only closes once the factory has removed its tab.
+    let close_sender = sender.clone();
+    let close_removing = removing_tab.clone();
+    tab_view.connect_close_page(move |tab_view, page| {
+        let removing = close_removing.get();
+        tab_view.close_page_finish(page, removing);
+
+        if !removing {
+            let position = tab_view.page_position(page) as usize;
+            close_sender.input(AppMsg::CloseTabAt(position));
+        }
+
+        glib::Propagation::Stop
+    });
+
+    let reorder_sender = sender.clone();
+    tab_view.connect_page_reordered(move |_, _, _| {
+        reorder_sender.input(AppMsg::SyncTabOrder);
+    });
+
+    // The menu is reset after its action has run.
+    let menu_sender = sender.clone();
+    tab_view.connect_setup_menu(move |tab_view, page| {
+        let position = page.map(|page| tab_view.page_position(page) as usize);
+        menu_sender.input(AppMsg::SetMenuTab(position));
+    });
+
+    // A page dragged to another window's tab bar has to come back to be removed from this
+    // factory, so the window it lands in opens a copy of its tab instead.
+    let detach_sender = sender.clone();
+    tab_view.connect_page_detached(move |tab_view, _, position| {
+        if tab_view.is_transferring_page() && !removing_tab.get() && !RETURNING_PAGE.get() {
+            detach_sender.input(AppMsg::TabDetached(position as usize));
+        }
+    });
+
+    let attach_sender = sender.clone();
+    tab_view.connect_page_attached(move |_, _, position| {
+        if !RETURNING_PAGE.get() {
+            attach_sender.input(AppMsg::TabAttached(position as usize));
+        }
+    });
+
+    // Windows build their tab view asynchronously, so one can't be handed back here. The page
+    // stays in this view instead and the new window opens a copy of its tab.
+    tab_view.connect_create_window(|tab_view| {
+        if let Some(dragged) = DRAGGED_TAB.take() {
+            open_window(session::WindowState {
+                tabs: vec![dragged.state],
+                selected: 0,
+            });
+            dragged.source.emit(AppMsg::RemoveMovedTab(dragged.index));
+        }
+        Some(tab_view.clone())
+    });
+}
+
+/// A window action that sends `msg()` to the window each time it is activated.
+fn message_action<A>(
+    sender: &AsyncComponentSender<AppModel>,
+    msg: impl Fn() -> AppMsg + 'static,
+) -> RelmAction<A>
+where
+    A: ActionName,
+    A::Target: EmptyType,
+    A::State: EmptyType,
+{
+    let sender = sender.clone();
+    RelmAction::new_stateless(move |_| sender.input(msg()))
+}
+
+/// The keys for each action, which `share/shortcuts.ui` lists too.
+fn set_accelerators(app: &adw::Application) {
+    app.set_accelerators_for_action::<NewTabAction>(&["<Control>t"]);
+    app.set_accelerators_for_action::<NewWindowAction>(&["<Control>n"]);
+    app.set_accelerators_for_action::<CloseTabAction>(&["<Control>w"]);
+    app.set_accelerators_for_action::<ReopenClosedTabAction>(&["<Control><Shift>t"]);
+    app.set_accelerators_for_action::<DuplicateTabAction>(&["<Control><Shift>k"]);
+    app.set_accelerators_for_action::<GoBackAction>(&["<Alt>Left", "Back"]);
+    app.set_accelerators_for_action::<GoForwardAction>(&["<Alt>Right", "Forward"]);
+    app.set_accelerators_for_action::<FocusAddressAction>(&["<Control>l", "F6"]);
+    app.set_accelerators_for_action::<ToggleOutlineAction>(&["F9"]);
+    app.set_accelerators_for_action::<FindAction>(&["<Control>f"]);
+    app.set_accelerators_for_action::<FindNextAction>(&["<Control>g"]);
+    app.set_accelerators_for_action::<FindPreviousAction>(&["<Control><Shift>g"]);
+    app.set_accelerators_for_action::<ZoomInAction>(&["<Control>plus", "<Control>equal"]);
+    app.set_accelerators_for_action::<ZoomOutAction>(&["<Control>minus"]);
+    app.set_accelerators_for_action::<ZoomResetAction>(&["<Control>0"]);
+    app.set_accelerators_for_action::<ReloadAction>(&["F5", "<Control>r"]);
+    app.set_accelerators_for_action::<ToggleBookmarkAction>(&["<Control>d"]);
+    app.set_accelerators_for_action::<PreferencesAction>(&["<Control>comma"]);
+    app.set_accelerators_for_action::<ShortcutsAction>(&["<Control>question"]);
+    app.set_accelerators_for_action::<QuitAction>(&["<Control>q"]);
+}
+
+static SHORTCUTS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/share/shortcuts.ui");
+
+fn show_shortcuts() {
+    let builder = gtk::Builder::from_file(SHORTCUTS_PATH);
+    let window: gtk::ShortcutsWindow = builder
+        .object("shortcuts")
+        .expect("Shortcuts window missing from the UI file");
+
+    window.set_transient_for(relm4::main_application().active_window().as_ref());
+    window.present();
+}
+
+fn register_app_actions() {
+    let new_window: RelmAction<NewWindowAction> =
+        RelmAction::new_stateless(|_| open_window(Default::default()));
+    let shortcuts: RelmAction<ShortcutsAction> = RelmAction::new_stateless(|_| show_shortcuts());
+
+    // Closing the windows lets them save their tabs first.
+    let quit: RelmAction<QuitAction> = RelmAction::new_stateless(|_| {
+        QUITTING.set(true);
+        for window in relm4::main_application().windows() {
+            window.close();
+        }
+    });
+
+    let mut app_actions = RelmActionGroup::<AppActionGroup>::new();
+    app_actions.add_action(new_window);
+    app_actions.add_action(shortcuts);
+    app_actions.add_action(quit);
+    app_actions.register_for_main_application();
+}
 
 static STYLESHEET_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/share/app.css");
 
@@ -1034,13 +4197,219 @@ fn load_css() {
     gtk::style_context_add_provider_for_display(&display, &provider, priority);
 }
 
-fn main() {
-    let app = adw::Application::new(Some("dev.ap5.docviewer"), Default::default());
+/// An open window, and the tabs it last saved.
+struct OpenWindow {
+    window: adw::ApplicationWindow,
+    sender: relm4::Sender<AppMsg>,
+    state: session::WindowState,
+}
+
+/// A tab being dragged out of its window.
+struct DraggedTab {
+    /// The window it came from.
+    source: relm4::Sender<AppMsg>,
+    view: adw::TabView,
+    index: DynamicIndex,
+    state: session::TabState,
+}
+
+thread_local! {
+    /// In the order they were opened, which is the order they are saved and restored in.
+    static WINDOWS: RefCell<Vec<OpenWindow>> = const { RefCell::new(Vec::new()) };
+
+    /// Windows opened after the first, which the application doesn't keep alive itself.
+    static EXTRA_WINDOWS: RefCell<Vec<AsyncController<AppModel>>> =
+        const { RefCell::new(Vec::new()) };
+
+    /// Set while quitting, so every window is restored on the next launch and not only the
+    /// last one closed.
+    static QUITTING: Cell<bool> = const { Cell::new(false) };
+
+    /// Documents to open once the first window is ready.
+    static PENDING_URIS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+
+    static DRAGGED_TAB: RefCell<Option<DraggedTab>> = const { RefCell::new(None) };
+
+    /// Set while a dropped page goes back to the window it came from.
+    static RETURNING_PAGE: Cell<bool> = const { Cell::new(false) };
+
+    static DOCUMENTATION: RefCell<Documentation> = RefCell::new(Documentation::default());
+}
+
+/// Opens a window with the tabs in `state`, or a new tab if there are none.
+fn open_window(state: session::WindowState) {
+    let controller = AppModel::builder().launch(state).detach();
+
+    let window = controller.widget().clone();
+    relm4::main_application().add_window(&window);
+    window.present();
+
+    EXTRA_WINDOWS.with_borrow_mut(|windows| windows.push(controller));
+}
+
+fn register_window(
+    window: &adw::ApplicationWindow,
+    sender: relm4::Sender<AppMsg>,
+    state: session::WindowState,
+) {
+    WINDOWS.with_borrow_mut(|windows| {
+        windows.push(OpenWindow {
+            window: window.clone(),
+            sender,
+            state,
+        })
+    });
+}
+
+/// Lets a closed window go.
+///
+/// Its tabs are dropped from the session, unless it was the last window or the application
+/// is quitting, so that they are restored on the next launch.
+fn forget_window(window: &adw::ApplicationWindow) {
+    let keep = QUITTING.get() || WINDOWS.with_borrow(|windows| windows.len() == 1);
+    if !keep {
+        WINDOWS.with_borrow_mut(|windows| windows.retain(|open| &open.window != window));
+        if let Err(e) = write_session() {
+            eprintln!("Could not save the session: {}", e);
+        }
+    }
+
+    let controller = EXTRA_WINDOWS.with_borrow_mut(|windows| {
+        let position = windows.iter().position(|c| c.widget() == window)?;
+        Some(windows.remove(position))
+    });
+
+    // Dropping the controller saves the window's tabs, which can't happen while it is closing.
+    // Holding the application keeps it running until then.
+    if let Some(controller) = controller {
+        let hold = relm4::main_application().hold();
+        glib::idle_add_local_once(move || {
+            drop(controller);
+            drop(hold);
+        });
+    }
+}
+
+/// Records the tabs of `window` and writes those of every window to disk.
+///
+/// A window that has been let go is no longer saved.
+fn save_window_state(
+    window: &adw::ApplicationWindow,
+    state: session::WindowState,
+) -> Result<(), store::Error> {
+    let found = WINDOWS.with_borrow_mut(|windows| {
+        let open = windows.iter_mut().find(|open| &open.window == window)?;
+        open.state = state;
+        Some(())
+    });
+
+    match found {
+        Some(()) => write_session(),
+        None => Ok(()),
+    }
+}
+
+fn write_session() -> Result<(), store::Error> {
+    let windows = WINDOWS.with_borrow(|windows| {
+        windows
+            .iter()
+            .map(|open| open.state.clone())
+            .filter(|state| !state.tabs.is_empty())
+            .collect()
+    });
+
+    session::Session::new(windows).save()
+}
+
+/// Keeps the other windows up to date with a change made in `window`.
+fn send_to_other_windows(window: &adw::ApplicationWindow, msg: impl Fn() -> AppMsg) {
+    WINDOWS.with_borrow(|windows| {
+        for open in windows.iter().filter(|open| &open.window != window) {
+            open.sender.emit(msg());
+        }
+    });
+}
 
-    app.connect_startup(|_| load_css());
+/// Opens documents named on the command line or by the desktop, creating the window if needed.
+///
+/// Both run in the first instance, so `docviewer ls` adds a tab to its active window.
+fn open_targets(app: &adw::Application, uris: Vec<String>) {
+    app.activate();
 
-    let starting_uri = "file:///tmp/man.html";
+    let active = app.active_window();
+    if let Some(window) = &active {
+        window.present();
+    }
+
+    if uris.is_empty() {
+        return;
+    }
+
+    let sender = WINDOWS.with_borrow(|windows| {
+        windows
+            .iter()
+            .find(|open| Some(open.window.upcast_ref::<gtk::Window>()) == active.as_ref())
+            .or(windows.first())
+            .map(|open| open.sender.clone())
+    });
+
+    match sender {
+        Some(sender) => sender.emit(AppMsg::OpenTabs(uris)),
+        None => PENDING_URIS.with_borrow_mut(|pending| pending.extend(uris)),
+    }
+}
 
-    let relm_app = RelmApp::from_app(app);
-    relm_app.run_async::<AppModel>(starting_uri.to_string());
+fn main() {
+    let app = adw::Application::new(
+        Some("dev.ap5.docviewer"),
+        gio::ApplicationFlags::HANDLES_COMMAND_LINE | gio::ApplicationFlags::HANDLES_OPEN,
+    );
+
+    app.connect_startup(|app| {
+        load_css();
+        set_accelerators(app);
+        register_app_actions();
+
+        let context = webkit6::WebContext::default().expect("Failed to get default web context");
+        schemes::register(&context);
+    });
+
+    app.connect_command_line(|app, command_line| {
+        let args: Vec<String> = command_line
+            .arguments()
+            .iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        let cwd = command_line.cwd().unwrap_or_default();
+
+        open_targets(app, cli::targets(&args, &cwd));
+        glib::ExitCode::SUCCESS.into()
+    });
+
+    app.connect_open(|app, files, _hint| {
+        open_targets(
+            app,
+            files.iter().map(|file| file.uri().to_string()).collect(),
+        );
+    });
+
+    let mut windows: Vec<session::WindowState> = session::Session::load()
+        .map(|session| session.windows)
+        .unwrap_or_default();
+    let first = if windows.is_empty() {
+        Default::default()
+    } else {
+        windows.remove(0)
+    };
+
+    // The other saved windows open after the first, so they keep their order.
+    let restored = RefCell::new(windows);
+    app.connect_activate(move |_| {
+        let windows = restored.take();
+        glib::idle_add_local_once(move || windows.into_iter().for_each(open_window));
+    });
+
+    let relm_app = RelmApp::from_app(app).with_args(std::env::args().collect());
+    relm_app.run_async::<AppModel>(first);
 }
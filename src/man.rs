@@ -0,0 +1,535 @@
+//! Locating man pages on disk and rendering them for the `man:` URI scheme.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use crate::{decompress, html, roff};
+
+/// man-db's default search order for sections, used when a page exists in several
+/// and man_db.conf(5) does not set one.
+pub const SECTION_ORDER: &[&str] = &[
+    "1", "n", "l", "8", "3", "0", "2", "3type", "5", "4", "9", "6", "7",
+];
+
+/// Nesting limit for `.so` links between pages.
+const MAX_SO_DEPTH: usize = 8;
+
+/// A page name with an optional section, as written in `man:` URIs and by man(1) users.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ManPageRef {
+    pub name: String,
+    pub section: Option<String>,
+}
+
+impl ManPageRef {
+    /// Parses `name(section)`, `name.section` or a bare `name`.
+    pub fn parse(text: &str) -> Option<ManPageRef> {
+        let text = text.trim();
+
+        if text.is_empty() {
+            return None;
+        }
+
+        if let Some((name, section)) = text.strip_suffix(')').and_then(|t| t.split_once('(')) {
+            return Some(ManPageRef::new(name, Some(section)));
+        }
+
+        if let Some((name, section)) = text.rsplit_once('.')
+            && !name.is_empty()
+            && looks_like_section(section)
+        {
+            return Some(ManPageRef::new(name, Some(section)));
+        }
+
+        Some(ManPageRef::new(text, None))
+    }
+
+    pub fn new(name: &str, section: Option<&str>) -> ManPageRef {
+        ManPageRef {
+            name: name.trim().to_string(),
+            section: section
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        format!("man:{}", self)
+    }
+}
+
+impl fmt::Display for ManPageRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.section {
+            Some(section) => write!(f, "{}({})", self.name, section),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Whether `text` could be a section name, like `1`, `3p` or `n`.
+pub fn looks_like_section(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    matches!(chars.next(), Some('0'..='9' | 'n' | 'l'))
+        && text.len() <= 8
+        && chars.all(|c| c.is_ascii_alphanumeric())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound(ManPageRef),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotFound(page) => write!(f, "No manual entry for {}", page),
+            Error::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// man-db's configuration file, under the names used by different distributions.
+const CONFIG_FILES: &[&str] = &["/etc/man_db.conf", "/etc/manpath.config"];
+
+/// Hierarchies searched after everything else, as man-db does without a configuration.
+const DEFAULT_ROOTS: &[&str] = &["/usr/local/share/man", "/usr/share/man"];
+
+/// The parts of man_db.conf(5) that decide where pages are found and in what order.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// `MANDATORY_MANPATH` entries, always searched.
+    mandatory: Vec<PathBuf>,
+    /// `MANPATH_MAP` entries, from a `PATH` directory to the hierarchy for its programs.
+    path_map: Vec<(PathBuf, PathBuf)>,
+    /// `SECTION` order, or [`SECTION_ORDER`].
+    pub sections: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            mandatory: Vec::new(),
+            path_map: Vec::new(),
+            sections: SECTION_ORDER.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Config {
+        CONFIG_FILES
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .map(|text| Config::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Config {
+        let mut config = Config::default();
+        let mut sections = Vec::new();
+
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some("MANDATORY_MANPATH"), Some(dir), _) => config.mandatory.push(dir.into()),
+                (Some("MANPATH_MAP"), Some(bin), Some(man)) => {
+                    config.path_map.push((bin.into(), man.into()))
+                }
+                (Some("SECTION" | "SECTIONS"), Some(first), second) => {
+                    sections = [first]
+                        .into_iter()
+                        .chain(second)
+                        .chain(fields)
+                        .map(String::from)
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        if !sections.is_empty() {
+            config.sections = sections;
+        }
+
+        config
+    }
+
+    /// The search path man-db builds when `MANPATH` is unset: hierarchies belonging to
+    /// directories in `PATH`, then the mandatory ones, then the usual defaults.
+    fn default_path(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        for dir in env::var_os("PATH").iter().flat_map(env::split_paths) {
+            if let Some((_, man)) = self.path_map.iter().find(|(bin, _)| *bin == dir) {
+                paths.push(man.clone());
+            } else if let Some(parent) = dir.parent() {
+                paths.push(parent.join("share/man"));
+                paths.push(parent.join("man"));
+            }
+        }
+
+        paths.extend(self.mandatory.iter().cloned());
+        paths.extend(DEFAULT_ROOTS.iter().map(PathBuf::from));
+        paths
+    }
+}
+
+static CONFIG: LazyLock<Config> = LazyLock::new(Config::load);
+
+/// Roots of man page hierarchies, from `MANPATH` or man_db.conf(5).
+///
+/// An empty component in `MANPATH` stands for the default path, as in man-db.
+pub fn man_path() -> Vec<PathBuf> {
+    let mut paths = match env::var_os("MANPATH").filter(|v| !v.is_empty()) {
+        Some(var) => env::split_paths(&var)
+            .flat_map(|component| match component.as_os_str().is_empty() {
+                true => CONFIG.default_path(),
+                false => vec![component],
+            })
+            .collect(),
+        None => CONFIG.default_path(),
+    };
+
+    let mut seen = HashSet::new();
+    paths.retain(|p| p.is_dir() && seen.insert(p.canonicalize().unwrap_or_else(|_| p.clone())));
+    paths
+}
+
+/// Splits a page file name like `printf.3p.gz` into its name and section.
+pub fn split_file_name(file_name: &str) -> Option<(&str, &str)> {
+    let (name, section) = decompress::strip_extension(file_name).rsplit_once('.')?;
+    (!name.is_empty() && looks_like_section(section)).then_some((name, section))
+}
+
+/// Position of a section in the configured order; sub-sections like `3p` sort with their parent.
+pub fn section_rank(section: &str) -> usize {
+    let order = &CONFIG.sections;
+    let exact = order.iter().position(|s| s == section);
+    let parent = || {
+        order
+            .iter()
+            .position(|s| section.starts_with(s.as_str()) && s.len() == 1)
+    };
+
+    exact.or_else(parent).unwrap_or(order.len())
+}
+
+/// A heading for a section, as printed in man(1)'s own page.
+pub fn section_title(section: &str) -> &'static str {
+    match section.get(..1).unwrap_or_default() {
+        "0" => "Header Files",
+        "1" => "User Commands",
+        "2" => "System Calls",
+        "3" if section == "3type" => "Library Types",
+        "3" => "Library Functions",
+        "4" => "Special Files",
+        "5" => "File Formats",
+        "6" => "Games",
+        "7" => "Miscellaneous",
+        "8" => "System Administration",
+        "9" => "Kernel Routines",
+        "n" => "Tcl/Tk Commands",
+        "l" => "Local Documentation",
+        _ => "Other",
+    }
+}
+
+/// Calls `f` with the hierarchy index, path, name and section of every page file in the man path.
+pub fn walk_pages(mut f: impl FnMut(usize, PathBuf, &str, &str)) {
+    for (root_index, root) in man_path().iter().enumerate() {
+        walk_hierarchy(root, |path, name, section| {
+            f(root_index, path, name, section)
+        });
+    }
+}
+
+/// Calls `f` with the path, name and section of every page file in one hierarchy.
+pub fn walk_hierarchy(root: &Path, mut f: impl FnMut(PathBuf, &str, &str)) {
+    let Ok(dirs) = fs::read_dir(root) else {
+        return;
+    };
+
+    for dir in dirs.flatten() {
+        let dir_name = dir.file_name();
+        if !dir_name.to_string_lossy().starts_with("man") {
+            continue;
+        }
+
+        let Ok(files) = fs::read_dir(dir.path()) else {
+            continue;
+        };
+
+        for file in files.flatten() {
+            let file_name = file.file_name();
+            let file_name = file_name.to_string_lossy();
+
+            if let Some((name, section)) = split_file_name(&file_name) {
+                f(file.path(), name, section);
+            }
+        }
+    }
+}
+
+/// Finds every file for a page, best match first: sections in man(1)'s order, then
+/// exact section matches before sub-sections, then earlier hierarchies in the man path.
+pub fn find_pages(page: &ManPageRef) -> Vec<(String, PathBuf)> {
+    let mut candidates: Vec<(usize, bool, usize, String, PathBuf)> = Vec::new();
+
+    walk_pages(|root_index, path, name, section| {
+        if name != page.name {
+            return;
+        }
+
+        let exact = match &page.section {
+            Some(wanted) if section == wanted => true,
+            Some(wanted) if section.starts_with(wanted.as_str()) => false,
+            Some(_) => return,
+            None => true,
+        };
+
+        candidates.push((
+            section_rank(section),
+            !exact,
+            root_index,
+            section.to_string(),
+            path,
+        ));
+    });
+
+    candidates.sort();
+    candidates
+        .into_iter()
+        .map(|(_, _, _, section, path)| (section, path))
+        .collect()
+}
+
+/// All the pages of one section, by name.
+#[derive(Debug, Clone)]
+pub struct CatalogSection {
+    pub section: String,
+    pub pages: Vec<String>,
+}
+
+/// Every page installed in the man path.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// The pages grouped by section, in man(1)'s section order.
+    pub sections: Vec<CatalogSection>,
+}
+
+impl Catalog {
+    pub fn scan() -> Catalog {
+        let mut sections: BTreeMap<(usize, String), BTreeSet<String>> = BTreeMap::new();
+
+        walk_pages(|_, _, name, section| {
+            sections
+                .entry((section_rank(section), section.to_string()))
+                .or_default()
+                .insert(name.to_string());
+        });
+
+        Catalog {
+            sections: sections
+                .into_iter()
+                .map(|((_, section), pages)| CatalogSection {
+                    section,
+                    pages: pages.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Reads a page source, following `.so` links to other pages.
+pub fn load_source(path: &Path) -> Result<String, Error> {
+    let mut path = path.to_path_buf();
+
+    for _ in 0..MAX_SO_DEPTH {
+        let source = read_page_file(&path)?;
+
+        let Some(target) = link_target(&source) else {
+            return Ok(source);
+        };
+
+        // Links are relative to the root of the hierarchy, like `man3/foo.3`.
+        let root = path
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new("/"));
+        path = resolve_link(root, target);
+    }
+
+    Err(Error::Io(
+        path,
+        io::Error::other("too many levels of .so links"),
+    ))
+}
+
+/// The page a page source links to with `.so`, if it is only a link.
+pub fn link_target(source: &str) -> Option<&str> {
+    source
+        .lines()
+        .find(|line| !line.trim().is_empty() && !line.starts_with(".\\\""))
+        .and_then(|line| line.strip_prefix(".so "))
+        .map(str::trim)
+}
+
+fn resolve_link(root: &Path, target: &str) -> PathBuf {
+    let path = root.join(target);
+    decompress::find_variant(&path).unwrap_or(path)
+}
+
+fn read_page_file(path: &Path) -> Result<String, Error> {
+    decompress::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))
+}
+
+/// Renders the page file at `path` into a complete HTML document.
+pub fn render_file(path: &Path) -> Result<String, Error> {
+    let source = load_source(path)?;
+    let document = roff::render(&source);
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let title = match split_file_name(&file_name) {
+        Some((name, section)) => format!("{}({})", name, section),
+        None => file_name.to_string(),
+    };
+
+    Ok(document.to_html(&title))
+}
+
+/// The NAME description of the page file at `path`, if it has one.
+pub fn description(path: &Path) -> Option<String> {
+    load_source(path)
+        .ok()
+        .as_deref()
+        .and_then(roff::name_description)
+}
+
+/// A page listing every section a page is in, for `man:` URIs without a section.
+///
+/// `choices` is in man(1)'s order, so the first one is what man would show.
+pub fn chooser_page(name: &str, choices: &[(String, PathBuf)]) -> String {
+    let mut items = String::new();
+
+    for (i, (section, path)) in choices.iter().enumerate() {
+        let page = ManPageRef::new(name, Some(section));
+        let description = description(path)
+            .map(|text| format!("<p>{}</p>", html::escape(&text)))
+            .unwrap_or_default();
+
+        items.push_str(&format!(
+            concat!(
+                "<li{class}><a href=\"{uri}\"><b>{name}</b>({section})</a>",
+                "<span class=\"section\">{title}</span>",
+                "{description}</li>\n",
+            ),
+            class = if i == 0 { " class=\"default\"" } else { "" },
+            uri = html::escape(&page.uri()),
+            name = html::escape(name),
+            section = html::escape(section),
+            title = section_title(section),
+            description = description,
+        ));
+    }
+
+    let body = format!(
+        concat!(
+            "<main class=\"chooser\">\n",
+            "<h1>{name}</h1>\n",
+            "<p>There are manual pages for <b>{name}</b> in {count} sections.</p>\n",
+            "<ul>\n{items}</ul>\n",
+            "</main>",
+        ),
+        name = html::escape(name),
+        count = choices.len(),
+        items = items,
+    );
+
+    html::document(name, "man", &body)
+}
+
+/// Builds the response for a `man:` URI, given everything after the scheme.
+pub fn page_for_uri(path: &str) -> String {
+    let path = path.trim_start_matches("//");
+    let path = path.split(['#', '?']).next().unwrap_or_default();
+
+    let result = match path.starts_with('/') {
+        true => render_file(Path::new(path)),
+        false => ManPageRef::parse(path)
+            .ok_or_else(|| Error::NotFound(ManPageRef::new(path, None)))
+            .and_then(|page| {
+                let (page, mut found) = match find_pages(&page) {
+                    // `python3.12` is a page name, not page `python3` in section 12.
+                    found if found.is_empty() && page.section.is_some() => {
+                        let whole = ManPageRef::new(path, None);
+                        let found = find_pages(&whole);
+                        (whole, found)
+                    }
+                    found => (page, found),
+                };
+
+                // Several hierarchies can have the same section; keep the first of each.
+                let mut seen = HashSet::new();
+                found.retain(|(section, _)| seen.insert(section.clone()));
+
+                match found.as_slice() {
+                    [] => Err(Error::NotFound(page)),
+                    [(_, file)] => render_file(file),
+                    [(_, file), ..] if page.section.is_some() => render_file(file),
+                    choices => Ok(chooser_page(&page.name, choices)),
+                }
+            }),
+    };
+
+    result.unwrap_or_else(|e| {
+        let title = match e {
+            Error::NotFound(_) => "Manual page not found",
+            Error::Io(..) => "Could not open manual page",
+        };
+        html::error_page(title, &e.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(name: &str, section: Option<&str>) -> Option<ManPageRef> {
+        Some(ManPageRef::new(name, section))
+    }
+
+    #[test]
+    fn page_refs() {
+        assert_eq!(ManPageRef::parse("printf(3)"), page("printf", Some("3")));
+        assert_eq!(
+            ManPageRef::parse(" printf ( 3p ) "),
+            page("printf", Some("3p"))
+        );
+        assert_eq!(ManPageRef::parse("printf.3"), page("printf", Some("3")));
+        assert_eq!(ManPageRef::parse("ls"), page("ls", None));
+        assert_eq!(ManPageRef::parse("ls()"), page("ls", None));
+        assert_eq!(ManPageRef::parse(""), None);
+        assert_eq!(ManPageRef::parse("   "), None);
+    }
+
+    #[test]
+    fn dotted_names() {
+        assert_eq!(
+            ManPageRef::parse("systemd.unit"),
+            page("systemd.unit", None)
+        );
+        assert_eq!(ManPageRef::parse(".1"), page(".1", None));
+        assert_eq!(ManPageRef::parse("tcl.n"), page("tcl", Some("n")));
+    }
+}
@@ -0,0 +1,327 @@
+//! The outline of a page, as a tree of its headings and the places listed under them.
+
+use std::{fmt, iter::Peekable, sync::Arc};
+
+use serde::Deserialize;
+
+/// What an entry of the outline stands for.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Heading,
+    /// A command-line option described in a man page.
+    Option,
+    /// An entry of a Texinfo index.
+    IndexEntry,
+}
+
+/// A place in the page, found by one of the rules in [`SCRIPT`].
+#[derive(Deserialize, Debug, Clone, Hash)]
+pub struct Entry {
+    pub kind: Kind,
+    /// How deep the entry is nested, from 0 for top-level headings.
+    pub level: usize,
+    pub text: String,
+    /// Stays the same for as long as the element is in the page, however the page changes.
+    pub index: usize,
+    /// The id of the element, if it has one to link to.
+    pub anchor: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// A script run in the page failed.
+    Script(glib::Error),
+    /// The page posted an outline that couldn't be read.
+    Json(Arc<serde_json::Error>),
+    /// An entry picked from the outline, with its text, is no longer in the page.
+    StaleOutline(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Script(e) => write!(f, "Could not run a script in the page: {}", e),
+            Error::Json(e) => write!(f, "Could not read the outline of the page: {}", e),
+            Error::StaleOutline(text) => write!(f, "“{}” is no longer in the page", text),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An entry of the outline and the entries under it.
+#[derive(Debug, Clone)]
+pub struct Node<T> {
+    pub item: T,
+    pub children: Vec<Node<T>>,
+}
+
+/// Nests each item under the nearest item before it with a lower level.
+///
+/// A level that skips one, like an `h3` straight after an `h1`, is nested one step deeper
+/// rather than two, and a later `h2` becomes its sibling.
+pub fn build_tree<T>(
+    items: impl IntoIterator<Item = T>,
+    level: impl Fn(&T) -> usize,
+) -> Vec<Node<T>> {
+    take_children(&mut items.into_iter().peekable(), None, &level)
+}
+
+/// Takes items for as long as they are deeper than `parent_level`.
+fn take_children<T, I: Iterator<Item = T>>(
+    items: &mut Peekable<I>,
+    parent_level: Option<usize>,
+    level: &impl Fn(&T) -> usize,
+) -> Vec<Node<T>> {
+    let mut nodes = Vec::new();
+
+    while let Some(item) = items.next_if(|item| parent_level.is_none_or(|p| level(item) > p)) {
+        let children = take_children(items, Some(level(&item)), level);
+        nodes.push(Node { item, children });
+    }
+
+    nodes
+}
+
+/// The positions of the nodes down to the first item that `matches`, starting at the top level.
+pub fn find_path<T>(nodes: &[Node<T>], matches: &impl Fn(&T) -> bool) -> Option<Vec<usize>> {
+    for (position, node) in nodes.iter().enumerate() {
+        if matches(&node.item) {
+            return Some(vec![position]);
+        }
+
+        if let Some(mut path) = find_path(&node.children, matches) {
+            path.insert(0, position);
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+/// Keeps the outline of the page up to date for as long as the page is open.
+///
+/// Posts the [`Entry`]s as JSON to the `outline` message handler whenever they change, and the
+/// index of the entry in view, or -1 before the first one, to the `heading` handler. An entry
+/// keeps its index for as long as its element is in the page, and can be found again with
+/// `globalThis.__outline.headings.get(index)`.
+///
+/// Each element is taken by the first rule that matches it. Besides headings, rules pick up
+/// the options of man pages, which are terms of definition lists starting with `-` or `+`,
+/// the sections of makeinfo's HTML output and the entries of Texinfo indices. Options and
+/// index entries are nested under the heading before them.
+pub const SCRIPT: &str = r#"
+(() => {
+    const outline = globalThis.__outline = {
+        ids: new WeakMap(),
+        nextId: 0,
+        // Elements by index, in the order of the page.
+        headings: new Map(),
+        json: null,
+        current: null,
+    };
+
+    const indexOf = (elem) => {
+        let index = outline.ids.get(elem);
+        if (index === undefined) {
+            index = outline.nextId++;
+            outline.ids.set(elem, index);
+        }
+        return index;
+    };
+
+    // The last heading above the top third of the view.
+    const reportCurrent = () => {
+        const line = window.innerHeight / 3;
+
+        let current = -1;
+        for (const [index, heading] of outline.headings) {
+            // Headings in collapsed sections have no boxes.
+            if (heading.getClientRects().length === 0)
+                continue;
+            if (heading.getBoundingClientRect().top > line)
+                break;
+            current = index;
+        }
+
+        if (current !== outline.current) {
+            outline.current = current;
+            window.webkit.messageHandlers.heading.postMessage(current);
+        }
+    };
+
+    const intersections = new IntersectionObserver(reportCurrent, {
+        rootMargin: "0px 0px -67% 0px",
+    });
+
+    // Levels of makeinfo's sectioning commands, which don't always match the tags it uses.
+    const texinfoLevels = {
+        top: 0,
+        chapter: 1, appendix: 1, unnumbered: 1,
+        section: 2, appendixsec: 2, unnumberedsec: 2,
+        subsection: 3, appendixsubsec: 3, unnumberedsubsec: 3,
+        subsubsection: 4, appendixsubsubsec: 4, unnumberedsubsubsec: 4,
+    };
+    const texinfoLevel = (elem) => {
+        for (const name of elem.classList) {
+            // Newer versions wrap sections in `div.section-level-extent` and the like.
+            const command = name.replace(/-level-extent$/, "");
+            if (command in texinfoLevels)
+                return texinfoLevels[command];
+        }
+        return null;
+    };
+
+    // Rules without a level go one below the heading before them.
+    const rules = [
+        {
+            kind: "heading",
+            selector: "div:is(.top, .chapter, .appendix, .unnumbered, .section, .appendixsec, "
+                + ".unnumberedsec, .subsection, .subsubsection, [class*='-level-extent']) "
+                + "> :is(h1, h2, h3, h4, h5, h6)",
+            level: (elem) => texinfoLevel(elem) ?? texinfoLevel(elem.parentElement)
+                ?? Number(elem.localName[1]) - 1,
+        },
+        {
+            kind: "heading",
+            selector: "h1, h2, h3, h4, h5, h6",
+            level: (elem) => Number(elem.localName[1]) - 1,
+        },
+        {
+            kind: "heading",
+            selector: "[role='heading']",
+            // ARIA's default level.
+            level: (elem) => Math.max((parseInt(elem.getAttribute("aria-level")) || 2) - 1, 0),
+        },
+        {
+            kind: "option",
+            selector: "body.man dl > dt",
+            matches: (elem) => /^[-+]/.test(elem.textContent.trim()),
+        },
+        {
+            kind: "index-entry",
+            selector: "body.info a.index-entry, td.printindex-index-entry > a",
+        },
+    ];
+
+    const entries = () => {
+        const selector = rules.map((rule) => rule.selector).join(", ");
+
+        const found = [];
+        let headingLevel = -1;
+        for (const elem of document.querySelectorAll(selector)) {
+            const rule = rules.find((rule) =>
+                elem.matches(rule.selector) && (rule.matches?.(elem) ?? true));
+            if (!rule)
+                continue;
+
+            let level = rule.level?.(elem);
+            if (level === undefined || level === null)
+                level = headingLevel + 1;
+            else
+                headingLevel = level;
+
+            found.push({ elem, kind: rule.kind, level });
+        }
+        return found;
+    };
+
+    const refresh = () => {
+        const found = entries();
+        outline.headings = new Map(found.map(({ elem }) => [indexOf(elem), elem]));
+
+        intersections.disconnect();
+        for (const { elem } of found)
+            intersections.observe(elem);
+
+        const json = JSON.stringify(found.map(({ elem, kind, level }) => ({
+            kind,
+            level,
+            text: elem.innerText.trim(),
+            index: indexOf(elem),
+            anchor: elem.id || null,
+        })));
+
+        if (json !== outline.json) {
+            outline.json = json;
+            window.webkit.messageHandlers.outline.postMessage(json);
+        }
+
+        reportCurrent();
+    };
+
+    // Pages that build or rewrite their content change many nodes at once.
+    let timeout = null;
+    const scheduleRefresh = () => {
+        clearTimeout(timeout);
+        timeout = setTimeout(refresh, 250);
+    };
+
+    new MutationObserver(scheduleRefresh).observe(document.documentElement, {
+        childList: true,
+        subtree: true,
+        characterData: true,
+        attributes: true,
+        attributeFilter: ["id", "hidden", "open"],
+    });
+
+    window.addEventListener("hashchange", scheduleRefresh);
+    window.addEventListener("popstate", scheduleRefresh);
+
+    refresh();
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the tree as names with their children in brackets, like `a [b c] d`.
+    fn shape(nodes: &[Node<(usize, &str)>]) -> String {
+        nodes
+            .iter()
+            .map(|node| match node.children.as_slice() {
+                [] => node.item.1.to_string(),
+                children => format!("{} [{}]", node.item.1, shape(children)),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn tree<'a>(items: &[(usize, &'a str)]) -> Vec<Node<(usize, &'a str)>> {
+        build_tree(items.iter().copied(), |&(level, _)| level)
+    }
+
+    #[test]
+    fn nested_levels() {
+        let nodes = tree(&[(0, "a"), (1, "b"), (2, "c"), (1, "d"), (0, "e")]);
+        assert_eq!(shape(&nodes), "a [b [c] d] e");
+    }
+
+    #[test]
+    fn skipped_levels() {
+        // An h1, an h3 and an h2.
+        let nodes = tree(&[(0, "a"), (2, "b"), (1, "c"), (2, "d")]);
+        assert_eq!(shape(&nodes), "a [b c [d]]");
+    }
+
+    #[test]
+    fn descending_levels() {
+        // A page that starts deeper than its later headings.
+        let nodes = tree(&[(3, "a"), (2, "b"), (1, "c"), (2, "d"), (0, "e")]);
+        assert_eq!(shape(&nodes), "a b c [d] e");
+    }
+
+    #[test]
+    fn paths() {
+        let nodes = tree(&[(0, "a"), (2, "b"), (1, "c"), (2, "d"), (0, "e")]);
+        let path = |name: &str| find_path(&nodes, &|&(_, n): &(usize, &str)| n == name);
+
+        assert_eq!(path("a"), Some(vec![0]));
+        assert_eq!(path("b"), Some(vec![0, 0]));
+        assert_eq!(path("d"), Some(vec![0, 1, 0]));
+        assert_eq!(path("e"), Some(vec![1]));
+        assert_eq!(path("f"), None);
+    }
+}
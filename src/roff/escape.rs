@@ -0,0 +1,504 @@
+//! Escape sequences, special characters and fonts in roff text.
+
+use crate::html;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Font {
+    #[default]
+    Roman,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+    MonoBold,
+    MonoItalic,
+}
+
+impl Font {
+    /// Looks up a font by its troff name or position. `P` is handled by [`FontSelection`].
+    pub fn from_name(name: &str) -> Option<Font> {
+        match name {
+            "R" | "1" | "" => Some(Font::Roman),
+            "B" | "3" => Some(Font::Bold),
+            "I" | "2" => Some(Font::Italic),
+            "BI" | "4" => Some(Font::BoldItalic),
+            "C" | "CR" | "CW" | "CS" | "V" => Some(Font::Mono),
+            "CB" => Some(Font::MonoBold),
+            "CI" => Some(Font::MonoItalic),
+            _ => None,
+        }
+    }
+
+    fn open_tags(self) -> &'static str {
+        match self {
+            Font::Roman => "",
+            Font::Bold => "<b>",
+            Font::Italic => "<i>",
+            Font::BoldItalic => "<b><i>",
+            Font::Mono => "<code>",
+            Font::MonoBold => "<code><b>",
+            Font::MonoItalic => "<code><i>",
+        }
+    }
+
+    fn close_tags(self) -> &'static str {
+        match self {
+            Font::Roman => "",
+            Font::Bold => "</b>",
+            Font::Italic => "</i>",
+            Font::BoldItalic => "</i></b>",
+            Font::Mono => "</code>",
+            Font::MonoBold => "</b></code>",
+            Font::MonoItalic => "</i></code>",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontSelection {
+    Font(Font),
+    Previous,
+}
+
+impl FontSelection {
+    pub fn from_name(name: &str) -> Option<FontSelection> {
+        match name {
+            "P" => Some(FontSelection::Previous),
+            name => Font::from_name(name).map(FontSelection::Font),
+        }
+    }
+}
+
+/// The current and previous font, which troff tracks for `\fP` and `.ft P`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FontState {
+    pub current: Font,
+    pub previous: Font,
+}
+
+impl FontState {
+    pub fn select(&mut self, selection: FontSelection) {
+        let next = match selection {
+            FontSelection::Font(font) => font,
+            FontSelection::Previous => self.previous,
+        };
+
+        self.previous = self.current;
+        self.current = next;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    Font(FontSelection),
+    /// `\c`: the next line continues this one without a word break.
+    NoSpace,
+}
+
+/// Splits a line of roff text into text runs and font changes.
+///
+/// String and register interpolation has already happened at this point, so
+/// `\*` and `\n` only show up here if they were malformed.
+pub fn parse(text: &str) -> Vec<Inline> {
+    let mut out = Vec::new();
+    let mut buf = String::new();
+    let mut chars = text.chars().peekable();
+
+    macro_rules! flush {
+        () => {
+            if !buf.is_empty() {
+                out.push(Inline::Text(std::mem::take(&mut buf)));
+            }
+        };
+    }
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            buf.push(c);
+            continue;
+        }
+
+        let Some(esc) = chars.next() else { break };
+
+        match esc {
+            'f' => {
+                let name = read_name(&mut chars);
+                if let Some(selection) = FontSelection::from_name(&name) {
+                    flush!();
+                    out.push(Inline::Font(selection));
+                }
+            }
+            '(' => {
+                let name: String = chars.by_ref().take(2).collect();
+                buf.push_str(special_char(&name).unwrap_or(""));
+            }
+            '[' => {
+                let name = read_until(&mut chars, ']');
+                buf.push_str(&named_char(&name));
+            }
+            'C' => {
+                let name = read_delimited(&mut chars);
+                buf.push_str(&named_char(&name));
+            }
+            'N' => {
+                let code = read_delimited(&mut chars);
+                if let Some(c) = code.parse().ok().and_then(char::from_u32) {
+                    buf.push(c);
+                }
+            }
+            'e' | 'E' | '\\' => buf.push('\\'),
+            '-' => buf.push('-'),
+            '.' => buf.push('.'),
+            '\'' => buf.push('\u{b4}'),
+            '`' => buf.push('`'),
+            '_' => buf.push('_'),
+            ' ' | '~' | '0' => buf.push('\u{a0}'),
+            't' => buf.push('\t'),
+            'c' => {
+                flush!();
+                out.push(Inline::NoSpace);
+            }
+            's' => skip_size(&mut chars),
+            'h' | 'v' | 'w' | 'o' | 'b' | 'l' | 'L' | 'D' | 'X' | 'x' | 'H' | 'S' | 'R' | 'Z'
+            | 'A' | 'B' | 'Y' | 'V' | 'U' => {
+                let _ = read_delimited(&mut chars);
+            }
+            'k' | 'n' | '*' | 'g' | 'F' | 'm' | 'M' | '$' => {
+                let _ = read_name(&mut chars);
+            }
+            '"' | '#' => break,
+            // Zero-width or layout-only escapes.
+            '&' | '|' | '^' | ')' | '%' | ':' | '/' | ',' | '{' | '}' | 'd' | 'u' | 'r' | 'a'
+            | 'p' | 'z' => {}
+            c => buf.push(c),
+        }
+    }
+
+    flush!();
+    out
+}
+
+/// Renders text with escapes into HTML, carrying the font state across calls.
+///
+/// Returns the HTML and whether the text ended with `\c`.
+pub fn render(text: &str, font: &mut FontState) -> (String, bool) {
+    let mut out = String::new();
+    let mut open: Option<Font> = None;
+    let mut no_space = false;
+
+    for inline in parse(text) {
+        match inline {
+            Inline::Text(t) => {
+                if open != Some(font.current) {
+                    if let Some(f) = open {
+                        out.push_str(f.close_tags());
+                    }
+                    out.push_str(font.current.open_tags());
+                    open = Some(font.current);
+                }
+
+                out.push_str(&html::escape(&t));
+                no_space = false;
+            }
+            Inline::Font(selection) => font.select(selection),
+            Inline::NoSpace => no_space = true,
+        }
+    }
+
+    if let Some(f) = open {
+        out.push_str(f.close_tags());
+    }
+
+    (out, no_space)
+}
+
+/// Renders text in a fixed font without touching any surrounding font state.
+pub fn render_in(font: Font, text: &str) -> String {
+    let mut state = FontState {
+        current: font,
+        previous: font,
+    };
+    render(text, &mut state).0
+}
+
+/// Text content with all escapes resolved and no markup.
+pub fn plain(text: &str) -> String {
+    parse(text)
+        .into_iter()
+        .filter_map(|inline| match inline {
+            Inline::Text(t) => Some(t),
+            _ => None,
+        })
+        .collect()
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+/// Reads an escape argument in one of the forms `x`, `(xx` or `[name]`.
+pub fn read_name(chars: &mut Chars) -> String {
+    match chars.next() {
+        Some('(') => chars.by_ref().take(2).collect(),
+        Some('[') => read_until(chars, ']'),
+        Some(c) => c.to_string(),
+        None => String::new(),
+    }
+}
+
+fn read_until(chars: &mut Chars, end: char) -> String {
+    chars.by_ref().take_while(|&c| c != end).collect()
+}
+
+fn read_delimited(chars: &mut Chars) -> String {
+    match chars.next() {
+        Some(delim) => read_until(chars, delim),
+        None => String::new(),
+    }
+}
+
+fn skip_size(chars: &mut Chars) {
+    if matches!(chars.peek(), Some('+' | '-')) {
+        chars.next();
+    }
+
+    match chars.peek() {
+        Some('(') => {
+            chars.next();
+            chars.next();
+            chars.next();
+        }
+        Some('[') => {
+            chars.next();
+            let _ = read_until(chars, ']');
+        }
+        Some('\'') => {
+            let _ = read_delimited(chars);
+        }
+        Some('1'..='3') => {
+            chars.next();
+            if chars.peek().is_some_and(char::is_ascii_digit) {
+                chars.next();
+            }
+        }
+        Some('0'..='9') => {
+            chars.next();
+        }
+        _ => {}
+    }
+}
+
+/// Resolves a `\[name]` or `\C'name'` character, including `\[uXXXX]` code points.
+fn named_char(name: &str) -> String {
+    if let Some(s) = special_char(name) {
+        return s.to_string();
+    }
+
+    let code_point = name
+        .strip_prefix('u')
+        .and_then(|hex| u32::from_str_radix(hex.split('_').next()?, 16).ok())
+        .and_then(char::from_u32);
+
+    if let Some(c) = code_point {
+        return c.to_string();
+    }
+
+    match name
+        .strip_prefix("char")
+        .and_then(|n| n.parse().ok())
+        .and_then(char::from_u32)
+    {
+        Some(c) => c.to_string(),
+        None => String::new(),
+    }
+}
+
+/// The subset of groff_char(7) that shows up in real manual pages.
+pub fn special_char(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "em" => "\u{2014}",
+        "en" => "\u{2013}",
+        "hy" | "-" => "-",
+        "mi" | "\\-" => "\u{2212}",
+        "bu" => "\u{2022}",
+        "ci" => "\u{25cb}",
+        "sq" => "\u{25a1}",
+        "co" => "\u{a9}",
+        "rg" => "\u{ae}",
+        "tm" => "\u{2122}",
+        "aq" => "'",
+        "dq" => "\"",
+        "lq" | "``" => "\u{201c}",
+        "rq" | "''" => "\u{201d}",
+        "oq" => "\u{2018}",
+        "cq" => "\u{2019}",
+        "Bq" => "\u{201e}",
+        "bq" => "\u{201a}",
+        "Fo" => "\u{ab}",
+        "Fc" => "\u{bb}",
+        "fo" => "\u{2039}",
+        "fc" => "\u{203a}",
+        "ga" => "`",
+        "aa" => "\u{b4}",
+        "ha" | "a^" => "^",
+        "ti" | "a~" => "~",
+        "rs" => "\\",
+        "sl" => "/",
+        "ul" | "ru" => "_",
+        "ba" | "bv" | "br" => "|",
+        "lh" => "\u{261c}",
+        "rh" => "\u{261e}",
+        "sc" => "\u{a7}",
+        "ps" => "\u{b6}",
+        "dg" => "\u{2020}",
+        "dd" => "\u{2021}",
+        "de" => "\u{b0}",
+        "ct" => "\u{a2}",
+        "Po" => "\u{a3}",
+        "Eu" | "eu" => "\u{20ac}",
+        "Ye" => "\u{a5}",
+        "Do" => "$",
+        "at" => "@",
+        "sh" => "#",
+        "pc" | "md" => "\u{b7}",
+        "->" | "ra" => "\u{2192}",
+        "<-" | "la" => "\u{2190}",
+        "ua" => "\u{2191}",
+        "da" => "\u{2193}",
+        "<>" => "\u{2194}",
+        "rA" | "=>" => "\u{21d2}",
+        "lA" => "\u{21d0}",
+        "<=" => "\u{2264}",
+        ">=" => "\u{2265}",
+        "!=" => "\u{2260}",
+        "==" => "\u{2261}",
+        "=~" | "~=" => "\u{2245}",
+        "~~" | "|=" => "\u{2248}",
+        "**" => "\u{2217}",
+        "mu" => "\u{d7}",
+        "di" => "\u{f7}",
+        "+-" => "\u{b1}",
+        "pl" => "+",
+        "eq" => "=",
+        "lt" => "<",
+        "gt" => ">",
+        "if" => "\u{221e}",
+        "no" => "\u{ac}",
+        "AN" | "an" => "\u{2227}",
+        "OR" | "or" => "\u{2228}",
+        "ca" => "\u{2229}",
+        "cu" => "\u{222a}",
+        "sr" => "\u{221a}",
+        "pd" => "\u{2202}",
+        "is" => "\u{222b}",
+        "mo" => "\u{2208}",
+        "nm" => "\u{2209}",
+        "fa" => "\u{2200}",
+        "te" => "\u{2203}",
+        "es" => "\u{2205}",
+        "12" => "\u{bd}",
+        "14" => "\u{bc}",
+        "34" => "\u{be}",
+        "S1" => "\u{b9}",
+        "S2" => "\u{b2}",
+        "S3" => "\u{b3}",
+        "*a" => "\u{3b1}",
+        "*b" => "\u{3b2}",
+        "*g" => "\u{3b3}",
+        "*d" => "\u{3b4}",
+        "*e" => "\u{3b5}",
+        "*l" => "\u{3bb}",
+        "*m" => "\u{3bc}",
+        "*p" => "\u{3c0}",
+        "*s" => "\u{3c3}",
+        "*D" => "\u{394}",
+        "*S" => "\u{3a3}",
+        "*W" => "\u{3a9}",
+        ":a" => "\u{e4}",
+        ":o" => "\u{f6}",
+        ":u" => "\u{fc}",
+        ":A" => "\u{c4}",
+        ":O" => "\u{d6}",
+        ":U" => "\u{dc}",
+        "ss" => "\u{df}",
+        "'e" => "\u{e9}",
+        "`e" => "\u{e8}",
+        "^e" => "\u{ea}",
+        "'a" => "\u{e1}",
+        "`a" => "\u{e0}",
+        "'o" => "\u{f3}",
+        "'i" => "\u{ed}",
+        "'u" => "\u{fa}",
+        "~n" => "\u{f1}",
+        ",c" => "\u{e7}",
+        "oa" => "\u{e5}",
+        "/o" => "\u{f8}",
+        "ae" => "\u{e6}",
+        "'E" => "\u{c9}",
+        "r!" => "\u{a1}",
+        "r?" => "\u{bf}",
+        "OK" => "\u{2713}",
+        "vs" => "|",
+        "lB" => "[",
+        "rB" => "]",
+        "lC" => "{",
+        "rC" => "}",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fonts() {
+        let mut font = FontState::default();
+
+        assert_eq!(
+            render(r"\fBbold\fR and \fIitalic\fP again", &mut font),
+            ("<b>bold</b> and <i>italic</i> again".to_string(), false)
+        );
+        assert_eq!(font.current, Font::Roman);
+    }
+
+    #[test]
+    fn fonts_carry_over_to_the_next_line() {
+        let mut font = FontState::default();
+
+        assert_eq!(render(r"\f(CWone", &mut font).0, "<code>one</code>");
+        assert_eq!(render("two", &mut font).0, "<code>two</code>");
+        assert_eq!(render(r"\f[]three", &mut font).0, "three");
+    }
+
+    #[test]
+    fn continued_lines() {
+        let mut font = FontState::default();
+
+        assert_eq!(render(r"word\c", &mut font), ("word".to_string(), true));
+        assert_eq!(render("word", &mut font), ("word".to_string(), false));
+    }
+
+    #[test]
+    fn characters() {
+        assert_eq!(
+            plain(r"\(em \[u00E9] \[char65] \N'66' \(co \e \- \&x \(xx"),
+            "\u{2014} é A B \u{a9} \\ - x "
+        );
+    }
+
+    #[test]
+    fn layout_escapes_and_comments_are_dropped() {
+        assert_eq!(plain(r"a\s-2small\s0 \h'1m'b\*(xx\kx"), "asmall b");
+        assert_eq!(plain(r#"text \" a comment"#), "text ");
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let mut font = FontState::default();
+        assert_eq!(render("<a & b>", &mut font).0, "&lt;a &amp; b&gt;");
+    }
+}
@@ -0,0 +1,320 @@
+//! The `-man` macro package (man(7)).
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::{
+    Renderer, escape,
+    escape::{Font, FontState},
+    split_args,
+    writer::Block,
+};
+use crate::html;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Trap {
+    /// The next line is the tag of a `.TP` paragraph.
+    Tag,
+    /// The next line is the text of a heading macro given without arguments.
+    Heading(u8),
+}
+
+#[derive(Debug, Default)]
+pub(super) struct State {
+    pub trap: Option<Trap>,
+    /// Set by `.B`, `.I`, `.SM` and `.SB` without arguments: the font for the next line.
+    pub font_trap: Option<Font>,
+    /// Output position where the text of an open `.UR`/`.MT` link starts, and its target.
+    link: Option<(usize, String)>,
+}
+
+fn alternating_fonts(name: &str) -> Option<(Font, Font)> {
+    Some(match name {
+        "BI" => (Font::Bold, Font::Italic),
+        "BR" => (Font::Bold, Font::Roman),
+        "IB" => (Font::Italic, Font::Bold),
+        "IR" => (Font::Italic, Font::Roman),
+        "RB" => (Font::Roman, Font::Bold),
+        "RI" => (Font::Roman, Font::Italic),
+        _ => return None,
+    })
+}
+
+impl Renderer {
+    pub(super) fn man_request(&mut self, name: &str, rest: &str) {
+        let args = split_args(rest);
+
+        match name {
+            "TH" => {
+                let arg = |i: usize| args.get(i).map(|a| escape::plain(a)).unwrap_or_default();
+
+                self.header.title = arg(0);
+                self.header.section = arg(1);
+                self.header.date = arg(2);
+                self.header.source = arg(3);
+                self.header.manual = arg(4);
+            }
+            "SH" | "SS" => {
+                let level = if name == "SH" { 2 } else { 3 };
+
+                match args.is_empty() {
+                    true => self.man.trap = Some(Trap::Heading(level)),
+                    false => self.heading(level, &escape::render_in(Font::Roman, &args.join(" "))),
+                }
+            }
+            "PP" | "P" | "LP" => self.man_paragraph(),
+            "TP" => {
+                self.close_to_list();
+                self.man.trap = Some(Trap::Tag);
+            }
+            "TQ" => {
+                self.w.end_paragraph();
+                self.w.discard_if_fresh(Block::Description);
+                self.man.trap = Some(Trap::Tag);
+            }
+            "IP" => {
+                self.close_to_list();
+                self.open_list();
+
+                if let Some(tag) = args.first().filter(|tag| !tag.is_empty()) {
+                    let html = escape::render(tag, &mut self.font).0;
+                    self.w.open(Block::Term);
+                    self.w.raw(&html);
+                    self.w.close();
+                }
+
+                self.w.open(Block::Description);
+            }
+            "HP" => {
+                self.man_paragraph();
+                self.w.open_with_class(Block::Paragraph, "hanging");
+            }
+            "RS" => {
+                self.w.end_paragraph();
+                self.w.open_with_class(Block::Indent, "indent");
+            }
+            "RE" => self.w.close_through(Block::Indent),
+            "B" | "I" | "SB" | "SM" => {
+                let font = match name {
+                    "B" | "SB" => Font::Bold,
+                    "I" => Font::Italic,
+                    _ => Font::Roman,
+                };
+
+                match args.is_empty() {
+                    true => self.man.font_trap = Some(font),
+                    false => self.man_text(&escape::render_in(font, &args.join(" "))),
+                }
+            }
+            "EX" => {
+                self.w.end_paragraph();
+                self.nofill = true;
+            }
+            "EE" => {
+                self.w.end_paragraph();
+                self.nofill = false;
+            }
+            "SY" => {
+                self.man_paragraph();
+                self.w.open_with_class(Block::Paragraph, "synopsis");
+                self.w.raw(&escape::render_in(Font::Bold, &args.join(" ")));
+            }
+            "OP" => {
+                let html = match args.as_slice() {
+                    [] => return,
+                    [opt] => format!("[{}]", escape::render_in(Font::Bold, opt)),
+                    [opt, arg, ..] => format!(
+                        "[{} {}]",
+                        escape::render_in(Font::Bold, opt),
+                        escape::render_in(Font::Italic, arg)
+                    ),
+                };
+                self.man_text(&html);
+            }
+            "YS" => self.man_paragraph(),
+            "UR" | "MT" => {
+                let target = args.first().cloned().unwrap_or_default();
+                let href = match name {
+                    "MT" => format!("mailto:{}", target),
+                    _ => target.clone(),
+                };
+
+                self.man_text(&format!("<a href=\"{}\">", html::escape(&href)));
+                self.man.link = Some((self.w.len(), target));
+            }
+            "UE" | "ME" => {
+                if let Some((start, target)) = self.man.link.take() {
+                    if self.w.len() == start {
+                        self.w.raw(&html::escape(&target));
+                    }
+                    self.w.raw("</a>");
+                }
+
+                if !args.is_empty() {
+                    self.w.raw(&escape::render_in(Font::Roman, &args.join(" ")));
+                }
+            }
+            name => {
+                // Anything else is a layout request or an obsolete macro with nothing to render.
+                if let Some((even, odd)) = alternating_fonts(name) {
+                    let html: String = args
+                        .iter()
+                        .enumerate()
+                        .map(|(i, arg)| escape::render_in(if i % 2 == 0 { even } else { odd }, arg))
+                        .collect();
+
+                    self.man_text(&html);
+                }
+            }
+        }
+    }
+
+    fn man_text(&mut self, html: &str) {
+        let join = std::mem::take(&mut self.join);
+        self.emit(html, join);
+    }
+
+    /// Starts a new paragraph at the current relative indent.
+    fn man_paragraph(&mut self) {
+        self.w
+            .close_until(|b| matches!(b, Block::Section | Block::Indent));
+        self.font = FontState::default();
+        self.man.trap = None;
+    }
+
+    fn close_to_list(&mut self) {
+        self.w
+            .close_until(|b| matches!(b, Block::Section | Block::Indent | Block::DefinitionList));
+    }
+
+    fn open_list(&mut self) {
+        if self.w.top() != Some(Block::DefinitionList) {
+            self.w.open(Block::DefinitionList);
+        }
+    }
+
+    pub(super) fn spring_trap(&mut self, trap: Trap, html: &str) {
+        match trap {
+            Trap::Tag => {
+                self.close_to_list();
+                self.open_list();
+
+                self.w.open(Block::Term);
+                self.w.raw(html);
+                self.w.close();
+
+                self.w.open(Block::Description);
+            }
+            Trap::Heading(level) => self.heading(level, html),
+        }
+    }
+}
+
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(<b>|<i>)?([A-Za-z_][\w.:+-]*)(</b>|</i>)?\(([1-9n][a-z]*)\)").unwrap()
+});
+
+/// Turns `name(section)` references in a rendered man page into `man:` links.
+pub(super) fn link_references(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut last = 0;
+    let mut in_link = false;
+
+    for caps in REFERENCE.captures_iter(body) {
+        let whole = caps.get(0).unwrap();
+
+        let start = match (caps.get(1), caps.get(3)) {
+            (Some(_), Some(_)) | (None, None) => whole.start(),
+            (Some(open), None) => open.end(),
+            (None, Some(_)) => continue,
+        };
+
+        let between = &body[last..start];
+        match (between.rfind("<a "), between.rfind("</a>")) {
+            (Some(open), Some(close)) => in_link = open > close,
+            (Some(_), None) => in_link = true,
+            (None, Some(_)) => in_link = false,
+            (None, None) => {}
+        }
+
+        let in_tag = between.rfind('<') > between.rfind('>');
+
+        if in_link || in_tag {
+            continue;
+        }
+
+        out.push_str(between);
+        out.push_str(&format!(
+            "<a href=\"man:{}({})\">{}</a>",
+            &caps[2],
+            &caps[4],
+            &body[start..whole.end()],
+        ));
+        last = whole.end();
+    }
+
+    out.push_str(&body[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::render;
+
+    const PAGE: &str = r#".TH LS 1 2024-01-01 "GNU coreutils" "User Commands"
+.SH NAME
+ls \- list directory contents
+.SH DESCRIPTION
+.SS Options
+.TP
+\fB\-a\fR, \fB\-\-all\fR
+do not ignore entries
+.PP
+See
+.BR dir (1)
+and
+.IR dircolors (1).
+.nf
+line one
+line  two
+.fi
+"#;
+
+    #[test]
+    fn header() {
+        let header = render(PAGE).header;
+
+        assert_eq!(header.title, "LS");
+        assert_eq!(header.section, "1");
+        assert_eq!(header.date, "2024-01-01");
+        assert_eq!(header.source, "GNU coreutils");
+        assert_eq!(header.manual, "User Commands");
+    }
+
+    #[test]
+    fn sections_and_tagged_paragraphs() {
+        let body = render(PAGE).body;
+
+        assert!(body.contains("<h2 id=\"name\">NAME</h2>\n<p>ls - list directory contents</p>"));
+        assert!(body.contains("<h3 id=\"options\">Options</h3>"));
+        assert!(body.contains(concat!(
+            "<dl>\n<dt><b>-a</b>, <b>--all</b></dt>\n",
+            "<dd>\n<p>do not ignore entries</p>\n</dd>\n</dl>",
+        )));
+    }
+
+    #[test]
+    fn references_to_other_pages() {
+        let body = render(PAGE).body;
+
+        assert!(body.contains("<a href=\"man:dir(1)\"><b>dir</b>(1)</a>"));
+        assert!(body.contains("<a href=\"man:dircolors(1)\"><i>dircolors</i>(1)</a>."));
+    }
+
+    #[test]
+    fn preformatted_text() {
+        let body = render(PAGE).body;
+        assert!(body.contains("<pre>line one\nline  two</pre>"));
+    }
+}
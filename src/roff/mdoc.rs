@@ -0,0 +1,1047 @@
+//! The `-mdoc` macro package (mdoc(7)), used by most BSD-derived manual pages.
+
+use super::{
+    Renderer, escape,
+    escape::{Font, FontSelection},
+    split_args,
+    writer::Block,
+};
+use crate::html;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListKind {
+    Tag,
+    Bullet,
+    Dash,
+    Enum,
+    Item,
+    Column,
+}
+
+impl ListKind {
+    fn from_args(args: &[String]) -> ListKind {
+        args.iter()
+            .find_map(|arg| match arg.as_str() {
+                "-tag" | "-hang" | "-ohang" | "-inset" | "-diag" => Some(ListKind::Tag),
+                "-bullet" => Some(ListKind::Bullet),
+                "-dash" | "-hyphen" => Some(ListKind::Dash),
+                "-enum" => Some(ListKind::Enum),
+                "-item" => Some(ListKind::Item),
+                "-column" => Some(ListKind::Column),
+                _ => None,
+            })
+            .unwrap_or(ListKind::Tag)
+    }
+
+    fn block(self) -> Block {
+        match self {
+            ListKind::Tag => Block::DefinitionList,
+            ListKind::Bullet | ListKind::Dash | ListKind::Item => Block::BulletList,
+            ListKind::Enum => Block::EnumList,
+            ListKind::Column => Block::Table,
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            ListKind::Dash => "dash",
+            ListKind::Item => "item",
+            _ => "",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct State {
+    /// Name given to the first `.Nm`, repeated by `.Nm` without arguments.
+    name: Option<String>,
+    lists: Vec<ListKind>,
+    /// Fill mode to restore at the end of each open `.Bd` display.
+    displays: Vec<bool>,
+    /// Number of arguments written so far inside `.Fo` … `.Fc`.
+    function_args: Option<usize>,
+    /// `.Sm off` is in effect.
+    no_spacing: bool,
+    /// Delimiters set by `.Es` for `.En`.
+    enclosure: (String, String),
+    /// Fields of the `.Rs` reference being collected.
+    reference: Option<Vec<(String, String)>>,
+    /// Head of an `.It` line being continued with `.Xo` … `.Xc`.
+    head: Option<String>,
+    /// The previous line was `.Ft`, so a following `.Fn` stays on the same synopsis line.
+    after_type: bool,
+}
+
+impl State {
+    /// Adds text to an `.It` head that is still open; returns whether the text was taken.
+    pub fn collect_head(&mut self, html: &str) -> bool {
+        let Some(head) = &mut self.head else {
+            return false;
+        };
+
+        if !head.is_empty() && !html.is_empty() {
+            head.push(' ');
+        }
+        head.push_str(html);
+
+        true
+    }
+}
+
+fn is_callable(name: &str) -> bool {
+    matches!(
+        name,
+        "Ac" | "Ad"
+            | "An"
+            | "Ao"
+            | "Ap"
+            | "Aq"
+            | "Ar"
+            | "At"
+            | "Bc"
+            | "Bo"
+            | "Bq"
+            | "Brc"
+            | "Bro"
+            | "Brq"
+            | "Bsx"
+            | "Bx"
+            | "Cd"
+            | "Cm"
+            | "Dc"
+            | "Do"
+            | "Dq"
+            | "Dv"
+            | "Dx"
+            | "Ec"
+            | "Em"
+            | "En"
+            | "Eo"
+            | "Er"
+            | "Es"
+            | "Ev"
+            | "Fa"
+            | "Fc"
+            | "Fl"
+            | "Fn"
+            | "Ft"
+            | "Fx"
+            | "Ic"
+            | "In"
+            | "Li"
+            | "Lk"
+            | "Ms"
+            | "Mt"
+            | "Nm"
+            | "No"
+            | "Ns"
+            | "Nx"
+            | "Oc"
+            | "Oo"
+            | "Op"
+            | "Ox"
+            | "Pa"
+            | "Pc"
+            | "Pf"
+            | "Po"
+            | "Pq"
+            | "Qc"
+            | "Ql"
+            | "Qo"
+            | "Qq"
+            | "Sc"
+            | "Sm"
+            | "So"
+            | "Sq"
+            | "St"
+            | "Sx"
+            | "Sy"
+            | "Ta"
+            | "Tn"
+            | "Ux"
+            | "Va"
+            | "Vt"
+            | "Xc"
+            | "Xo"
+            | "Xr"
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Delimiter {
+    Open,
+    Middle,
+    Close,
+}
+
+fn delimiter(token: &str) -> Option<Delimiter> {
+    match token {
+        "(" | "[" => Some(Delimiter::Open),
+        "|" => Some(Delimiter::Middle),
+        "." | "," | ":" | ";" | ")" | "]" | "?" | "!" => Some(Delimiter::Close),
+        _ => None,
+    }
+}
+
+/// Pairs of quotes for the enclosure macros, as (one-line, open, close).
+fn enclosure(name: &str) -> Option<(bool, &'static str, &'static str)> {
+    Some(match name {
+        "Aq" => (true, "\u{27e8}", "\u{27e9}"),
+        "Ao" => (false, "\u{27e8}", ""),
+        "Ac" => (false, "", "\u{27e9}"),
+        "Bq" | "Op" => (true, "[", "]"),
+        "Bo" | "Oo" => (false, "[", ""),
+        "Bc" | "Oc" => (false, "", "]"),
+        "Brq" => (true, "{", "}"),
+        "Bro" => (false, "{", ""),
+        "Brc" => (false, "", "}"),
+        "Dq" => (true, "\u{201c}", "\u{201d}"),
+        "Do" => (false, "\u{201c}", ""),
+        "Dc" => (false, "", "\u{201d}"),
+        "Pq" => (true, "(", ")"),
+        "Po" => (false, "(", ""),
+        "Pc" => (false, "", ")"),
+        "Qq" => (true, "\"", "\""),
+        "Qo" => (false, "\"", ""),
+        "Qc" => (false, "", "\""),
+        "Sq" | "Ql" => (true, "\u{2018}", "\u{2019}"),
+        "So" => (false, "\u{2018}", ""),
+        "Sc" => (false, "", "\u{2019}"),
+        _ => return None,
+    })
+}
+
+/// HTML element and class for the macros that format their arguments one by one.
+fn format(name: &str) -> Option<(&'static str, &'static str)> {
+    Some(match name {
+        "Ad" => ("span", "Ad"),
+        "An" => ("span", "An"),
+        "Ar" => ("var", "Ar"),
+        "Cd" => ("code", "Cd"),
+        "Cm" => ("code", "Cm"),
+        "Dv" => ("code", "Dv"),
+        "Em" => ("em", "Em"),
+        "Er" => ("code", "Er"),
+        "Ev" => ("code", "Ev"),
+        "Fa" => ("var", "Fa"),
+        "Fl" => ("code", "Fl"),
+        "Ft" => ("var", "Ft"),
+        "Ic" => ("code", "Ic"),
+        "Li" => ("code", "Li"),
+        "Ms" => ("span", "Ms"),
+        "Nm" => ("code", "Nm"),
+        "Pa" => ("span", "Pa"),
+        "Sy" => ("strong", "Sy"),
+        "Tn" => ("span", "Tn"),
+        "Va" => ("var", "Va"),
+        "Vt" => ("var", "Vt"),
+        _ => return None,
+    })
+}
+
+fn section_manual(section: &str) -> &'static str {
+    match section.chars().next() {
+        Some('1') => "General Commands Manual",
+        Some('2') => "System Calls Manual",
+        Some('3') => "Library Functions Manual",
+        Some('4') => "Device Drivers Manual",
+        Some('5') => "File Formats Manual",
+        Some('6') => "Games Manual",
+        Some('7') => "Miscellaneous Information Manual",
+        Some('8') => "System Manager's Manual",
+        Some('9') => "Kernel Developer's Manual",
+        _ => "",
+    }
+}
+
+fn standard(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "-ansiC" | "-ansiC-89" => "ANSI X3.159-1989 (\u{201c}ANSI C89\u{201d})",
+        "-isoC" | "-isoC-90" => "ISO/IEC 9899:1990 (\u{201c}ISO C90\u{201d})",
+        "-isoC-99" => "ISO/IEC 9899:1999 (\u{201c}ISO C99\u{201d})",
+        "-isoC-2011" => "ISO/IEC 9899:2011 (\u{201c}ISO C11\u{201d})",
+        "-p1003.1" | "-p1003.1-88" => "IEEE Std 1003.1-1988 (\u{201c}POSIX.1\u{201d})",
+        "-p1003.1-90" => "IEEE Std 1003.1-1990 (\u{201c}POSIX.1\u{201d})",
+        "-p1003.1-96" => "ISO/IEC 9945-1:1996 (\u{201c}POSIX.1\u{201d})",
+        "-p1003.1-2001" => "IEEE Std 1003.1-2001 (\u{201c}POSIX.1\u{201d})",
+        "-p1003.1-2004" => "IEEE Std 1003.1-2004 (\u{201c}POSIX.1\u{201d})",
+        "-p1003.1-2008" => "IEEE Std 1003.1-2008 (\u{201c}POSIX.1\u{201d})",
+        "-p1003.2" | "-p1003.2-92" => "IEEE Std 1003.2-1992 (\u{201c}POSIX.2\u{201d})",
+        "-susv2" => "Version 2 of the Single UNIX Specification (\u{201c}SUSv2\u{201d})",
+        "-susv3" => "Version 3 of the Single UNIX Specification (\u{201c}SUSv3\u{201d})",
+        "-susv4" => "Version 4 of the Single UNIX Specification (\u{201c}SUSv4\u{201d})",
+        "-svid4" => "System V Interface Definition, Fourth Edition (\u{201c}SVID4\u{201d})",
+        "-xpg4" => "X/Open Portability Guide Issue 4 (\u{201c}XPG4\u{201d})",
+        "-xpg4.2" => "X/Open Portability Guide Issue 4, Version 2 (\u{201c}XPG4.2\u{201d})",
+        "-ieee754" => "IEEE Std 754-1985",
+        _ => return None,
+    })
+}
+
+fn operating_system(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "At" => "AT&T UNIX",
+        "Bsx" => "BSD/OS",
+        "Bx" => "BSD",
+        "Dx" => "DragonFly",
+        "Fx" => "FreeBSD",
+        "Nx" => "NetBSD",
+        "Ox" => "OpenBSD",
+        "Ux" => "UNIX",
+        _ => return None,
+    })
+}
+
+/// Words of one output line, with mdoc's rules for spacing around punctuation.
+#[derive(Debug, Default)]
+struct Words {
+    html: String,
+    no_space: bool,
+    spacing: bool,
+}
+
+impl Words {
+    fn new(spacing: bool) -> Self {
+        Self {
+            spacing,
+            ..Default::default()
+        }
+    }
+
+    fn word(&mut self, html: &str) {
+        if !self.html.is_empty() && !self.no_space && self.spacing {
+            self.html.push(' ');
+        }
+        self.html.push_str(html);
+        self.no_space = false;
+    }
+
+    fn open(&mut self, html: &str) {
+        self.word(html);
+        self.no_space = true;
+    }
+
+    fn close(&mut self, html: &str) {
+        self.html.push_str(html);
+        self.no_space = false;
+    }
+
+    fn delimiter(&mut self, kind: Delimiter, html: &str) {
+        match kind {
+            Delimiter::Open => self.open(html),
+            Delimiter::Middle => self.word(html),
+            Delimiter::Close => self.close(html),
+        }
+    }
+}
+
+fn text(token: &str) -> String {
+    escape::render_in(Font::Roman, token)
+}
+
+fn element(tag: &str, class: &str, html: &str) -> String {
+    format!("<{tag} class=\"{class}\">{html}</{tag}>")
+}
+
+impl Renderer {
+    pub(super) fn mdoc_request(&mut self, name: &str, rest: &str) {
+        let args = split_args(rest);
+        let synopsis = self.section == "SYNOPSIS";
+
+        match name {
+            "Dd" => self.header.date = escape::plain(&args.join(" ")),
+            "Dt" => {
+                let arg = |i: usize| args.get(i).map(|a| escape::plain(a)).unwrap_or_default();
+
+                self.header.title = arg(0);
+                self.header.section = arg(1);
+                self.header.manual = section_manual(&self.header.section).to_string();
+            }
+            "Os" => self.header.source = escape::plain(&args.join(" ")),
+            "Sh" | "Ss" => {
+                let html = self.words(&args);
+                self.mdoc.after_type = false;
+
+                if name == "Sh" {
+                    self.mdoc.lists.clear();
+                    self.mdoc.displays.clear();
+                }
+
+                self.heading(if name == "Sh" { 2 } else { 3 }, &html);
+            }
+            "Pp" | "Lp" => self.w.vertical_space(),
+            "D1" | "Dl" => {
+                let html = match name {
+                    "Dl" => format!("<code>{}</code>", self.words(&args)),
+                    _ => self.words(&args),
+                };
+
+                self.w.end_paragraph();
+                self.w.open_with_class(Block::Indent, "indent");
+                self.w.text_line(&html, false, false);
+                self.w.close_through(Block::Indent);
+            }
+            "Bd" => {
+                let literal = args.iter().any(|a| a == "-literal" || a == "-unfilled");
+                let class = if args.iter().any(|a| a == "-offset") {
+                    "indent"
+                } else {
+                    ""
+                };
+
+                self.w.end_paragraph();
+                self.mdoc.displays.push(self.nofill);
+                self.w.open_with_class(Block::Display, class);
+                self.nofill = literal;
+            }
+            "Ed" => {
+                self.w.close_through(Block::Display);
+                self.nofill = self.mdoc.displays.pop().unwrap_or(false);
+            }
+            "Bl" => {
+                let kind = ListKind::from_args(&args);
+
+                self.w.end_paragraph();
+                self.mdoc.lists.push(kind);
+                self.w.open_with_class(kind.block(), kind.class());
+            }
+            "El" => {
+                if let Some(kind) = self.mdoc.lists.pop() {
+                    self.w.close_through(kind.block());
+                }
+            }
+            "It" => self.list_item(rest, &args),
+            "Nd" => {
+                let html = format!("\u{2014} {}", self.words(&args));
+                self.mdoc_text(&html);
+            }
+            "Bf" => {
+                let font = match args.first().map(String::as_str) {
+                    Some("-emphasis" | "Em") => Font::Italic,
+                    Some("-literal" | "Li") => Font::Mono,
+                    Some("-symbolic" | "Sy") => Font::Bold,
+                    _ => Font::Roman,
+                };
+                self.font.select(FontSelection::Font(font));
+            }
+            "Ef" => self.font.reset(),
+            "Rs" => self.mdoc.reference = Some(Vec::new()),
+            "Re" => {
+                if let Some(fields) = self.mdoc.reference.take() {
+                    let html = format_reference(&fields);
+                    self.w.end_paragraph();
+                    self.mdoc_text(&html);
+                    self.w.end_paragraph();
+                }
+            }
+            field if field.starts_with('%') => {
+                if let Some(fields) = &mut self.mdoc.reference {
+                    fields.push((field.to_string(), escape::plain(&args.join(" "))));
+                }
+            }
+            "Ex" | "Rv" => {
+                let names: Vec<&String> = args.iter().filter(|a| *a != "-std").collect();
+                let html = self.standard_sentence(name, &names);
+                self.mdoc_text(&html);
+            }
+            "Lb" => {
+                let library = args.first().map(|a| escape::plain(a)).unwrap_or_default();
+                let html = format!("library \u{201c}{}\u{201d}", html::escape(&library));
+                self.mdoc_text(&html);
+            }
+            "Bt" => self.mdoc_text("is currently in beta test."),
+            "Ud" => self.mdoc_text("currently under development."),
+            "Bk" | "Ek" | "Db" => {}
+            name if is_callable(name) => {
+                if synopsis && self.mdoc.lists.is_empty() {
+                    self.synopsis_line(name);
+                }
+
+                let mut tokens = Vec::with_capacity(args.len() + 1);
+                tokens.push(name.to_string());
+                tokens.extend(args.iter().cloned());
+
+                let html = self.words(&tokens);
+                self.mdoc_text(&html);
+
+                if tokens.iter().any(|t| t == "Xc") {
+                    self.finish_head();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts a new line in the SYNOPSIS section where mandoc would.
+    fn synopsis_line(&mut self, name: &str) {
+        let new_line = match name {
+            "Nm" | "Fd" | "In" | "Ft" => true,
+            "Fn" | "Fo" => !self.mdoc.after_type,
+            _ => false,
+        };
+
+        self.mdoc.after_type = name == "Ft";
+
+        if new_line {
+            self.w.end_paragraph();
+            self.w.open_with_class(Block::Paragraph, "synopsis");
+        }
+    }
+
+    fn mdoc_text(&mut self, html: &str) {
+        let join = std::mem::take(&mut self.join);
+        self.emit(html, join);
+    }
+
+    fn list_item(&mut self, rest: &str, args: &[String]) {
+        let Some(&kind) = self.mdoc.lists.last() else {
+            return;
+        };
+
+        match kind {
+            ListKind::Tag => {
+                self.w.close_until(|b| b == Block::DefinitionList);
+
+                let continued = args.iter().any(|a| a == "Xo");
+                let html = self.words(args);
+
+                self.mdoc.head = Some(html);
+                if !continued {
+                    self.finish_head();
+                }
+            }
+            ListKind::Bullet | ListKind::Dash | ListKind::Enum | ListKind::Item => {
+                self.w.close_until(|b| b == kind.block());
+                self.w.open(Block::Item);
+
+                if !args.is_empty() {
+                    let html = self.words(args);
+                    self.mdoc_text(&html);
+                }
+            }
+            ListKind::Column => {
+                self.w.close_until(|b| b == Block::Table);
+
+                let mut cells = Vec::new();
+                for part in rest.split('\t') {
+                    let tokens = split_args(part);
+                    for cell in tokens.split(|t| t == "Ta") {
+                        cells.push(self.words(cell));
+                    }
+                }
+
+                let row: String = cells.iter().map(|c| format!("<td>{}</td>", c)).collect();
+                self.w.raw(&format!("<tr>{}</tr>\n", row));
+            }
+        }
+    }
+
+    /// Writes the collected head of a tagged list item and opens its body.
+    fn finish_head(&mut self) {
+        let Some(head) = self.mdoc.head.take() else {
+            return;
+        };
+
+        self.w.open(Block::Term);
+        self.w.raw(&head);
+        self.w.close();
+        self.w.open(Block::Description);
+    }
+
+    fn standard_sentence(&self, name: &str, names: &[&String]) -> String {
+        let default = self.mdoc.name.clone().unwrap_or_default();
+        let names: Vec<String> = match names.is_empty() {
+            true => vec![default],
+            false => names.iter().map(|n| escape::plain(n)).collect(),
+        };
+
+        let formatted: Vec<String> = names
+            .iter()
+            .map(|n| match name {
+                "Rv" => element("code", "Fn", &format!("{}()", html::escape(n))),
+                _ => element("code", "Nm", &html::escape(n)),
+            })
+            .collect();
+
+        let list = match formatted.as_slice() {
+            [one] => one.clone(),
+            [init @ .., last] => format!("{} and {}", init.join(", "), last),
+            [] => String::new(),
+        };
+
+        match name {
+            "Rv" => format!(
+                "The {} function{} return{} the value 0 if successful; otherwise the value -1 \
+                 is returned and the global variable <var class=\"Va\">errno</var> is set to \
+                 indicate the error.",
+                list,
+                if names.len() > 1 { "s" } else { "" },
+                if names.len() > 1 { "" } else { "s" },
+            ),
+            _ => format!(
+                "The {} utilit{} exit{} 0 on success, and &gt;0 if an error occurs.",
+                list,
+                if names.len() > 1 { "ies" } else { "y" },
+                if names.len() > 1 { "" } else { "s" },
+            ),
+        }
+    }
+
+    /// Renders a macro line (or the arguments of a block macro) into HTML.
+    fn words(&mut self, tokens: &[String]) -> String {
+        let mut words = Words::new(!self.mdoc.no_spacing);
+        self.run_words(tokens, &mut words);
+        words.html
+    }
+
+    fn run_words(&mut self, tokens: &[String], words: &mut Words) {
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+            i += 1;
+
+            if is_callable(token) {
+                self.call(token, tokens, &mut i, words);
+            } else {
+                plain_word(token, words);
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, tokens: &[String], i: &mut usize, words: &mut Words) {
+        if let Some((one_line, open, close)) = enclosure(name) {
+            if !one_line {
+                match open.is_empty() {
+                    true => words.close(close),
+                    false => words.open(open),
+                }
+                return;
+            }
+
+            // The enclosure runs to the end of the line, minus any trailing punctuation.
+            let mut end = tokens.len();
+            while end > *i && delimiter(&tokens[end - 1]) == Some(Delimiter::Close) {
+                end -= 1;
+            }
+
+            words.open(open);
+            if name == "Ql" {
+                let inner = self.words(&tokens[*i..end]);
+                words.word(&element("code", "Ql", &inner));
+            } else {
+                self.run_words(&tokens[*i..end], words);
+            }
+            words.close(close);
+
+            *i = end;
+            return;
+        }
+
+        match name {
+            "Ns" => words.no_space = true,
+            "Ap" => {
+                words.close("'");
+                words.no_space = true;
+            }
+            "No" => {
+                for token in take_args(tokens, i) {
+                    plain_word(token, words);
+                }
+            }
+            "Pf" => {
+                if let Some(prefix) = tokens.get(*i) {
+                    words.word(&text(prefix));
+                    words.no_space = true;
+                    *i += 1;
+                }
+            }
+            "Sm" => {
+                let arg = tokens.get(*i).map(String::as_str);
+                let off = match arg {
+                    Some("on") => false,
+                    Some("off") => true,
+                    _ => !self.mdoc.no_spacing,
+                };
+                if matches!(arg, Some("on" | "off")) {
+                    *i += 1;
+                }
+                self.mdoc.no_spacing = off;
+                words.spacing = !off;
+            }
+            "Eo" | "Ec" => {
+                if let Some(delim) = tokens.get(*i).filter(|t| !is_callable(t)) {
+                    match name {
+                        "Eo" => words.open(&text(delim)),
+                        _ => words.close(&text(delim)),
+                    }
+                    *i += 1;
+                }
+            }
+            "Es" => {
+                let args = take_args(tokens, i);
+                self.mdoc.enclosure = (
+                    args.first().cloned().unwrap_or_default(),
+                    args.get(1).cloned().unwrap_or_default(),
+                );
+            }
+            "En" => {
+                let (open, close) = self.mdoc.enclosure.clone();
+                words.open(&text(&open));
+                self.run_words(&tokens[*i..], words);
+                words.close(&text(&close));
+                *i = tokens.len();
+            }
+            "Xr" => {
+                let args = take_args(tokens, i);
+                let (page, rest) = split_leading(args, 2);
+
+                match page {
+                    [name, section] => {
+                        let (name, section) = (escape::plain(name), escape::plain(section));
+                        words.word(&format!(
+                            "<a class=\"Xr\" href=\"man:{}({})\">{}({})</a>",
+                            html::escape(&name),
+                            html::escape(&section),
+                            html::escape(&name),
+                            html::escape(&section),
+                        ));
+                    }
+                    [name] => words.word(&text(name)),
+                    _ => {}
+                }
+                trailing(rest, words);
+            }
+            "Sx" => {
+                let args = take_args(tokens, i);
+                let (title, rest) = split_at_delimiters(args);
+                let title: Vec<String> = title.iter().map(|t| escape::plain(t)).collect();
+                let title = title.join(" ");
+
+                words.word(&format!(
+                    "<a class=\"Sx\" href=\"#{}\">{}</a>",
+                    html::escape(&html::slug(&title)),
+                    html::escape(&title),
+                ));
+                trailing(rest, words);
+            }
+            "Lk" | "Mt" => {
+                let args = take_args(tokens, i);
+                let (link, rest) = split_at_delimiters(args);
+
+                if let Some((target, label)) = link.split_first() {
+                    let target = escape::plain(target);
+                    let href = match name {
+                        "Mt" => format!("mailto:{}", target),
+                        _ => target.clone(),
+                    };
+                    let label = match label.is_empty() {
+                        true => html::escape(&target),
+                        false => label.iter().map(|l| text(l)).collect::<Vec<_>>().join(" "),
+                    };
+
+                    words.word(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        html::escape(&href),
+                        label
+                    ));
+                }
+                trailing(rest, words);
+            }
+            "Fn" => {
+                let args = take_args(tokens, i);
+                let (call, rest) = split_at_delimiters(args);
+
+                if let Some((function, params)) = call.split_first() {
+                    let params: Vec<String> = params
+                        .iter()
+                        .map(|p| element("var", "Fa", &text(p)))
+                        .collect();
+
+                    words.word(&format!(
+                        "{}({})",
+                        element("code", "Fn", &text(function)),
+                        params.join(", ")
+                    ));
+
+                    if self.section == "SYNOPSIS" {
+                        words.close(";");
+                    }
+                }
+                trailing(rest, words);
+            }
+            "Fo" => {
+                if let Some(function) = tokens.get(*i) {
+                    words.word(&format!("{}(", element("code", "Fn", &text(function))));
+                    words.no_space = true;
+                    self.mdoc.function_args = Some(0);
+                    *i += 1;
+                }
+            }
+            "Fa" if self.mdoc.function_args.is_some() => {
+                for arg in take_args(tokens, i) {
+                    let count = self.mdoc.function_args.get_or_insert(0);
+                    if *count > 0 {
+                        words.close(",");
+                    }
+                    *count += 1;
+                    words.word(&element("var", "Fa", &text(arg)));
+                }
+            }
+            "Fc" => {
+                words.close(")");
+                if self.section == "SYNOPSIS" {
+                    words.close(";");
+                }
+                self.mdoc.function_args = None;
+            }
+            "In" => {
+                let args = take_args(tokens, i);
+                let (header, rest) = split_leading(args, 1);
+
+                if let Some(header) = header.first() {
+                    words.word(&format!(
+                        "#include &lt;{}&gt;",
+                        element("code", "In", &text(header))
+                    ));
+                }
+                trailing(rest, words);
+            }
+            "St" => {
+                let args = take_args(tokens, i);
+                let (standard_name, rest) = split_leading(args, 1);
+
+                if let Some(arg) = standard_name.first() {
+                    match standard(arg) {
+                        Some(s) => words.word(&html::escape(s)),
+                        None => words.word(&text(arg)),
+                    }
+                }
+                trailing(rest, words);
+            }
+            "At" | "Bsx" | "Bx" | "Dx" | "Fx" | "Nx" | "Ox" | "Ux" => {
+                let args = take_args(tokens, i);
+                let (version, rest) = split_at_delimiters(args);
+
+                let os = operating_system(name).unwrap_or_default();
+                let version: Vec<String> = version.iter().map(|v| text(v)).collect();
+
+                match (name, version.is_empty()) {
+                    (_, true) => words.word(&html::escape(os)),
+                    ("At", false) => {
+                        words.word(&format!("{} {}", version.join(" "), "AT&amp;T UNIX"))
+                    }
+                    (_, false) => {
+                        words.word(&format!("{} {}", html::escape(os), version.join(" ")))
+                    }
+                }
+                trailing(rest, words);
+            }
+            "Xo" | "Xc" | "Ta" => {}
+            name => {
+                let Some((tag, class)) = format(name) else {
+                    return;
+                };
+
+                let args = take_args(tokens, i);
+                let is_word =
+                    |t: &&String| delimiter(t).is_none() && !(name == "An" && t.starts_with('-'));
+
+                if !args.iter().any(|t| is_word(&t)) {
+                    let default = match name {
+                        "Fl" => Some("-".to_string()),
+                        "Ar" => Some("file ...".to_string()),
+                        "Nm" => self.mdoc.name.clone(),
+                        "Pa" => Some("~".to_string()),
+                        _ => None,
+                    };
+
+                    if let Some(default) = default {
+                        words.word(&element(tag, class, &html::escape(&default)));
+                    }
+                }
+
+                for token in args {
+                    if let Some(kind) = delimiter(token) {
+                        words.delimiter(kind, &text(token));
+                        continue;
+                    }
+
+                    if !is_word(&token) {
+                        continue;
+                    }
+
+                    let html = match name {
+                        "Fl" => text(&format!("-{}", token)),
+                        _ => text(token),
+                    };
+
+                    if name == "Nm" && self.mdoc.name.is_none() {
+                        self.mdoc.name = Some(escape::plain(token));
+                    }
+
+                    words.word(&element(tag, class, &html));
+                }
+            }
+        }
+    }
+}
+
+/// Takes the arguments of a macro: everything up to the next callable macro.
+fn take_args<'a>(tokens: &'a [String], i: &mut usize) -> &'a [String] {
+    let start = *i;
+
+    while *i < tokens.len() && !is_callable(&tokens[*i]) {
+        *i += 1;
+    }
+
+    &tokens[start..*i]
+}
+
+fn plain_word(token: &str, words: &mut Words) {
+    match delimiter(token) {
+        Some(kind) => words.delimiter(kind, &text(token)),
+        None => words.word(&text(token)),
+    }
+}
+
+fn trailing(tokens: &[String], words: &mut Words) {
+    for token in tokens {
+        plain_word(token, words);
+    }
+}
+
+/// Splits off up to `count` leading arguments, stopping at the first delimiter.
+fn split_leading(args: &[String], count: usize) -> (&[String], &[String]) {
+    let end = args
+        .iter()
+        .take(count)
+        .take_while(|a| delimiter(a).is_none())
+        .count();
+
+    args.split_at(end)
+}
+
+/// Splits arguments before the trailing run of delimiters.
+fn split_at_delimiters(args: &[String]) -> (&[String], &[String]) {
+    let end = args
+        .iter()
+        .position(|a| delimiter(a).is_some())
+        .unwrap_or(args.len());
+
+    args.split_at(end)
+}
+
+fn format_reference(fields: &[(String, String)]) -> String {
+    let field = |name: &str| -> Vec<&str> {
+        fields
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    };
+
+    let mut parts = Vec::new();
+
+    let authors: Vec<String> = field("%A").iter().map(|a| html::escape(a)).collect();
+    match authors.as_slice() {
+        [] => {}
+        [one] => parts.push(one.clone()),
+        [init @ .., last] => parts.push(format!("{}, and {}", init.join(", "), last)),
+    }
+
+    for title in field("%T") {
+        parts.push(format!("\u{201c}{}\u{201d}", html::escape(title)));
+    }
+
+    for name in ["%B", "%J"] {
+        for value in field(name) {
+            parts.push(format!("<i>{}</i>", html::escape(value)));
+        }
+    }
+
+    for name in ["%R", "%V", "%N", "%I", "%C", "%P", "%Q", "%D", "%O"] {
+        for value in field(name) {
+            parts.push(html::escape(value));
+        }
+    }
+
+    for url in field("%U") {
+        let url = html::escape(url);
+        parts.push(format!("<a href=\"{}\">{}</a>", url, url));
+    }
+
+    format!("{}.", parts.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::render;
+
+    const PAGE: &str = r#".Dd January 1, 2024
+.Dt TRUE 1
+.Os
+.Sh NAME
+.Nm true
+.Nd do nothing, successfully
+.Sh SYNOPSIS
+.Nm
+.Op Fl v
+.Ar file
+.Sh DESCRIPTION
+.Bl -tag -width Ds
+.It Fl v
+Be verbose.
+.El
+.Bl -bullet
+.It
+One.
+.El
+.Sh SEE ALSO
+.Xr false 1 ,
+.Xr sh 1
+"#;
+
+    #[test]
+    fn header() {
+        let header = render(PAGE).header;
+
+        assert_eq!(header.title, "TRUE");
+        assert_eq!(header.section, "1");
+        assert_eq!(header.date, "January 1, 2024");
+        assert_eq!(header.manual, "General Commands Manual");
+    }
+
+    #[test]
+    fn name_and_synopsis() {
+        let body = render(PAGE).body;
+
+        assert!(body.contains("<p><code class=\"Nm\">true</code>\n— do nothing, successfully</p>"));
+        // `.Nm` without arguments repeats the name from the NAME section.
+        assert!(body.contains(concat!(
+            "<p class=\"synopsis\"><code class=\"Nm\">true</code>\n",
+            "[<code class=\"Fl\">-v</code>]\n<var class=\"Ar\">file</var></p>",
+        )));
+    }
+
+    #[test]
+    fn lists() {
+        let body = render(PAGE).body;
+
+        assert!(body.contains(concat!(
+            "<dl>\n<dt><code class=\"Fl\">-v</code></dt>\n",
+            "<dd>\n<p>Be verbose.</p>\n</dd>\n</dl>",
+        )));
+        assert!(body.contains("<ul>\n<li>\n<p>One.</p>\n</li>\n</ul>"));
+    }
+
+    #[test]
+    fn cross_references() {
+        let body = render(PAGE).body;
+
+        assert!(body.contains(concat!(
+            "<a class=\"Xr\" href=\"man:false(1)\">false(1)</a>,\n",
+            "<a class=\"Xr\" href=\"man:sh(1)\">sh(1)</a>",
+        )));
+    }
+}
@@ -0,0 +1,869 @@
+//! A troff interpreter that understands enough of the language and of the
+//! `-man` and `-mdoc` macro packages to turn manual pages into HTML.
+//!
+//! This is not a typesetter: requests that only affect layout on a terminal
+//! or on paper are ignored, and the output relies on the stylesheet for
+//! indentation and spacing.
+
+mod escape;
+mod man;
+mod mdoc;
+mod tbl;
+mod writer;
+
+use std::collections::HashMap;
+
+use crate::html::{self, SlugSet};
+use escape::{Font, FontSelection, FontState};
+use writer::{Block, Writer};
+
+/// Nesting limit for macro calls and string interpolation.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct Header {
+    pub title: String,
+    pub section: String,
+    pub date: String,
+    pub source: String,
+    pub manual: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub header: Header,
+    pub body: String,
+}
+
+impl Document {
+    /// A complete HTML page, with `title` used for the window title.
+    pub fn to_html(&self, title: &str) -> String {
+        let Header {
+            title: name,
+            section,
+            date,
+            source,
+            manual,
+        } = &self.header;
+
+        let page_id = match section.as_str() {
+            "" => html::escape(name),
+            section => format!("{}({})", html::escape(name), html::escape(section)),
+        };
+
+        let body = format!(
+            concat!(
+                "<header class=\"man-header\"><span>{id}</span><span>{manual}</span><span>{id}</span></header>\n",
+                "{body}\n",
+                "<footer class=\"man-footer\"><span>{source}</span><span>{date}</span><span>{id}</span></footer>",
+            ),
+            id = page_id,
+            manual = html::escape(manual),
+            body = self.body,
+            source = html::escape(source),
+            date = html::escape(date),
+        );
+
+        html::document(title, "man", &body)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Package {
+    Man,
+    Mdoc,
+}
+
+/// Renders troff source using the `-man` or `-mdoc` package, whichever the page is written in.
+pub fn render(source: &str) -> Document {
+    let mut renderer = Renderer::new(source);
+    renderer.run();
+    renderer.finish()
+}
+
+/// The one-line description from a page's NAME section, as whatis(1) prints it.
+///
+/// This reads the source directly instead of rendering it, so it is cheap enough
+/// to run over many pages.
+pub fn name_description(source: &str) -> Option<String> {
+    let mut in_name = false;
+    let mut words = Vec::new();
+
+    for line in logical_lines(source) {
+        if !line.starts_with(['.', '\'']) {
+            if in_name {
+                words.push(escape::plain(&line));
+            }
+            continue;
+        }
+
+        let (name, rest) = split_request(&line);
+
+        match name {
+            "Nd" => return Some(escape::plain(&split_args(rest).join(" "))),
+            "SH" | "Sh" if in_name => break,
+            "SH" | "Sh" => {
+                let title = split_args(rest).concat();
+                in_name = title.eq_ignore_ascii_case("NAME");
+            }
+            "B" | "I" | "BR" | "RB" | "BI" | "IB" | "IR" | "RI" | "SM" | "SB" if in_name => {
+                words.push(escape::plain(&split_args(rest).concat()));
+            }
+            _ => {}
+        }
+    }
+
+    let text = words.join(" ");
+    let (_, description) = text
+        .split_once(" - ")
+        .or_else(|| text.split_once(" \u{2014} "))
+        .or_else(|| text.split_once(" \u{2013} "))?;
+
+    let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!description.is_empty()).then_some(description)
+}
+
+/// A stack of line sources: the page itself and any macros being expanded.
+#[derive(Debug, Default)]
+struct Input {
+    frames: Vec<(Vec<String>, usize)>,
+}
+
+impl Input {
+    fn push(&mut self, lines: Vec<String>) {
+        self.frames.push((lines, 0));
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        while let Some((lines, pos)) = self.frames.last_mut() {
+            if let Some(line) = lines.get(*pos) {
+                *pos += 1;
+                return Some(line.clone());
+            }
+            self.frames.pop();
+        }
+        None
+    }
+
+    fn depth(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+/// Joins physical lines that end in an escaped newline.
+fn logical_lines(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending = String::new();
+
+    for line in source.lines() {
+        let trailing = line.len() - line.trim_end_matches('\\').len();
+
+        if trailing % 2 == 1 {
+            pending.push_str(&line[..line.len() - 1]);
+        } else {
+            pending.push_str(line);
+            lines.push(std::mem::take(&mut pending));
+        }
+    }
+
+    if !pending.is_empty() {
+        lines.push(pending);
+    }
+
+    lines
+}
+
+/// Splits request or macro arguments, honouring double quotes and escaped spaces.
+fn split_args(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        while chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+
+        let Some(&first) = chars.peek() else { break };
+        let mut arg = String::new();
+
+        if first == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        arg.push('"');
+                    }
+                    '"' => break,
+                    '\\' => {
+                        arg.push('\\');
+                        arg.extend(chars.next());
+                    }
+                    c => arg.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ' ' || c == '\t' {
+                    break;
+                }
+                chars.next();
+                arg.push(c);
+                if c == '\\' {
+                    arg.extend(chars.next());
+                }
+            }
+        }
+
+        args.push(arg);
+    }
+
+    args
+}
+
+/// Splits a control line into the request name and the rest of the line.
+fn split_request(line: &str) -> (&str, &str) {
+    let line = line[1..].trim_start_matches([' ', '\t']);
+    let end = line.find([' ', '\t']).unwrap_or(line.len());
+
+    (&line[..end], line[end..].trim_start_matches([' ', '\t']))
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+struct Renderer {
+    package: Package,
+    input: Input,
+    w: Writer,
+    header: Header,
+    slugs: SlugSet,
+
+    font: FontState,
+    nofill: bool,
+    /// The previous text line ended in `\c`.
+    join: bool,
+
+    strings: HashMap<String, String>,
+    macros: HashMap<String, Vec<String>>,
+    registers: HashMap<String, i64>,
+    /// Results of `.ie` requests waiting for their `.el`.
+    else_stack: Vec<bool>,
+
+    /// Upper-case title of the current `.SH`/`.Sh` section.
+    section: String,
+
+    man: man::State,
+    mdoc: mdoc::State,
+}
+
+impl Renderer {
+    fn new(source: &str) -> Self {
+        let lines = logical_lines(source);
+
+        let is_mdoc = lines
+            .iter()
+            .filter(|line| line.starts_with('.') || line.starts_with('\''))
+            .map(|line| split_request(line).0)
+            .find(|name| matches!(*name, "TH" | "Dd"))
+            == Some("Dd");
+
+        let mut input = Input::default();
+        input.push(lines);
+
+        let strings = [
+            ("lq", "\u{201c}"),
+            ("rq", "\u{201d}"),
+            ("R", "\u{ae}"),
+            ("Tm", "\u{2122}"),
+            ("S", ""),
+            ("HF", "B"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        Self {
+            package: if is_mdoc { Package::Mdoc } else { Package::Man },
+            input,
+            w: Writer::default(),
+            header: Header::default(),
+            slugs: SlugSet::default(),
+            font: FontState::default(),
+            nofill: false,
+            join: false,
+            strings,
+            macros: HashMap::new(),
+            registers: HashMap::new(),
+            else_stack: Vec::new(),
+            section: String::new(),
+            man: Default::default(),
+            mdoc: Default::default(),
+        }
+    }
+
+    fn run(&mut self) {
+        while let Some(line) = self.input.next_line() {
+            self.line(&line);
+        }
+    }
+
+    fn finish(self) -> Document {
+        let mut body = self.w.finish();
+
+        if self.package == Package::Man {
+            body = man::link_references(&body);
+        }
+
+        Document {
+            header: self.header,
+            body,
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        let line = self.interpolate(line, 0);
+
+        if line.starts_with('.') || line.starts_with('\'') {
+            let (name, rest) = split_request(&line);
+            if !name.is_empty() {
+                self.request(name, rest);
+            }
+        } else {
+            self.text(&line);
+        }
+    }
+
+    /// Copy-mode processing: resolves strings and registers and removes comments.
+    fn interpolate(&self, line: &str, depth: usize) -> String {
+        if !line.contains('\\') {
+            return line.to_string();
+        }
+
+        let mut out = String::with_capacity(line.len());
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('"') => break,
+                Some('#') => break,
+                Some('*') => {
+                    let name = escape::read_name(&mut chars);
+                    let name = name.split(' ').next().unwrap_or_default();
+                    if let Some(value) = self.strings.get(name)
+                        && depth < MAX_DEPTH
+                    {
+                        out.push_str(&self.interpolate(value, depth + 1));
+                    }
+                }
+                Some('n') => {
+                    let _ = chars.next_if(|&c| c == '+' || c == '-');
+                    let name = escape::read_name(&mut chars);
+                    out.push_str(&self.register(&name).to_string());
+                }
+                Some(c) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                None => {}
+            }
+        }
+
+        out
+    }
+
+    fn register(&self, name: &str) -> i64 {
+        match name {
+            // We are neither groff nor troff, and we format like nroff does.
+            ".g" | ".T" => 0,
+            ".H" | ".V" => 1,
+            _ => self.registers.get(name).copied().unwrap_or(0),
+        }
+    }
+
+    /// Renders a line of text in the current font.
+    fn inline(&mut self, text: &str) -> String {
+        let (html, no_space) = escape::render(text, &mut self.font);
+        self.join = no_space;
+        html
+    }
+
+    fn text(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            if line.is_empty() || !self.nofill {
+                self.w.vertical_space();
+            } else {
+                self.w.raw("\n");
+            }
+            return;
+        }
+
+        let join = self.join;
+
+        let html = match self.man.font_trap.take() {
+            Some(font) => {
+                self.join = false;
+                escape::render_in(font, line)
+            }
+            None => self.inline(line),
+        };
+
+        if html.is_empty() && !self.join {
+            // Only escapes without output, like the end of a conditional block.
+            return;
+        }
+
+        if line.starts_with([' ', '\t']) && !self.nofill {
+            self.w.line_break();
+        }
+
+        self.emit(&html, join);
+    }
+
+    /// Writes a line of rendered text, springing any pending input trap.
+    fn emit(&mut self, html: &str, join: bool) {
+        if self.mdoc.collect_head(html) {
+            return;
+        }
+
+        match self.man.trap.take() {
+            Some(trap) => self.spring_trap(trap, html),
+            None => self.w.text_line(html, self.nofill, join),
+        }
+    }
+
+    fn heading(&mut self, level: u8, html: &str) {
+        let text = strip_tags(html);
+
+        if level <= 2 {
+            self.w.close_all();
+            self.w.open(Block::Section);
+            self.section = text.trim().to_uppercase();
+        } else {
+            self.w.close_until(|b| b == Block::Section);
+        }
+
+        self.font.reset();
+        self.nofill = false;
+
+        let id = self.slugs.unique(&text);
+        self.w.raw(&format!(
+            "<h{level} id=\"{}\">{}</h{level}>\n",
+            html::escape(&id),
+            html
+        ));
+    }
+
+    fn request(&mut self, name: &str, rest: &str) {
+        if let Some(body) = self.macros.get(name) {
+            if self.input.depth() < MAX_DEPTH {
+                let args = split_args(rest);
+                let lines = body
+                    .iter()
+                    .map(|line| substitute_args(line, &args))
+                    .collect();
+                self.registers.insert(".$".to_string(), args.len() as i64);
+                self.input.push(lines);
+            }
+            return;
+        }
+
+        match name {
+            "de" | "de1" | "am" | "am1" => self.define_macro(name.starts_with("am"), rest),
+            "ig" => {
+                let end = split_args(rest)
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| ".".into());
+                self.skip_until(&format!(".{}", end));
+            }
+            "ds" | "ds1" | "as" | "as1" => {
+                let (key, value) = rest.split_once([' ', '\t']).unwrap_or((rest, ""));
+                let value = value.trim_start();
+                let value = value.strip_prefix('"').unwrap_or(value).to_string();
+
+                if name.starts_with("as") {
+                    self.strings
+                        .entry(key.to_string())
+                        .or_default()
+                        .push_str(&value);
+                } else {
+                    self.strings.insert(key.to_string(), value);
+                }
+            }
+            "rm" => {
+                let key = rest.trim();
+                self.strings.remove(key);
+                self.macros.remove(key);
+            }
+            "nr" => {
+                let args = split_args(rest);
+                if let [key, value, ..] = args.as_slice() {
+                    let current = self.register(key);
+                    let value = match value.chars().next() {
+                        Some('+') => current + eval_number(&value[1..]),
+                        Some('-') => current - eval_number(&value[1..]),
+                        _ => eval_number(value),
+                    };
+                    self.registers.insert(key.clone(), value);
+                }
+            }
+            "rr" => {
+                self.registers.remove(rest.trim());
+            }
+            "if" => {
+                let (result, body) = self.condition(rest);
+                self.conditional(result, body);
+            }
+            "ie" => {
+                let (result, body) = self.condition(rest);
+                self.else_stack.push(result);
+                self.conditional(result, body);
+            }
+            "el" => {
+                let result = !self.else_stack.pop().unwrap_or(true);
+                self.conditional(result, rest);
+            }
+            "do" => {
+                let line = format!(".{}", rest);
+                let (name, rest) = split_request(&line);
+                self.request(name, rest);
+            }
+            "ft" => {
+                let name = rest.trim();
+                let selection = FontSelection::from_name(name).unwrap_or(FontSelection::Previous);
+                self.font.select(selection);
+            }
+            "br" => self.w.line_break(),
+            "sp" | "bp" => self.w.vertical_space(),
+            "nf" => {
+                self.w.end_paragraph();
+                self.nofill = true;
+            }
+            "fi" => {
+                self.w.end_paragraph();
+                self.nofill = false;
+            }
+            "TS" => self.table(),
+            "EQ" => self.skip_until(".EN"),
+            _ => match self.package {
+                Package::Man => self.man_request(name, rest),
+                Package::Mdoc => self.mdoc_request(name, rest),
+            },
+        }
+    }
+
+    fn define_macro(&mut self, append: bool, rest: &str) {
+        let args = split_args(rest);
+        let Some(name) = args.first().cloned() else {
+            return;
+        };
+        let end = format!(".{}", args.get(1).map_or(".", String::as_str));
+
+        let mut body = Vec::new();
+
+        while let Some(line) = self.input.next_line() {
+            if line.trim_end() == end || (end == ".." && line.starts_with("..")) {
+                break;
+            }
+            // Copy mode: `\\` in a macro body stands for a single backslash.
+            body.push(line.replace("\\\\", "\\"));
+        }
+
+        if append {
+            self.macros.entry(name).or_default().extend(body);
+        } else {
+            self.macros.insert(name, body);
+        }
+    }
+
+    fn skip_until(&mut self, end: &str) {
+        while let Some(line) = self.input.next_line() {
+            if line.trim_end() == end {
+                break;
+            }
+        }
+    }
+
+    /// Runs or skips the body of a conditional request.
+    fn conditional(&mut self, result: bool, body: &str) {
+        let body = body.trim_start();
+
+        if result {
+            let body = body.strip_prefix("\\{").unwrap_or(body).trim_start();
+            if !body.is_empty() {
+                self.line(body);
+            }
+            return;
+        }
+
+        let mut depth = brace_depth(body);
+
+        while depth > 0 {
+            let Some(line) = self.input.next_line() else {
+                break;
+            };
+            depth += brace_depth(&line);
+        }
+    }
+
+    /// Evaluates the condition of an `.if`/`.ie` request and returns the rest of the line.
+    fn condition<'a>(&self, text: &'a str) -> (bool, &'a str) {
+        let text = text.trim_start();
+        let (negate, text) = match text.strip_prefix('!') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+
+        let Some(first) = text.chars().next() else {
+            return (false, "");
+        };
+
+        let (result, rest) = match first {
+            'n' | 'o' => (true, &text[first.len_utf8()..]),
+            't' | 'e' | 'v' => (false, &text[first.len_utf8()..]),
+            'd' | 'r' | 'c' | 'm' | 'F' | 'S' => {
+                let (arg, rest) = split_word(text[first.len_utf8()..].trim_start());
+                let result = match first {
+                    'd' => self.strings.contains_key(arg) || self.macros.contains_key(arg),
+                    'r' => self.registers.contains_key(arg),
+                    _ => false,
+                };
+                (result, rest)
+            }
+            '0'..='9' | '(' | '-' | '+' => {
+                let (expr, rest) = split_word(text);
+                (eval_number(expr) > 0, rest)
+            }
+            delim => {
+                let mut parts = text[first.len_utf8()..].splitn(3, delim);
+                let left = parts.next().unwrap_or_default();
+                let right = parts.next().unwrap_or_default();
+                (left == right, parts.next().unwrap_or_default())
+            }
+        };
+
+        (result != negate, rest)
+    }
+
+    fn table(&mut self) {
+        let mut lines = Vec::new();
+
+        while let Some(line) = self.input.next_line() {
+            if line.starts_with(".TE") {
+                break;
+            }
+            lines.push(self.interpolate(&line, 0));
+        }
+
+        self.w.end_paragraph();
+        let html = tbl::render(&lines, |text| escape::render_in(Font::Roman, text));
+        self.w.raw(&html);
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let end = text.find([' ', '\t']).unwrap_or(text.len());
+    (&text[..end], &text[end..])
+}
+
+fn brace_depth(line: &str) -> i32 {
+    line.matches("\\{").count() as i32 - line.matches("\\}").count() as i32
+}
+
+/// Replaces `\$1`…`\$9`, `\$*` and `\$@` in a macro body line.
+fn substitute_args(line: &str, args: &[String]) -> String {
+    if !line.contains("\\$") {
+        return line.to_string();
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(pos) = rest.find("\\$") {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 2..];
+
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some(c @ '1'..='9') => {
+                out.push_str(
+                    args.get(c as usize - '1' as usize)
+                        .map_or("", String::as_str),
+                );
+                rest = &rest[1..];
+            }
+            Some('*') => {
+                out.push_str(&args.join(" "));
+                rest = &rest[1..];
+            }
+            Some('@') => {
+                let quoted: Vec<String> = args.iter().map(|a| format!("\"{}\"", a)).collect();
+                out.push_str(&quoted.join(" "));
+                rest = &rest[1..];
+            }
+            Some('(') => {
+                let index: String = chars.take(2).collect();
+                let index: usize = index.parse().unwrap_or(0);
+                out.push_str(args.get(index.wrapping_sub(1)).map_or("", String::as_str));
+                rest = rest.get(3..).unwrap_or("");
+            }
+            _ => {}
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Evaluates a troff numeric expression: strictly left to right, with parentheses.
+fn eval_number(expr: &str) -> i64 {
+    fn operand(chars: &mut std::iter::Peekable<std::str::Chars>) -> i64 {
+        match chars.peek() {
+            Some('(') => {
+                chars.next();
+                let value = expression(chars);
+                chars.next_if_eq(&')');
+                value
+            }
+            Some('-') => {
+                chars.next();
+                operand(chars).wrapping_neg()
+            }
+            Some('+') => {
+                chars.next();
+                operand(chars)
+            }
+            _ => {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    digits.push(c);
+                }
+                // Scaling units make no difference to us.
+                while chars.next_if(|c| c.is_ascii_alphabetic()).is_some() {}
+                digits.parse::<f64>().map_or(0, |v| v as i64)
+            }
+        }
+    }
+
+    fn expression(chars: &mut std::iter::Peekable<std::str::Chars>) -> i64 {
+        let mut value = operand(chars);
+
+        while let Some(&op) = chars.peek() {
+            if op == ')' {
+                break;
+            }
+            chars.next();
+
+            let op2 = chars.next_if(|&c| c == '=' || (op == '<' && c == '>'));
+            let rhs = operand(chars);
+
+            value = match (op, op2) {
+                // Pages can hold any numbers, which mustn't overflow.
+                ('+', _) => value.wrapping_add(rhs),
+                ('-', _) => value.wrapping_sub(rhs),
+                ('*', _) => value.wrapping_mul(rhs),
+                ('/', _) => value.checked_div(rhs).unwrap_or(0),
+                ('%', _) => value.checked_rem(rhs).unwrap_or(0),
+                ('<', Some('=')) => (value <= rhs) as i64,
+                ('>', Some('=')) => (value >= rhs) as i64,
+                ('<', Some('>')) => (value != rhs) as i64,
+                ('<', _) => (value < rhs) as i64,
+                ('>', _) => (value > rhs) as i64,
+                ('=', _) => (value == rhs) as i64,
+                ('&', _) => (value > 0 && rhs > 0) as i64,
+                (':', _) => (value > 0 || rhs > 0) as i64,
+                _ => value,
+            };
+        }
+
+        value
+    }
+
+    expression(&mut expr.trim().chars().peekable())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions() {
+        let renderer = Renderer::new("");
+
+        assert_eq!(renderer.condition("n .B text"), (true, " .B text"));
+        assert_eq!(renderer.condition("t .B text"), (false, " .B text"));
+        assert_eq!(renderer.condition("!t text"), (true, " text"));
+        assert_eq!(renderer.condition("dlq text"), (true, " text"));
+        assert_eq!(renderer.condition("d missing text"), (false, " text"));
+        assert_eq!(renderer.condition("1+1 text"), (true, " text"));
+        assert_eq!(renderer.condition("2-2 text"), (false, " text"));
+        assert_eq!(renderer.condition("'a'a' text"), (true, " text"));
+        assert_eq!(renderer.condition("'a'b' text"), (false, " text"));
+        assert_eq!(renderer.condition(""), (false, ""));
+    }
+
+    #[test]
+    fn conditions_with_multibyte_delimiters() {
+        let renderer = Renderer::new("");
+
+        assert_eq!(renderer.condition("éaéaé text"), (true, " text"));
+        assert_eq!(renderer.condition("éaébé text"), (false, " text"));
+        assert_eq!(renderer.condition("é"), (true, ""));
+    }
+
+    #[test]
+    fn numbers_are_evaluated_left_to_right() {
+        assert_eq!(eval_number("1+2*3"), 9);
+        assert_eq!(eval_number("1+(2*3)"), 7);
+        assert_eq!(eval_number("-3+1"), -2);
+        assert_eq!(eval_number("7/2"), 3);
+        assert_eq!(eval_number("7%0"), 0);
+        assert_eq!(eval_number("1/0"), 0);
+        assert_eq!(eval_number("1.5i"), 1);
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(eval_number("3>2"), 1);
+        assert_eq!(eval_number("2<=1"), 0);
+        assert_eq!(eval_number("1<>2"), 1);
+        assert_eq!(eval_number("2=2"), 1);
+        assert_eq!(eval_number("1&0"), 0);
+        assert_eq!(eval_number("1:0"), 1);
+    }
+
+    #[test]
+    fn huge_numbers_do_not_overflow() {
+        assert_eq!(eval_number("99999999999999999999+1"), i64::MIN);
+        assert_eq!(eval_number("-99999999999999999999-2"), i64::MAX);
+        assert_eq!(eval_number("99999999999999999999*2"), -2);
+        assert_eq!(eval_number("-(-99999999999999999999-1)"), i64::MIN);
+
+        let renderer = Renderer::new("");
+        assert_eq!(
+            renderer.condition("99999999999999999999+1 text"),
+            (false, " text")
+        );
+    }
+}
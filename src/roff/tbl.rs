@@ -0,0 +1,272 @@
+//! Tables written for the tbl(1) preprocessor, between `.TS` and `.TE`.
+
+use std::{collections::VecDeque, fmt::Write};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Column {
+    /// One of `l`, `r`, `c`, `n`, `a`, `s` (span) or `^` (span from above).
+    kind: char,
+    bold: bool,
+    italic: bool,
+}
+
+fn parse_format_row(spec: &str) -> Vec<Column> {
+    let mut columns: Vec<Column> = Vec::new();
+
+    for c in spec.chars() {
+        match c.to_ascii_lowercase() {
+            kind @ ('l' | 'r' | 'c' | 'n' | 'a' | 's' | '^' | '_' | '=') => columns.push(Column {
+                kind,
+                ..Default::default()
+            }),
+            'b' => columns
+                .last_mut()
+                .into_iter()
+                .for_each(|col| col.bold = true),
+            'i' => columns
+                .last_mut()
+                .into_iter()
+                .for_each(|col| col.italic = true),
+            _ => {}
+        }
+    }
+
+    columns
+}
+
+fn option_char(options: &str, name: &str) -> Option<char> {
+    let start = options.find(&format!("{}(", name))? + name.len() + 1;
+    options[start..].chars().next()
+}
+
+/// Renders the lines between `.TS` and `.TE` as an HTML table, using `cell` for cell text.
+pub fn render(lines: &[String], cell: impl Fn(&str) -> String) -> String {
+    let mut lines = lines.iter().map(String::as_str).peekable();
+
+    let mut tab = '\t';
+    let mut class = "";
+
+    if let Some(&options) = lines.peek().filter(|line| line.trim_end().ends_with(';')) {
+        tab = option_char(options, "tab").unwrap_or('\t');
+        if options.contains("box") {
+            class = " class=\"box\"";
+        }
+        lines.next();
+    }
+
+    let mut formats = read_formats(&mut lines);
+    let mut row_index = 0;
+
+    let mut out = format!("<table{}>\n", class);
+
+    while let Some(line) = lines.next() {
+        if line.starts_with(".T&") {
+            formats = read_formats(&mut lines);
+            row_index = 0;
+            continue;
+        }
+
+        if line.starts_with('.') || line.starts_with('\'') {
+            continue;
+        }
+
+        if matches!(line.trim(), "_" | "=") {
+            continue;
+        }
+
+        let mut cells = Vec::new();
+        let mut queue: VecDeque<String> = line.split(tab).map(str::to_string).collect();
+
+        while let Some(text) = queue.pop_front() {
+            let Some(first) = text.strip_prefix("T{") else {
+                cells.push(text);
+                continue;
+            };
+
+            let (block, rest) = read_text_block(first, &mut lines);
+            cells.push(block);
+
+            // The rest of the row continues after the closing `T}`.
+            let more: Vec<&str> = rest.split(tab).skip(1).collect();
+            for text in more.into_iter().rev() {
+                queue.push_front(text.to_string());
+            }
+        }
+
+        let columns = match formats.get(row_index.min(formats.len().saturating_sub(1))) {
+            Some(format) => format.clone(),
+            None => vec![
+                Column {
+                    kind: 'l',
+                    ..Default::default()
+                };
+                cells.len()
+            ],
+        };
+        row_index += 1;
+
+        out.push_str("<tr>");
+
+        let mut texts = cells.into_iter();
+        let mut col = 0;
+
+        while col < columns.len() {
+            let spec = columns[col];
+
+            if spec.kind == 's' {
+                col += 1;
+                continue;
+            }
+
+            let span = 1 + columns[col + 1..]
+                .iter()
+                .take_while(|c| c.kind == 's')
+                .count();
+
+            let text = match spec.kind {
+                '^' | '_' | '=' => String::new(),
+                _ => texts.next().unwrap_or_default(),
+            };
+
+            let mut html = match text.trim() {
+                "_" | "=" | "\\_" => String::new(),
+                text => cell(text),
+            };
+
+            if spec.bold {
+                html = format!("<b>{}</b>", html);
+            }
+            if spec.italic {
+                html = format!("<i>{}</i>", html);
+            }
+
+            let align = match spec.kind {
+                'r' | 'n' => " style=\"text-align: right\"",
+                'c' => " style=\"text-align: center\"",
+                _ => "",
+            };
+
+            let _ = match span {
+                1 => write!(out, "<td{}>{}</td>", align, html),
+                span => write!(out, "<td colspan=\"{}\"{}>{}</td>", span, align, html),
+            };
+
+            col += span;
+        }
+
+        out.push_str("</tr>\n");
+    }
+
+    out.push_str("</table>\n");
+    out
+}
+
+fn read_formats<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Vec<Vec<Column>> {
+    let mut formats = Vec::new();
+
+    for line in lines.by_ref() {
+        let line = line.trim_end();
+        let (line, last) = match line.strip_suffix('.') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+
+        formats.extend(
+            line.split(',')
+                .map(parse_format_row)
+                .filter(|row| !row.is_empty()),
+        );
+
+        if last {
+            break;
+        }
+    }
+
+    formats
+}
+
+/// Collects a `T{` … `T}` text block into one line of text.
+///
+/// Also returns whatever followed the closing `T}` on its line.
+fn read_text_block<'a>(
+    first: &str,
+    lines: &mut impl Iterator<Item = &'a str>,
+) -> (String, &'a str) {
+    let mut parts = vec![first.trim().to_string()];
+    let mut rest = "";
+
+    for line in lines.by_ref() {
+        if let Some(after) = line.strip_prefix("T}") {
+            rest = after;
+            break;
+        }
+
+        if line.starts_with(".br") || line.starts_with(".sp") {
+            parts.push("\n".to_string());
+        } else if !line.starts_with('.') {
+            parts.push(line.to_string());
+        }
+    }
+
+    parts.retain(|p| !p.is_empty());
+    (parts.join(" "), rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(lines: &[&str]) -> String {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        render(&lines, |text| text.to_uppercase())
+    }
+
+    #[test]
+    fn options_and_alignment() {
+        assert_eq!(
+            table(&["box tab(;);", "l r c.", "a;1;x", "b;22;y"]),
+            concat!(
+                "<table class=\"box\">\n",
+                "<tr><td>A</td><td style=\"text-align: right\">1</td>",
+                "<td style=\"text-align: center\">X</td></tr>\n",
+                "<tr><td>B</td><td style=\"text-align: right\">22</td>",
+                "<td style=\"text-align: center\">Y</td></tr>\n",
+                "</table>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn spans_and_fonts() {
+        assert_eq!(
+            table(&["lb s", "l li.", "title", "_", "a\tb"]),
+            concat!(
+                "<table>\n",
+                "<tr><td colspan=\"2\"><b>TITLE</b></td></tr>\n",
+                "<tr><td>A</td><td><i>B</i></td></tr>\n",
+                "</table>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn text_blocks() {
+        assert_eq!(
+            table(&["l l.", "T{", "long", ".br", "text", "T}\tafter"]),
+            "<table>\n<tr><td>LONG \n TEXT</td><td>AFTER</td></tr>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn format_changes() {
+        assert_eq!(
+            table(&["l.", "a", ".T&", "r.", "b"]),
+            concat!(
+                "<table>\n",
+                "<tr><td>A</td></tr>\n",
+                "<tr><td style=\"text-align: right\">B</td></tr>\n",
+                "</table>\n",
+            )
+        );
+    }
+}
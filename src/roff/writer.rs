@@ -0,0 +1,259 @@
+//! Block structure of the generated HTML.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    Section,
+    Indent,
+    Display,
+    DefinitionList,
+    BulletList,
+    EnumList,
+    Table,
+    Term,
+    Description,
+    Item,
+    Paragraph,
+    Preformatted,
+}
+
+impl Block {
+    fn tag(self) -> &'static str {
+        match self {
+            Block::Section => "section",
+            Block::Indent | Block::Display => "div",
+            Block::DefinitionList => "dl",
+            Block::BulletList => "ul",
+            Block::EnumList => "ol",
+            Block::Table => "table",
+            Block::Term => "dt",
+            Block::Description => "dd",
+            Block::Item => "li",
+            Block::Paragraph => "p",
+            Block::Preformatted => "pre",
+        }
+    }
+
+    /// Blocks that hold running text directly.
+    fn is_text(self) -> bool {
+        matches!(self, Block::Paragraph | Block::Preformatted | Block::Term)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Writer {
+    out: String,
+    stack: Vec<Block>,
+    /// Nothing has been written into the innermost block yet.
+    fresh: bool,
+}
+
+impl Writer {
+    pub fn top(&self) -> Option<Block> {
+        self.stack.last().copied()
+    }
+
+    pub fn contains(&self, block: Block) -> bool {
+        self.stack.contains(&block)
+    }
+
+    pub fn len(&self) -> usize {
+        self.out.len()
+    }
+
+    pub fn open(&mut self, block: Block) {
+        self.open_with_class(block, "");
+    }
+
+    pub fn open_with_class(&mut self, block: Block, class: &str) {
+        match class {
+            "" => self.out.push_str(&format!("<{}>", block.tag())),
+            class => self
+                .out
+                .push_str(&format!("<{} class=\"{}\">", block.tag(), class)),
+        }
+
+        if !block.is_text() {
+            self.out.push('\n');
+        }
+
+        self.stack.push(block);
+        self.fresh = true;
+    }
+
+    pub fn close(&mut self) {
+        if let Some(block) = self.stack.pop() {
+            self.out.push_str(&format!("</{}>\n", block.tag()));
+            self.fresh = false;
+        }
+    }
+
+    /// Drops the innermost block if nothing was written into it.
+    pub fn discard_if_fresh(&mut self, block: Block) -> bool {
+        if !self.fresh || self.top() != Some(block) {
+            return false;
+        }
+
+        let tag_start = self.out.rfind('<').unwrap_or(self.out.len());
+        self.out.truncate(tag_start);
+        self.stack.pop();
+        self.fresh = false;
+
+        true
+    }
+
+    /// Closes blocks until the innermost one satisfies `keep`.
+    pub fn close_until(&mut self, keep: impl Fn(Block) -> bool) {
+        while let Some(top) = self.top() {
+            if keep(top) {
+                break;
+            }
+            self.close();
+        }
+    }
+
+    /// Closes blocks up to and including the innermost `block`, if there is one.
+    pub fn close_through(&mut self, block: Block) {
+        if !self.contains(block) {
+            return;
+        }
+
+        while let Some(top) = self.top() {
+            self.close();
+            if top == block {
+                break;
+            }
+        }
+    }
+
+    pub fn close_all(&mut self) {
+        while !self.stack.is_empty() {
+            self.close();
+        }
+    }
+
+    /// Ends the current paragraph, if any.
+    pub fn end_paragraph(&mut self) {
+        if matches!(self.top(), Some(Block::Paragraph | Block::Preformatted)) {
+            self.close();
+        }
+    }
+
+    /// Writes one input line of running text into a paragraph or preformatted block.
+    pub fn text_line(&mut self, html: &str, nofill: bool, join: bool) {
+        let container = if nofill {
+            Block::Preformatted
+        } else {
+            Block::Paragraph
+        };
+
+        match self.top() {
+            Some(Block::Term) => {}
+            Some(top) if top == container => {}
+            Some(top) if top.is_text() => {
+                self.close();
+                self.open(container);
+            }
+            _ => self.open(container),
+        }
+
+        if !self.fresh && !join {
+            self.out.push('\n');
+        }
+
+        self.out.push_str(html);
+        self.fresh = false;
+    }
+
+    /// Writes markup at the current position without opening any block.
+    pub fn raw(&mut self, html: &str) {
+        self.out.push_str(html);
+        self.fresh = false;
+    }
+
+    pub fn line_break(&mut self) {
+        if self.top() == Some(Block::Paragraph) && !self.fresh {
+            self.raw("<br>");
+        }
+    }
+
+    /// Vertical space: a new paragraph in filled text, an empty line in preformatted text.
+    pub fn vertical_space(&mut self) {
+        match self.top() {
+            Some(Block::Preformatted) => self.raw("\n"),
+            _ => self.end_paragraph(),
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        self.close_all();
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_of_a_paragraph() {
+        let mut writer = Writer::default();
+        writer.text_line("one", false, false);
+        writer.text_line("two", false, false);
+        writer.text_line("three", false, true);
+
+        assert_eq!(writer.finish(), "<p>one\ntwothree</p>\n");
+    }
+
+    #[test]
+    fn switching_between_filled_and_preformatted_text() {
+        let mut writer = Writer::default();
+        writer.open(Block::Section);
+        writer.text_line("filled", false, false);
+        writer.text_line("  kept", true, false);
+        writer.vertical_space();
+        writer.text_line("as is", true, false);
+
+        assert_eq!(
+            writer.finish(),
+            "<section>\n<p>filled</p>\n<pre>  kept\n\nas is</pre>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn empty_blocks_are_discarded() {
+        let mut writer = Writer::default();
+        writer.open(Block::Section);
+        writer.open_with_class(Block::Paragraph, "synopsis");
+
+        assert!(writer.discard_if_fresh(Block::Paragraph));
+        assert!(!writer.discard_if_fresh(Block::Section));
+        assert_eq!(writer.finish(), "<section>\n</section>\n");
+    }
+
+    #[test]
+    fn closing_through_a_block() {
+        let mut writer = Writer::default();
+        writer.open(Block::Section);
+        writer.open(Block::DefinitionList);
+        writer.open(Block::Description);
+        writer.text_line("text", false, false);
+
+        writer.close_through(Block::DefinitionList);
+        assert_eq!(writer.top(), Some(Block::Section));
+
+        writer.close_through(Block::Table);
+        assert_eq!(writer.top(), Some(Block::Section));
+    }
+
+    #[test]
+    fn line_breaks_only_between_text() {
+        let mut writer = Writer::default();
+        writer.open(Block::Paragraph);
+        writer.line_break();
+        writer.text_line("one", false, false);
+        writer.line_break();
+        writer.text_line("two", false, false);
+
+        assert_eq!(writer.finish(), "<p>one<br>\ntwo</p>\n");
+    }
+}
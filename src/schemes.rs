@@ -0,0 +1,160 @@
+//! Custom URI schemes for documents that are generated inside the viewer.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use webkit6::{URISchemeRequest, WebContext, gio, glib};
+
+use crate::{decompress, devhelp, home, html, info, man, xdg};
+
+/// Serves installed documentation like `file:` does, decompressing it if needed.
+const DOCFILE_SCHEME: &str = "docfile";
+
+/// Schemes that read local files, which only local pages may load.
+const LOCAL_SCHEMES: &[&str] = &["man", "info", "docviewer", DOCFILE_SCHEME];
+
+pub fn register(context: &WebContext) {
+    if let Some(security_manager) = context.security_manager() {
+        for scheme in LOCAL_SCHEMES {
+            security_manager.register_uri_scheme_as_local(scheme);
+        }
+    }
+
+    context.register_uri_scheme("man", |request| {
+        let path = request_path(request, "man");
+        respond(request, move || man::page_for_uri(&path));
+    });
+
+    context.register_uri_scheme("info", |request| {
+        let path = request_path(request, "info");
+        respond(request, move || info::page_for_uri(&path));
+    });
+
+    context.register_uri_scheme("docviewer", |request| {
+        let path = request_path(request, "docviewer");
+        respond(request, move || home::page_for_uri(&path));
+    });
+
+    context.register_uri_scheme(DOCFILE_SCHEME, |request| {
+        let path = request_path(request, DOCFILE_SCHEME);
+        respond_with(request, move || read_document(&path));
+    });
+}
+
+/// Rewrites `file:` URIs of compressed documents to [`DOCFILE_SCHEME`], which can read them.
+///
+/// Pages loaded this way resolve relative links within the same scheme, so the
+/// rest of a compressed documentation tree keeps working.
+pub fn document_uri(uri: &str) -> String {
+    let Some(rest) = uri.strip_prefix("file://") else {
+        return uri.to_string();
+    };
+
+    let path = rest.split(['#', '?']).next().unwrap_or_default();
+
+    match decompress::codec_for_name(path) {
+        Some(_) => format!("{}://{}", DOCFILE_SCHEME, rest),
+        None => uri.to_string(),
+    }
+}
+
+/// Where [`DOCFILE_SCHEME`] may read from: the directories documentation is installed in.
+fn documentation_roots() -> Vec<PathBuf> {
+    let mut roots = man::man_path();
+    roots.extend(info::info_path());
+    roots.extend(devhelp::book_roots());
+    roots.extend(xdg::data_dirs().iter().map(|dir| dir.join("doc")));
+    roots
+}
+
+/// Finds the file for a [`DOCFILE_SCHEME`] path, which has to be installed documentation.
+///
+/// Files next to compressed ones, like the images of Info manuals and the stylesheets of
+/// books, are served as they are.
+fn find_document(path: &Path) -> io::Result<PathBuf> {
+    let file = decompress::find_variant(path).ok_or(io::ErrorKind::NotFound)?;
+    let file = file.canonicalize()?;
+
+    let installed = documentation_roots()
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| file.starts_with(root));
+
+    match installed {
+        true => Ok(file),
+        false => Err(io::ErrorKind::PermissionDenied.into()),
+    }
+}
+
+/// Reads a local file for [`DOCFILE_SCHEME`], returning its contents and MIME type.
+fn read_document(path: &str) -> (Vec<u8>, String) {
+    let path = path.trim_start_matches("//");
+    let path = Path::new(path.split(['#', '?']).next().unwrap_or_default());
+
+    let result =
+        find_document(path).and_then(|file| decompress::read(&file).map(|bytes| (file, bytes)));
+
+    match result {
+        Ok((file, bytes)) => {
+            let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+            let name = decompress::strip_extension(&file_name);
+
+            let (content_type, _) = gio::content_type_guess(Some(name), &bytes);
+            let mime_type = gio::content_type_get_mime_type(&content_type)
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+
+            (bytes, mime_type)
+        }
+        Err(e) => {
+            let message = format!("Could not read {}: {}", path.display(), e);
+            let page = html::error_page("Could not open document", &message);
+            (page.into_bytes(), "text/html".to_string())
+        }
+    }
+}
+
+/// Everything after `scheme:` in the request URI, percent-decoded.
+fn request_path(request: &URISchemeRequest, scheme: &str) -> String {
+    let uri = request.uri().map(|s| s.to_string()).unwrap_or_default();
+    let path = uri
+        .strip_prefix(scheme)
+        .and_then(|s| s.strip_prefix(':'))
+        .unwrap_or(&uri);
+
+    glib::Uri::unescape_string(path, None::<&str>)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Builds the page on a worker thread and finishes the request with it.
+fn respond<F>(request: &URISchemeRequest, build: F)
+where
+    F: FnOnce() -> String + Send + 'static,
+{
+    respond_with(request, move || {
+        (build().into_bytes(), "text/html".to_string())
+    });
+}
+
+/// Like [`respond`], for responses of any MIME type.
+fn respond_with<F>(request: &URISchemeRequest, build: F)
+where
+    F: FnOnce() -> (Vec<u8>, String) + Send + 'static,
+{
+    let request = request.clone();
+
+    glib::spawn_future_local(async move {
+        let (body, mime_type) = gio::spawn_blocking(build).await.unwrap_or_else(|_| {
+            let page = html::error_page("Internal error", "The document could not be generated.");
+            (page.into_bytes(), "text/html".to_string())
+        });
+
+        let bytes = glib::Bytes::from_owned(body);
+        let stream = gio::MemoryInputStream::from_bytes(&bytes);
+
+        request.finish(&stream, bytes.len() as i64, Some(&mime_type));
+    });
+}
@@ -0,0 +1,698 @@
+//! Full-text search over man pages, Info manuals and Devhelp books.
+//!
+//! The index is kept in the cache directory. [`Index::update`] compares it with the
+//! installed documentation by modification time, so only files that were added,
+//! changed or removed since the last run are read again. It runs at startup and again
+//! whenever something changes in one of the [`watched_dirs`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::UNIX_EPOCH,
+};
+
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+
+use crate::{decompress, devhelp, html, info, man, roff, store, xdg};
+
+/// Bumped whenever the index format or tokenization changes, to force a rebuild.
+const INDEX_VERSION: u32 = 2;
+
+const INDEX_FILE: &str = "search-index";
+
+/// Words longer than this are usually hashes or encoded data.
+const MAX_TERM_LEN: usize = 40;
+
+/// How much more a word in a title counts than one in the text.
+const TITLE_WEIGHT: u32 = 5;
+
+/// BM25 term frequency saturation and length normalization.
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// Document IDs are renumbered once this fraction of them belongs to removed documents.
+const MAX_REMOVED_FRACTION: f32 = 0.25;
+
+/// Characters of context around the first match in a snippet.
+const SNIPPET_BEFORE: usize = 60;
+const SNIPPET_LENGTH: usize = 200;
+
+/// Words too common to be worth indexing.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "if", "in", "is", "it", "of",
+    "on", "or", "that", "the", "this", "to", "was", "with",
+];
+
+static STEMMER: LazyLock<Stemmer> = LazyLock::new(|| Stemmer::create(Algorithm::English));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Source {
+    ManPage,
+    InfoNode,
+    Book,
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::ManPage => "Man page",
+            Source::InfoNode => "Info",
+            Source::Book => "HTML",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Document {
+    uri: String,
+    title: String,
+    source: Source,
+    /// The plain text, with runs of whitespace collapsed, for snippets.
+    text: String,
+    /// Number of terms, for length normalization.
+    length: u32,
+}
+
+impl Document {
+    fn new(uri: String, title: String, source: Source, text: &str) -> Self {
+        Document {
+            uri,
+            title,
+            source,
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+            length: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(PathBuf, io::Error),
+    Info(info::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+            Error::Info(e) => e.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    /// Modification time in seconds since the epoch.
+    modified: u64,
+    documents: Vec<u32>,
+}
+
+/// A file that documents are read from.
+enum SourceFile {
+    ManPage {
+        path: PathBuf,
+        name: String,
+        section: String,
+    },
+    InfoManual {
+        path: PathBuf,
+        name: String,
+    },
+    BookPage {
+        path: PathBuf,
+    },
+}
+
+impl SourceFile {
+    fn path(&self) -> &Path {
+        match self {
+            SourceFile::ManPage { path, .. }
+            | SourceFile::InfoManual { path, .. }
+            | SourceFile::BookPage { path } => path,
+        }
+    }
+
+    /// The documents in the file.
+    fn read(&self) -> Result<Vec<Document>, Error> {
+        let read_to_string =
+            |path: &Path| decompress::read_to_string(path).map_err(|e| Error::Io(path.into(), e));
+
+        match self {
+            SourceFile::ManPage {
+                path,
+                name,
+                section,
+            } => {
+                // Pages that are only `.so` links would duplicate the page they point to.
+                let source = read_to_string(path)?;
+                if man::link_target(&source).is_some() {
+                    return Ok(Vec::new());
+                }
+
+                let uri = man::ManPageRef::new(name, Some(section)).uri();
+                let title = format!("{}({})", name, section);
+                let text = html::text(&roff::render(&source).body);
+
+                Ok(vec![Document::new(uri, title, Source::ManPage, &text)])
+            }
+            SourceFile::InfoManual { name, .. } => {
+                let nodes = info::InfoFile::open(name)
+                    .and_then(|file| file.nodes())
+                    .map_err(Error::Info)?;
+
+                let documents = nodes
+                    .into_iter()
+                    .map(|node| {
+                        let uri = info::NodeRef::new(name, &node.name).uri();
+                        let title = format!("({}){}", name, node.name);
+                        Document::new(uri, title, Source::InfoNode, &node.plain_text())
+                    })
+                    .collect();
+
+                Ok(documents)
+            }
+            SourceFile::BookPage { path } => {
+                let page = read_to_string(path)?;
+
+                let title = html::title(&page)
+                    .filter(|title| !title.is_empty())
+                    .unwrap_or_else(|| {
+                        path.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into()
+                    });
+
+                let uri = html::file_uri(path);
+                Ok(vec![Document::new(
+                    uri,
+                    title,
+                    Source::Book,
+                    &html::text(&page),
+                )])
+            }
+        }
+    }
+}
+
+/// Every file the index should cover.
+fn source_files() -> Vec<SourceFile> {
+    let mut files = Vec::new();
+
+    man::walk_pages(|_, path, name, section| {
+        files.push(SourceFile::ManPage {
+            path,
+            name: name.to_string(),
+            section: section.to_string(),
+        });
+    });
+
+    let directory = info::Directory::load();
+    let manuals: HashSet<&str> = directory
+        .categories
+        .iter()
+        .flat_map(|category| &category.entries)
+        .map(|entry| entry.target.file.as_str())
+        .collect();
+
+    for name in manuals {
+        if let Some(path) = info::find_file(name) {
+            files.push(SourceFile::InfoManual {
+                path,
+                name: name.to_string(),
+            });
+        }
+    }
+
+    for book in devhelp::books() {
+        let Ok(entries) = fs::read_dir(&book.dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            if decompress::strip_extension(&file_name).ends_with(".html") {
+                files.push(SourceFile::BookPage { path });
+            }
+        }
+    }
+
+    files
+}
+
+/// The directories that documentation is installed into, for watching.
+///
+/// Includes the section directories of each man hierarchy and the directory of each book,
+/// since watching a directory doesn't cover its subdirectories.
+pub fn watched_dirs() -> Vec<PathBuf> {
+    let subdirs = |root: &Path, matches: fn(&str) -> bool| -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(root) else {
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .filter(|entry| matches(&entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect()
+    };
+
+    let mut dirs = Vec::new();
+
+    for root in man::man_path() {
+        dirs.extend(subdirs(&root, |name| name.starts_with("man")));
+        dirs.push(root);
+    }
+
+    dirs.extend(info::info_path());
+
+    for root in devhelp::book_roots() {
+        dirs.extend(subdirs(&root, |_| true));
+        dirs.push(root);
+    }
+
+    dirs.retain(|dir| dir.is_dir());
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn modified(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Splits text into words, with the byte offset of each.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// The index term for a word, or `None` if it is not worth indexing.
+///
+/// Plain words are stemmed, so "sockets" finds "socket". Identifiers like
+/// `SO_REUSEPORT` or `utf8` are kept as they are.
+fn term(word: &str) -> Option<String> {
+    let word = word.trim_matches('_').to_lowercase();
+
+    if word.chars().count() < 2 || word.len() > MAX_TERM_LEN {
+        return None;
+    }
+    if STOP_WORDS.contains(&word.as_str()) {
+        return None;
+    }
+
+    if word.chars().all(char::is_alphabetic) {
+        Some(STEMMER.stem(&word).into_owned())
+    } else {
+        Some(word)
+    }
+}
+
+/// One search result.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub uri: String,
+    pub title: String,
+    pub source: Source,
+    /// Text around the first match, as Pango markup with matching words in bold.
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    /// Documents by ID. Removed documents leave a `None` behind, so IDs stay valid until
+    /// [`Index::compact`] renumbers them.
+    documents: Vec<Option<Document>>,
+    files: HashMap<PathBuf, FileEntry>,
+    /// Document IDs and weighted term counts, by term.
+    postings: HashMap<String, Vec<(u32, u32)>>,
+    total_length: u64,
+}
+
+impl Index {
+    fn path() -> PathBuf {
+        xdg::app_cache_dir().join(INDEX_FILE)
+    }
+
+    /// Reads the index from the cache, or starts an empty one if it is missing or outdated.
+    pub fn load() -> Index {
+        let index = fs::File::open(Self::path())
+            .ok()
+            .and_then(|file| bincode::deserialize_from::<_, Index>(BufReader::new(file)).ok())
+            .filter(|index| index.version == INDEX_VERSION);
+
+        index.unwrap_or_else(|| Index {
+            version: INDEX_VERSION,
+            ..Default::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), store::Error> {
+        store::save_bincode(&Self::path(), self)
+    }
+
+    /// Number of documents in the index.
+    pub fn len(&self) -> usize {
+        self.documents.iter().flatten().count()
+    }
+
+    /// Reads files that changed since the last update and forgets ones that are gone.
+    ///
+    /// Returns whether anything changed.
+    pub fn update(&mut self) -> bool {
+        let files = source_files();
+        let paths: HashSet<&Path> = files.iter().map(SourceFile::path).collect();
+
+        let mut seen = HashSet::new();
+        let mut changed = false;
+
+        for file in &files {
+            let path = file.path().to_path_buf();
+
+            // Symlinks to files that are indexed anyway would only add duplicates.
+            if path.is_symlink()
+                && fs::canonicalize(&path).is_ok_and(|target| paths.contains(target.as_path()))
+            {
+                continue;
+            }
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+
+            let modified = modified(&path);
+            if self
+                .files
+                .get(&path)
+                .is_some_and(|entry| entry.modified == modified)
+            {
+                continue;
+            }
+
+            // A file that can't be read is still recorded, so it isn't retried until it changes.
+            let documents = file.read().unwrap_or_else(|e| {
+                eprintln!("Could not index {}: {}", path.display(), e);
+                Vec::new()
+            });
+
+            self.remove_file(&path);
+            self.add_file(path, modified, documents);
+            changed = true;
+        }
+
+        let removed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+
+        for path in removed {
+            self.remove_file(&path);
+            changed = true;
+        }
+
+        if changed {
+            self.postings.retain(|_, postings| {
+                postings.retain(|(id, _)| self.documents[*id as usize].is_some());
+                !postings.is_empty()
+            });
+
+            let removed = self.documents.len() - self.len();
+            if removed as f32 > self.documents.len() as f32 * MAX_REMOVED_FRACTION {
+                self.compact();
+            }
+        }
+
+        changed
+    }
+
+    /// Renumbers the documents so removed ones no longer take up IDs.
+    ///
+    /// IDs keep their order, so postings stay sorted by ID.
+    fn compact(&mut self) {
+        let mut new_ids = vec![None; self.documents.len()];
+        let mut documents = Vec::with_capacity(self.len());
+
+        for (id, document) in self.documents.drain(..).enumerate() {
+            if let Some(document) = document {
+                new_ids[id] = Some(documents.len() as u32);
+                documents.push(Some(document));
+            }
+        }
+        self.documents = documents;
+
+        for entry in self.files.values_mut() {
+            entry.documents = entry
+                .documents
+                .iter()
+                .filter_map(|id| new_ids[*id as usize])
+                .collect();
+        }
+
+        self.postings.retain(|_, postings| {
+            postings.retain_mut(|(id, _)| match new_ids[*id as usize] {
+                Some(new_id) => {
+                    *id = new_id;
+                    true
+                }
+                None => false,
+            });
+            !postings.is_empty()
+        });
+    }
+
+    fn add_file(&mut self, path: PathBuf, modified: u64, documents: Vec<Document>) {
+        let mut ids = Vec::new();
+
+        for mut document in documents {
+            let id = self.documents.len() as u32;
+            let mut counts: HashMap<String, u32> = HashMap::new();
+
+            for (_, word) in words(&document.title) {
+                if let Some(term) = term(word) {
+                    *counts.entry(term).or_default() += TITLE_WEIGHT;
+                }
+            }
+            for (_, word) in words(&document.text) {
+                if let Some(term) = term(word) {
+                    *counts.entry(term).or_default() += 1;
+                }
+            }
+
+            document.length = counts.values().sum();
+            self.total_length += document.length as u64;
+
+            for (term, count) in counts {
+                self.postings.entry(term).or_default().push((id, count));
+            }
+
+            self.documents.push(Some(document));
+            ids.push(id);
+        }
+
+        self.files.insert(
+            path,
+            FileEntry {
+                modified,
+                documents: ids,
+            },
+        );
+    }
+
+    /// Forgets a file's documents. Their postings are dropped at the end of the update.
+    fn remove_file(&mut self, path: &Path) {
+        let Some(entry) = self.files.remove(path) else {
+            return;
+        };
+
+        for id in entry.documents {
+            if let Some(document) = self.documents[id as usize].take() {
+                self.total_length -= document.length as u64;
+            }
+        }
+    }
+
+    /// Documents containing every word of `query`, ranked with BM25, best first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Hit> {
+        let mut terms: Vec<String> = words(query).filter_map(|(_, word)| term(word)).collect();
+        terms.sort();
+        terms.dedup();
+
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let count = self.len().max(1) as f32;
+        let average_length = (self.total_length as f32 / count).max(1.0);
+
+        let mut scores: HashMap<u32, (usize, f32)> = HashMap::new();
+
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                return Vec::new();
+            };
+
+            let frequency = postings.len() as f32;
+            let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+            for &(id, tf) in postings {
+                let Some(document) = &self.documents[id as usize] else {
+                    continue;
+                };
+
+                let tf = tf as f32;
+                let norm = 1.0 - B + B * document.length as f32 / average_length;
+                let score = idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+
+                let entry = scores.entry(id).or_default();
+                entry.0 += 1;
+                entry.1 += score;
+            }
+        }
+
+        let mut ranked: Vec<(u32, f32)> = scores
+            .into_iter()
+            .filter(|(_, (matched, _))| *matched == terms.len())
+            .map(|(id, (_, score))| (id, score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // The same page can be installed in several places; only show it once.
+        let mut uris = HashSet::new();
+
+        ranked
+            .into_iter()
+            .filter_map(|(id, _)| self.documents[id as usize].as_ref())
+            .filter(|document| uris.insert(document.uri.clone()))
+            .take(limit)
+            .map(|document| Hit {
+                uri: document.uri.clone(),
+                title: document.title.clone(),
+                source: document.source,
+                snippet: snippet(&document.text, &terms),
+            })
+            .collect()
+    }
+}
+
+/// Text around the first word matching one of `terms`, with every match in bold.
+fn snippet(text: &str, terms: &[String]) -> String {
+    let first = words(text)
+        .find(|(_, word)| term(word).is_some_and(|t| terms.contains(&t)))
+        .map_or(0, |(offset, _)| offset);
+
+    let mut start = first.saturating_sub(SNIPPET_BEFORE);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    // Start at a word boundary unless that skips the match.
+    if start > 0
+        && let Some(space) = text[start..first].find(' ')
+    {
+        start += space + 1;
+    }
+
+    let mut end = (start + SNIPPET_LENGTH).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let window = &text[start..end];
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+
+    let mut last = 0;
+    for (offset, word) in words(window) {
+        if term(word).is_some_and(|t| terms.contains(&t)) {
+            out.push_str(&html::escape(&window[last..offset]));
+            out.push_str(&format!("<b>{}</b>", html::escape(word)));
+            last = offset + word.len();
+        }
+    }
+    out.push_str(&html::escape(&window[last..]));
+
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(index: &mut Index, path: &str, title: &str, text: &str) {
+        let uri = format!("file://{}", path);
+        let document = Document::new(uri, title.to_string(), Source::Book, text);
+        index.add_file(PathBuf::from(path), 0, vec![document]);
+    }
+
+    #[test]
+    fn compacting_drops_removed_documents() {
+        let mut index = Index::default();
+        add(&mut index, "/a", "alpha", "first words");
+        add(&mut index, "/b", "beta", "second words");
+        add(&mut index, "/c", "gamma", "third words");
+
+        index.remove_file(Path::new("/a"));
+        index.remove_file(Path::new("/b"));
+        index.compact();
+
+        assert_eq!(index.documents.len(), 1);
+        assert_eq!(index.files[Path::new("/c")].documents, vec![0]);
+        assert_eq!(index.postings["gamma"], vec![(0, TITLE_WEIGHT)]);
+        assert!(!index.postings.contains_key("alpha"));
+
+        let hits = index.search("third words", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "gamma");
+    }
+
+    #[test]
+    fn snippets_come_from_the_indexed_text() {
+        let mut index = Index::default();
+        add(
+            &mut index,
+            "/a",
+            "alpha",
+            "Binds\n   several <sockets>\tto one port",
+        );
+
+        let hits = index.search("socket", 10);
+        assert_eq!(
+            hits[0].snippet,
+            "Binds several &lt;<b>sockets</b>&gt; to one port"
+        );
+    }
+
+    #[test]
+    fn documents_added_after_compacting_get_new_ids() {
+        let mut index = Index::default();
+        add(&mut index, "/a", "alpha", "words");
+        add(&mut index, "/b", "beta", "words");
+
+        index.remove_file(Path::new("/a"));
+        index.compact();
+        add(&mut index, "/c", "gamma", "words");
+
+        assert_eq!(index.files[Path::new("/b")].documents, vec![0]);
+        assert_eq!(index.files[Path::new("/c")].documents, vec![1]);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("words", 10).len(), 2);
+    }
+}
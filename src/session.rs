@@ -0,0 +1,147 @@
+//! The open windows and their tabs, saved so they can be restored on the next launch.
+
+use std::{
+    fmt, fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{store, xdg};
+
+/// Bumped whenever the format changes, so older files are ignored instead of misread.
+const SESSION_VERSION: u32 = 3;
+
+const SESSION_FILE: &str = "session";
+
+/// A WebView's back/forward list, as serialized by WebKit.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct History(pub Vec<u8>);
+
+impl fmt::Debug for History {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "History({} bytes)", self.0.len())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TabState {
+    pub uri: String,
+    pub title: Option<String>,
+    pub history: Option<History>,
+    /// The vertical scroll offset of the page, in CSS pixels.
+    pub scroll_y: f64,
+    pub pinned: bool,
+}
+
+impl TabState {
+    /// A tab that hasn't been opened before.
+    pub fn new(uri: &str) -> Self {
+        TabState {
+            uri: uri.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The tabs of one window.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub tabs: Vec<TabState>,
+    /// The position of the selected tab.
+    pub selected: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    version: u32,
+    /// In the order the windows were opened.
+    pub windows: Vec<WindowState>,
+}
+
+impl Session {
+    pub fn new(windows: Vec<WindowState>) -> Self {
+        Session {
+            version: SESSION_VERSION,
+            windows,
+        }
+    }
+
+    fn path() -> PathBuf {
+        xdg::app_state_dir().join(SESSION_FILE)
+    }
+
+    /// Reads the last saved session, if there is one in the current format.
+    pub fn load() -> Option<Session> {
+        Self::read(&Self::path())
+    }
+
+    fn read(path: &Path) -> Option<Session> {
+        let file = fs::File::open(path).ok()?;
+
+        bincode::deserialize_from::<_, Session>(BufReader::new(file))
+            .ok()
+            .filter(|session| session.version == SESSION_VERSION)
+    }
+
+    pub fn save(&self) -> Result<(), store::Error> {
+        store::save_bincode(&Self::path(), self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("docviewer-session-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn round_trip() {
+        let session = Session::new(vec![
+            WindowState {
+                tabs: vec![
+                    TabState {
+                        uri: "man:ls(1)".to_string(),
+                        title: Some("ls(1)".to_string()),
+                        history: Some(History(vec![0, 1, 2, 255])),
+                        scroll_y: 1234.5,
+                        pinned: true,
+                    },
+                    TabState::new("info:find"),
+                ],
+                selected: 1,
+            },
+            WindowState::default(),
+        ]);
+
+        let path = path("round-trip");
+        store::save_bincode(&path, &session).unwrap();
+
+        assert_eq!(Session::read(&path), Some(session));
+    }
+
+    #[test]
+    fn other_versions_are_ignored() {
+        let mut session = Session::new(vec![WindowState::default()]);
+        session.version = SESSION_VERSION - 1;
+
+        let path = path("old-version");
+        store::save_bincode(&path, &session).unwrap();
+
+        assert_eq!(Session::read(&path), None);
+        assert_eq!(Session::read(&path.with_file_name("missing")), None);
+    }
+
+    #[test]
+    fn damaged_files_are_ignored() {
+        let path = path("damaged");
+        store::write(&path, [1, 2, 3]).unwrap();
+
+        assert_eq!(Session::read(&path), None);
+    }
+}
@@ -0,0 +1,77 @@
+//! User preferences, kept in a JSON file under `$XDG_CONFIG_HOME`.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    home,
+    store::{self, Error},
+    xdg,
+};
+
+/// What a new tab shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NewTabPage {
+    #[default]
+    Home,
+    Blank,
+    Custom,
+}
+
+impl NewTabPage {
+    /// The choices in the order the preferences list them.
+    pub const ALL: [NewTabPage; 3] = [NewTabPage::Home, NewTabPage::Blank, NewTabPage::Custom];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NewTabPage::Home => "Home page",
+            NewTabPage::Blank => "Blank page",
+            NewTabPage::Custom => "Custom address",
+        }
+    }
+
+    pub fn position(self) -> u32 {
+        Self::ALL.iter().position(|&page| page == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_position(position: u32) -> Self {
+        Self::ALL
+            .get(position as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub new_tab: NewTabPage,
+    /// The address new tabs open when `new_tab` is [`NewTabPage::Custom`].
+    pub custom_uri: String,
+}
+
+impl Settings {
+    fn path() -> PathBuf {
+        xdg::app_config_dir().join("settings.json")
+    }
+
+    /// Reads the saved preferences; missing ones take their defaults.
+    pub fn load() -> Result<Settings, Error> {
+        store::load_json(&Self::path())
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        store::save_json(&Self::path(), self)
+    }
+
+    /// The page a new tab opens.
+    pub fn new_tab_uri(&self) -> String {
+        match self.new_tab {
+            NewTabPage::Home => home::URI.to_string(),
+            NewTabPage::Blank => "about:blank".to_string(),
+            NewTabPage::Custom if self.custom_uri.trim().is_empty() => home::URI.to_string(),
+            NewTabPage::Custom => self.custom_uri.trim().to_string(),
+        }
+    }
+}
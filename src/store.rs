@@ -0,0 +1,95 @@
+//! Reading and writing the files that bookmarks, history, preferences, the session and the
+//! search index are kept in.
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    Bincode(PathBuf, bincode::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Could not access {}: {}", path.display(), e),
+            Error::Json(path, e) => write!(f, "Invalid JSON in {}: {}", path.display(), e),
+            Error::Bincode(path, e) => write!(f, "Could not encode {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reads a file, or `None` if it doesn't exist yet.
+pub fn read(path: &Path) -> Result<Option<String>, Error> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::Io(path.to_path_buf(), e)),
+    }
+}
+
+/// Writes a file, creating its directory if needed.
+///
+/// The contents go to a temporary file that is only swapped in once it is safely on disk, so
+/// a failed write or a crash can't lose what the file held before.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    let io_error = |e| Error::Io(path.to_path_buf(), e);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+
+    let partial = path.with_extension("partial");
+    let write_partial = || {
+        let mut file = fs::File::create(&partial)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    };
+    write_partial().map_err(|e| Error::Io(partial.clone(), e))?;
+
+    fs::rename(&partial, path).map_err(io_error)
+}
+
+/// Adds to the end of a file, creating it and its directory if needed.
+pub fn append(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    let io_error = |e| Error::Io(path.to_path_buf(), e);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_ref()))
+        .map_err(io_error)
+}
+
+/// Reads a JSON file; a file that doesn't exist yet reads as the default value.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Error> {
+    match read(path)? {
+        Some(text) => serde_json::from_str(&text).map_err(|e| Error::Json(path.to_path_buf(), e)),
+        None => Ok(T::default()),
+    }
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let text =
+        serde_json::to_string_pretty(value).map_err(|e| Error::Json(path.to_path_buf(), e))?;
+    write(path, text)
+}
+
+pub fn save_bincode<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let bytes = bincode::serialize(value).map_err(|e| Error::Bincode(path.to_path_buf(), e))?;
+    write(path, bytes)
+}
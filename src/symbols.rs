@@ -0,0 +1,187 @@
+//! A searchable index of API symbols from gi-docgen projects and Devhelp books.
+
+use std::collections::HashSet;
+
+use crate::{devhelp, gidocgen};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Class,
+    Interface,
+    Record,
+    Union,
+    Enum,
+    Flags,
+    Error,
+    Alias,
+    Callback,
+    Constructor,
+    Method,
+    ClassMethod,
+    VirtualMethod,
+    TypeFunction,
+    Function,
+    Macro,
+    Property,
+    Signal,
+    Constant,
+    Other,
+}
+
+impl SymbolKind {
+    /// The kind of a Devhelp keyword, from its `type` attribute.
+    fn from_devhelp(kind: &str) -> Self {
+        match kind {
+            "function" => SymbolKind::Function,
+            "struct" => SymbolKind::Record,
+            "union" => SymbolKind::Union,
+            "enum" => SymbolKind::Enum,
+            "typedef" => SymbolKind::Alias,
+            "macro" => SymbolKind::Macro,
+            "property" => SymbolKind::Property,
+            "signal" => SymbolKind::Signal,
+            "constant" | "variable" => SymbolKind::Constant,
+            _ => SymbolKind::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SymbolKind::Class => "class",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Record => "struct",
+            SymbolKind::Union => "union",
+            SymbolKind::Enum => "enumeration",
+            SymbolKind::Flags => "flags",
+            SymbolKind::Error => "error domain",
+            SymbolKind::Alias => "alias",
+            SymbolKind::Callback => "callback",
+            SymbolKind::Constructor => "constructor",
+            SymbolKind::Method => "method",
+            SymbolKind::ClassMethod => "class method",
+            SymbolKind::VirtualMethod => "virtual method",
+            SymbolKind::TypeFunction => "type function",
+            SymbolKind::Function => "function",
+            SymbolKind::Macro => "macro",
+            SymbolKind::Property => "property",
+            SymbolKind::Signal => "signal",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Other => "page",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// The qualified name, like `Gtk.Widget.show` or `gtk_widget_show`.
+    pub name: String,
+    /// The C identifier, when the name is not one already.
+    pub ident: Option<String>,
+    pub kind: SymbolKind,
+    pub summary: String,
+    pub uri: String,
+    pub deprecated: bool,
+}
+
+impl Symbol {
+    pub fn new(name: &str, kind: SymbolKind, summary: &str, uri: String) -> Self {
+        Symbol {
+            name: name.to_string(),
+            ident: None,
+            kind,
+            summary: summary.to_string(),
+            uri,
+            deprecated: false,
+        }
+    }
+
+    fn from_keyword(keyword: &devhelp::Keyword) -> Self {
+        // Function keywords are written like `gtk_widget_show ()`.
+        let name = keyword.name.trim_end_matches("()").trim_end();
+
+        let mut symbol = Symbol::new(
+            name,
+            SymbolKind::from_devhelp(&keyword.kind),
+            "",
+            keyword.uri.clone(),
+        );
+        symbol.deprecated = keyword.deprecated;
+        symbol
+    }
+
+    /// The name without its namespace and owner, like `show` for `Gtk.Widget.show`.
+    fn short_name(&self) -> &str {
+        self.name.rsplit(['.', ':']).next().unwrap_or(&self.name)
+    }
+
+    /// How well the symbol matches a lowercase query, best first, or `None` if it doesn't.
+    fn rank(&self, query: &str) -> Option<u8> {
+        let names = [Some(self.name.as_str()), self.ident.as_deref()];
+        let names = names.iter().flatten().map(|name| name.to_lowercase());
+
+        let short_name = self.short_name().to_lowercase();
+
+        names
+            .filter_map(|name| {
+                if name == query {
+                    Some(0)
+                } else if short_name == query {
+                    Some(1)
+                } else if name.starts_with(query) || short_name.starts_with(query) {
+                    Some(2)
+                } else if name.contains(query) {
+                    Some(3)
+                } else {
+                    None
+                }
+            })
+            .min()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Index {
+    symbols: Vec<Symbol>,
+}
+
+impl Index {
+    /// Collects the symbols of every project and book.
+    ///
+    /// gi-docgen output usually ships a Devhelp index too, so book keywords pointing at a page
+    /// already in the index are left out.
+    pub fn build(projects: Vec<gidocgen::Project>, books: &[devhelp::Book]) -> Self {
+        let mut symbols: Vec<Symbol> = projects.into_iter().flat_map(|p| p.symbols).collect();
+
+        let mut uris: HashSet<String> = symbols.iter().map(|s| s.uri.clone()).collect();
+
+        for keyword in books.iter().flat_map(|book| &book.keywords) {
+            if uris.insert(keyword.uri.clone()) {
+                symbols.push(Symbol::from_keyword(keyword));
+            }
+        }
+
+        Index { symbols }
+    }
+
+    /// Symbols whose name contains `query`, ignoring case, best matches first.
+    ///
+    /// Exact names rank first, then prefixes, then other matches; shorter names win ties.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Symbol> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(u8, &Symbol)> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| Some((symbol.rank(&query)?, symbol)))
+            .collect();
+
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            (a_rank, a.name.len(), &a.name).cmp(&(b_rank, b.name.len(), &b.name))
+        });
+
+        matches.into_iter().take(limit).map(|(_, s)| s).collect()
+    }
+}
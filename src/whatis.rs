@@ -0,0 +1,197 @@
+//! apropos(1)-style search over the names and NAME descriptions of man pages.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::LazyLock,
+};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    decompress,
+    man::{self, ManPageRef},
+};
+
+/// A line of a plain-text `whatis` database, like `ls (1) - list directory contents`.
+///
+/// Some generators list several names for one page, separated by commas.
+static WHATIS_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<names>.+?)\s*\((?P<section>[^)\s]+)\)\s+-+\s+(?P<description>.*)$").unwrap()
+});
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub page: ManPageRef,
+    pub description: String,
+}
+
+impl Entry {
+    fn section(&self) -> &str {
+        self.page.section.as_deref().unwrap_or_default()
+    }
+}
+
+/// What to look for in names and descriptions.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Lowercase text to find anywhere.
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// A case-insensitive pattern, parsed as a regular expression if `regex` is set.
+    pub fn new(query: &str, regex: bool) -> Result<Pattern, regex::Error> {
+        if regex {
+            let regex = RegexBuilder::new(query).case_insensitive(true).build()?;
+            Ok(Pattern::Regex(regex))
+        } else {
+            Ok(Pattern::Substring(query.trim().to_lowercase()))
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Pattern::Substring(query) => text.to_lowercase().contains(query),
+            Pattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// The matches for one section.
+#[derive(Debug, Clone)]
+pub struct SectionMatches<'a> {
+    pub section: &'a str,
+    pub entries: Vec<&'a Entry>,
+}
+
+/// The name and description of every page in the man path.
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    entries: Vec<Entry>,
+}
+
+impl Database {
+    /// Reads the `whatis` file of hierarchies that have one, and page sources in the rest.
+    ///
+    /// man-db keeps its own database in a binary format, so on most systems this
+    /// reads the NAME section of every page.
+    pub fn load() -> Database {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+
+        for root in man::man_path() {
+            let mut add = |entry: Entry| {
+                if seen.insert(entry.page.clone()) {
+                    entries.push(entry);
+                }
+            };
+
+            if let Ok(text) = decompress::read_to_string(&root.join("whatis")) {
+                parse_whatis(&text).into_iter().for_each(&mut add);
+                continue;
+            }
+
+            let mut pages = Vec::new();
+            man::walk_hierarchy(&root, |path, name, section| {
+                pages.push((path, ManPageRef::new(name, Some(section))));
+            });
+
+            for (path, page) in pages {
+                // Pages that are `.so` links get the description of their target, as in apropos(1).
+                if let Some(description) = man::description(&path) {
+                    add(Entry { page, description });
+                }
+            }
+        }
+
+        Database { entries }
+    }
+
+    /// Entries whose name or description matches, grouped by section in man(1)'s order.
+    pub fn search(&self, pattern: &Pattern) -> Vec<SectionMatches<'_>> {
+        let mut sections: BTreeMap<(usize, &str), Vec<&Entry>> = BTreeMap::new();
+
+        for entry in &self.entries {
+            if pattern.matches(&entry.page.name) || pattern.matches(&entry.description) {
+                sections
+                    .entry((man::section_rank(entry.section()), entry.section()))
+                    .or_default()
+                    .push(entry);
+            }
+        }
+
+        sections
+            .into_iter()
+            .map(|((_, section), mut entries)| {
+                entries.sort_by(|a, b| a.page.name.cmp(&b.page.name));
+                SectionMatches { section, entries }
+            })
+            .collect()
+    }
+}
+
+fn parse_whatis(text: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+
+    for line in text.lines() {
+        let Some(caps) = WHATIS_LINE.captures(line) else {
+            continue;
+        };
+
+        for name in caps["names"].split(',').map(str::trim) {
+            entries.push(Entry {
+                page: ManPageRef::new(name, Some(&caps["section"])),
+                description: caps["description"].trim().to_string(),
+            });
+        }
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> Vec<(String, String)> {
+        parse_whatis(text)
+            .into_iter()
+            .map(|entry| (entry.page.to_string(), entry.description))
+            .collect()
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(page, description)| (page.to_string(), description.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lines() {
+        let text = "ls (1) - list directory contents\n\
+                    printf(3p)  --  print formatted output  \n\
+                    not a whatis line\n";
+
+        assert_eq!(
+            parsed(text),
+            pairs(&[
+                ("ls(1)", "list directory contents"),
+                ("printf(3p)", "print formatted output"),
+            ])
+        );
+    }
+
+    #[test]
+    fn several_names() {
+        assert_eq!(
+            parsed("gzip, gunzip, zcat (1) - compress or expand files"),
+            pairs(&[
+                ("gzip(1)", "compress or expand files"),
+                ("gunzip(1)", "compress or expand files"),
+                ("zcat(1)", "compress or expand files"),
+            ])
+        );
+    }
+}
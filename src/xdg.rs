@@ -0,0 +1,68 @@
+//! XDG base directories, as described by the Base Directory Specification.
+
+use std::{env, path::PathBuf};
+
+/// The subdirectory of each base directory that holds this application's files.
+const APP_DIR: &str = "docviewer";
+
+fn home() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+/// An absolute path from `var`, or `default` under the home directory.
+fn home_dir(var: &str, default: &str) -> PathBuf {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| home().join(default))
+}
+
+pub fn data_home() -> PathBuf {
+    home_dir("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn cache_home() -> PathBuf {
+    home_dir("XDG_CACHE_HOME", ".cache")
+}
+
+pub fn config_home() -> PathBuf {
+    home_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn state_home() -> PathBuf {
+    home_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// Where to keep preferences.
+pub fn app_config_dir() -> PathBuf {
+    config_home().join(APP_DIR)
+}
+
+/// Where to keep files the user would miss, like bookmarks.
+pub fn app_data_dir() -> PathBuf {
+    data_home().join(APP_DIR)
+}
+
+/// Where to keep state that should outlive a restart, like open tabs.
+pub fn app_state_dir() -> PathBuf {
+    state_home().join(APP_DIR)
+}
+
+/// Where to keep files that can be rebuilt, like search indexes.
+pub fn app_cache_dir() -> PathBuf {
+    cache_home().join(APP_DIR)
+}
+
+/// `XDG_DATA_HOME` followed by `XDG_DATA_DIRS`, most important first.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let system = env::var_os("XDG_DATA_DIRS")
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    let mut dirs = vec![data_home()];
+    dirs.extend(env::split_paths(&system).filter(|p| p.is_absolute()));
+    dirs.dedup();
+    dirs
+}